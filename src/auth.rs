@@ -0,0 +1,152 @@
+use crate::message::Datagram;
+use hmac::{Hmac, KeyInit, Mac};
+use prost::Message;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Environment variable that can hold the swarm key instead of a key file
+pub const SWARM_KEY_ENV: &str = "CONCLAVE_SWARM_KEY";
+
+/// Minimum accepted swarm key length in bytes
+pub const MIN_SWARM_KEY_LENGTH: usize = 16;
+
+/// Pre-shared swarm key used to authenticate every datagram with HMAC-SHA256
+#[derive(Clone)]
+pub struct SwarmKey {
+    key: Vec<u8>,
+}
+
+impl std::fmt::Debug for SwarmKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print key material
+        f.debug_struct("SwarmKey")
+            .field("key", &"<redacted>")
+            .finish()
+    }
+}
+
+impl SwarmKey {
+    /// Create a swarm key from raw key material
+    pub fn new(key: &[u8]) -> Result<Self, String> {
+        if key.len() < MIN_SWARM_KEY_LENGTH {
+            return Err(format!(
+                "Swarm key is only {} bytes, expected at least {}",
+                key.len(),
+                MIN_SWARM_KEY_LENGTH
+            ));
+        }
+
+        Ok(Self { key: key.to_vec() })
+    }
+
+    /// Compute the MAC over the authenticated fields of a datagram
+    fn mac(&self, datagram: &Datagram) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+
+        // Length-prefix variable-size fields so field boundaries cannot be shifted
+        mac.update(&(datagram.message_id.len() as u64).to_be_bytes());
+        mac.update(datagram.message_id.as_bytes());
+        mac.update(&datagram.fragment_index.to_be_bytes());
+        mac.update(&datagram.fragment_count.to_be_bytes());
+        mac.update(&(datagram.payload.len() as u64).to_be_bytes());
+        mac.update(&datagram.payload);
+        mac.update(&(datagram.sender_id.len() as u64).to_be_bytes());
+        mac.update(datagram.sender_id.as_bytes());
+        mac.update(&datagram.epoch.to_be_bytes());
+        mac.update(&datagram.sequence.to_be_bytes());
+        if let Some(ref nack) = datagram.nack {
+            mac.update(&nack.encode_to_vec());
+        }
+        mac
+    }
+
+    /// Sign a datagram in place
+    pub fn sign(&self, datagram: &mut Datagram) {
+        datagram.mac = self.mac(datagram).finalize().into_bytes().to_vec();
+    }
+
+    /// Verify the MAC of a datagram in constant time
+    pub fn verify(&self, datagram: &Datagram) -> bool {
+        !datagram.mac.is_empty() && self.mac(datagram).verify_slice(&datagram.mac).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_datagram() -> Datagram {
+        Datagram {
+            message_id: "msg-1".to_string(),
+            fragment_index: 0,
+            fragment_count: 1,
+            payload: b"I am the judge".to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let key = SwarmKey::new(b"0123456789abcdef").unwrap();
+        let mut datagram = test_datagram();
+
+        key.sign(&mut datagram);
+        assert_eq!(datagram.mac.len(), 32);
+        assert!(key.verify(&datagram));
+    }
+
+    #[test]
+    fn test_tampered_datagram_is_rejected() {
+        let key = SwarmKey::new(b"0123456789abcdef").unwrap();
+        let mut datagram = test_datagram();
+        key.sign(&mut datagram);
+
+        datagram.payload = b"I am the judge too".to_vec();
+        assert!(!key.verify(&datagram));
+    }
+
+    #[test]
+    fn test_tampered_sequence_and_nack_are_rejected() {
+        let key = SwarmKey::new(b"0123456789abcdef").unwrap();
+        let mut datagram = test_datagram();
+        datagram.sequence = 7;
+        key.sign(&mut datagram);
+
+        let mut resequenced = datagram.clone();
+        resequenced.sequence = 8;
+        assert!(!key.verify(&resequenced));
+
+        let mut nack = datagram.clone();
+        nack.nack = Some(crate::message::Nack {
+            target_id: "agent-2".to_string(),
+            target_epoch: 1,
+            missing_sequences: vec![3],
+        });
+        assert!(!key.verify(&nack));
+    }
+
+    #[test]
+    fn test_wrong_key_and_missing_mac_are_rejected() {
+        let key = SwarmKey::new(b"0123456789abcdef").unwrap();
+        let other_key = SwarmKey::new(b"fedcba9876543210").unwrap();
+
+        let mut datagram = test_datagram();
+        assert!(!key.verify(&datagram));
+
+        other_key.sign(&mut datagram);
+        assert!(!key.verify(&datagram));
+    }
+
+    #[test]
+    fn test_short_key_is_rejected() {
+        assert!(SwarmKey::new(b"short").is_err());
+    }
+
+    #[test]
+    fn test_debug_redacts_key() {
+        let key = SwarmKey::new(b"super-secret-swarm-key").unwrap();
+        assert!(!format!("{:?}", key).contains("super-secret"));
+    }
+}
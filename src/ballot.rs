@@ -0,0 +1,327 @@
+use crate::message::{AgentMessage, StructuredMessage};
+use crate::transcript::{PromptMessage, PromptRole, push_prompt_message};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
+use thiserror::Error;
+use tracing::warn;
+
+/// Schema tag of ballots sent over the wire
+pub const BALLOT_SCHEMA: &str = "conclave.ballot.v1";
+
+/// How often the judge is asked for a ballot before giving up
+pub const BALLOT_ATTEMPTS: usize = 3;
+
+/// Lowest and highest speaker points on the usual Public Forum scale
+const POINTS_RANGE: std::ops::RangeInclusive<f64> = 25.0..=30.0;
+
+/// Errors raised while obtaining a ballot from the judge
+#[derive(Error, Debug)]
+pub enum BallotError {
+    #[error("Ballot is not valid JSON for the schema: {0}")]
+    Schema(#[from] serde_json::Error),
+
+    #[error("Ballot is incomplete: {0}")]
+    Incomplete(String),
+
+    #[error("Speaker points for '{speaker}' must be between 25 and 30, got {points}")]
+    PointsOutOfRange { speaker: String, points: f64 },
+
+    /// The LLM failed, after its retries, so asking in prose would fail too
+    #[error("Failed to ask the LLM for a ballot: {0}")]
+    Llm(anyhow::Error),
+
+    #[error("Failed to write ballot: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Side of the resolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Affirmative,
+    Negative,
+}
+
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Affirmative => write!(f, "affirmative"),
+            Side::Negative => write!(f, "negative"),
+        }
+    }
+}
+
+/// An argument that decided the round
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VotingIssue {
+    pub issue: String,
+    /// Side that won the argument
+    pub winner: Side,
+    /// Why that side won it
+    pub reason: String,
+}
+
+/// Points awarded to one debater, by agent id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpeakerPoints {
+    pub speaker: String,
+    pub points: f64,
+}
+
+/// A judge's decision in machine-readable form
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ballot {
+    pub winner: Side,
+    pub voting_issues: Vec<VotingIssue>,
+    pub speaker_points: Vec<SpeakerPoints>,
+    /// Reason for decision, with feedback for both sides
+    pub rfd: String,
+}
+
+impl Ballot {
+    /// Parse and validate a ballot from the judge's answer
+    ///
+    /// The answer may wrap the JSON object in prose or a code fence.
+    pub fn parse(answer: &str) -> Result<Self, BallotError> {
+        let json = match (answer.find('{'), answer.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &answer[start..=end],
+            _ => answer,
+        };
+        let ballot: Ballot = serde_json::from_str(json)?;
+        ballot.validate()?;
+        Ok(ballot)
+    }
+
+    fn validate(&self) -> Result<(), BallotError> {
+        if self.voting_issues.is_empty() {
+            return Err(BallotError::Incomplete(
+                "at least one voting issue is required".to_string(),
+            ));
+        }
+        if self
+            .voting_issues
+            .iter()
+            .any(|issue| issue.issue.trim().is_empty() || issue.reason.trim().is_empty())
+        {
+            return Err(BallotError::Incomplete(
+                "every voting issue needs an issue and a reason".to_string(),
+            ));
+        }
+        if self.speaker_points.is_empty() {
+            return Err(BallotError::Incomplete(
+                "speaker points are required for every debater".to_string(),
+            ));
+        }
+        for points in &self.speaker_points {
+            if points.speaker.trim().is_empty() {
+                return Err(BallotError::Incomplete(
+                    "speaker points need the debater's agent id".to_string(),
+                ));
+            }
+            if !POINTS_RANGE.contains(&points.points) {
+                return Err(BallotError::PointsOutOfRange {
+                    speaker: points.speaker.clone(),
+                    points: points.points,
+                });
+            }
+        }
+        if self.rfd.trim().is_empty() {
+            return Err(BallotError::Incomplete("the RFD is empty".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Write the ballot as pretty-printed JSON
+    pub fn write_to(&self, path: &Path) -> Result<(), BallotError> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    /// The ballot as a typed message from `sender_id`
+    pub fn to_message(&self, sender_id: String) -> Result<AgentMessage, BallotError> {
+        Ok(AgentMessage::new_structured(
+            sender_id,
+            StructuredMessage {
+                schema: BALLOT_SCHEMA.to_string(),
+                json: serde_json::to_string(self)?,
+            },
+        ))
+    }
+}
+
+/// The ballot as the judge would read it out
+impl std::fmt::Display for Ballot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Decision: {}", self.winner)?;
+        writeln!(f, "Voting issues:")?;
+        for (number, issue) in self.voting_issues.iter().enumerate() {
+            writeln!(
+                f,
+                "{}. {} ({}): {}",
+                number + 1,
+                issue.issue,
+                issue.winner,
+                issue.reason
+            )?;
+        }
+        let points: Vec<String> = self
+            .speaker_points
+            .iter()
+            .map(|points| format!("{} {}", points.speaker, points.points))
+            .collect();
+        writeln!(f, "Speaker points: {}", points.join(", "))?;
+        write!(f, "\n{}", self.rfd)
+    }
+}
+
+/// Instructions asking the judge for its decision as a ballot
+pub fn ballot_request() -> String {
+    "Give your decision as a JSON ballot and nothing else, following this example exactly:\n\
+     {\"winner\": \"affirmative\", \
+     \"voting_issues\": [{\"issue\": \"Privacy\", \"winner\": \"negative\", \"reason\": \"...\"}], \
+     \"speaker_points\": [{\"speaker\": \"agent-id\", \"points\": 28.5}], \
+     \"rfd\": \"...\"}\n\
+     winner is \"affirmative\" or \"negative\". List the 2-4 voting issues that decided the round. \
+     Give every debater, by agent id, speaker points between 25 and 30. \
+     Put your full reason for decision and feedback for both sides in rfd."
+        .to_string()
+}
+
+/// Ask for a ballot with `ask`, feeding schema violations back until one is valid
+///
+/// `prompt` is the conversation so far; `ask` sends a prompt to the LLM.
+pub async fn request_ballot<F, Fut>(
+    mut prompt: Vec<PromptMessage>,
+    mut ask: F,
+) -> Result<Ballot, BallotError>
+where
+    F: FnMut(Vec<PromptMessage>) -> Fut,
+    Fut: Future<Output = anyhow::Result<String>>,
+{
+    push_prompt_message(&mut prompt, PromptRole::User, &ballot_request());
+
+    let mut attempt = 1;
+    loop {
+        let answer = ask(prompt.clone()).await.map_err(BallotError::Llm)?;
+        match Ballot::parse(&answer) {
+            Ok(ballot) => return Ok(ballot),
+            Err(e) if attempt < BALLOT_ATTEMPTS => {
+                warn!("Rejected ballot (attempt {}): {}", attempt, e);
+                push_prompt_message(&mut prompt, PromptRole::Assistant, &answer);
+                push_prompt_message(
+                    &mut prompt,
+                    PromptRole::User,
+                    &format!(
+                        "That ballot was rejected: {}. Reply with the corrected JSON ballot only.",
+                        e
+                    ),
+                );
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::MessageKind;
+
+    const VALID: &str = r#"{
+        "winner": "negative",
+        "voting_issues": [{"issue": "Privacy", "winner": "negative", "reason": "Dropped in summary."}],
+        "speaker_points": [{"speaker": "aff-1", "points": 28}, {"speaker": "neg-1", "points": 29.5}],
+        "rfd": "The negative won privacy and weighed it."
+    }"#;
+
+    #[test]
+    fn test_parse_valid_ballot_in_prose() {
+        let ballot =
+            Ballot::parse(&format!("Here is my ballot:\n```json\n{}\n```", VALID)).unwrap();
+        assert_eq!(ballot.winner, Side::Negative);
+        assert_eq!(ballot.speaker_points[1].points, 29.5);
+        assert!(ballot.to_string().starts_with(
+            "Decision: negative\nVoting issues:\n1. Privacy (negative): Dropped in summary.\n"
+        ));
+
+        let message = ballot.to_message("judge-1".to_string()).unwrap();
+        match message.kind {
+            Some(MessageKind::Structured(ref structured)) => {
+                assert_eq!(structured.schema, BALLOT_SCHEMA);
+                assert_eq!(Ballot::parse(&structured.json).unwrap(), ballot);
+            }
+            other => panic!("Expected a structured message, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_schema_violations_are_rejected() {
+        assert!(matches!(
+            Ballot::parse("The affirmative wins."),
+            Err(BallotError::Schema(_))
+        ));
+        assert!(matches!(
+            Ballot::parse(&VALID.replace("\"negative\",\n", "\"pro\",\n")),
+            Err(BallotError::Schema(_))
+        ));
+        assert!(matches!(
+            Ballot::parse(&VALID.replace("29.5", "31")),
+            Err(BallotError::PointsOutOfRange { .. })
+        ));
+        assert!(matches!(
+            Ballot::parse(&VALID.replace("The negative won privacy and weighed it.", " ")),
+            Err(BallotError::Incomplete(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request_retries_until_the_ballot_is_valid() {
+        let mut answers = vec!["Negative wins.".to_string(), VALID.to_string()].into_iter();
+        let mut prompts = Vec::new();
+        let ballot = request_ballot(
+            vec![PromptMessage {
+                role: PromptRole::User,
+                content: "Participants: aff-1, neg-1.".to_string(),
+            }],
+            |prompt| {
+                prompts.push(prompt);
+                let answer = answers.next().unwrap();
+                async move { Ok(answer) }
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(ballot.winner, Side::Negative);
+
+        // The rejected answer and the reason are fed back for the second attempt
+        assert_eq!(prompts.len(), 2);
+        let retry = &prompts[1];
+        assert_eq!(retry[1].content, "Negative wins.");
+        assert!(retry[2].content.starts_with("That ballot was rejected"));
+
+        let mut attempts = 0;
+        let result = request_ballot(Vec::new(), |_| {
+            attempts += 1;
+            async { Ok("No ballot.".to_string()) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, BALLOT_ATTEMPTS);
+
+        // An LLM failure ends the request at once
+        let mut attempts = 0;
+        let result = request_ballot(Vec::new(), |_| {
+            attempts += 1;
+            async { Err(anyhow::anyhow!("connection reset")) }
+        })
+        .await;
+        assert!(matches!(result, Err(BallotError::Llm(_))));
+        assert_eq!(attempts, 1);
+    }
+}
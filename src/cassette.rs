@@ -0,0 +1,502 @@
+use crate::identity::to_hex;
+use crate::transcript::without_preamble;
+use async_trait::async_trait;
+use llm::{
+    LLMProvider, ToolCall,
+    chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, MessageType, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::ModelsProvider,
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+use tracing::{debug, error};
+
+/// Schema tag of cassette files
+pub const CASSETTE_SCHEMA: &str = "conclave.cassette.v1";
+
+/// Errors raised while loading or writing a cassette
+#[derive(Error, Debug)]
+pub enum CassetteError {
+    #[error("Failed to access cassette: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid cassette: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Unsupported cassette schema '{0}'")]
+    Schema(String),
+}
+
+/// A chat message as stored in a cassette
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecordedMessage {
+    role: String,
+    content: String,
+    /// Tool calls made, or their results, for tool messages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ToolCall>,
+}
+
+impl From<&ChatMessage> for RecordedMessage {
+    fn from(message: &ChatMessage) -> Self {
+        let role = match message.role {
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+        };
+        let tool_calls = match message.message_type {
+            MessageType::ToolUse(ref calls) | MessageType::ToolResult(ref calls) => calls.clone(),
+            _ => Vec::new(),
+        };
+        Self {
+            role: role.to_string(),
+            content: message.content.clone(),
+            tool_calls,
+        }
+    }
+}
+
+/// A chat request as stored in a cassette
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecordedRequest {
+    /// SHA-256 of the system prompt, so a changed personality does not match
+    system_hash: String,
+    messages: Vec<RecordedMessage>,
+}
+
+impl RecordedRequest {
+    /// The messages a replayed request must match
+    ///
+    /// The preamble of the opening message is left out, since who was present
+    /// when depends on heartbeat timing and differs between runs.
+    fn conversation(&self) -> Vec<RecordedMessage> {
+        let mut messages = self.messages.clone();
+        if let Some(opening) = messages.first_mut()
+            && opening.role == "user"
+        {
+            opening.content = without_preamble(&opening.content).to_string();
+        }
+        messages
+    }
+
+    /// Whether a request replays the recorded `self`
+    fn matches(&self, request: &RecordedRequest) -> bool {
+        self.system_hash == request.system_hash && self.conversation() == request.conversation()
+    }
+}
+
+/// A chat response as stored in a cassette
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecordedResponse {
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ToolCall>,
+}
+
+impl RecordedResponse {
+    fn from_response(response: &dyn ChatResponse) -> Self {
+        Self {
+            text: response.text(),
+            tool_calls: response.tool_calls().unwrap_or_default(),
+        }
+    }
+}
+
+impl ChatResponse for RecordedResponse {
+    fn text(&self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        if self.tool_calls.is_empty() {
+            None
+        } else {
+            Some(self.tool_calls.clone())
+        }
+    }
+}
+
+impl std::fmt::Display for RecordedResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.text, self.tool_calls.is_empty()) {
+            (Some(text), _) => write!(f, "{}", text),
+            (None, false) => {
+                for call in &self.tool_calls {
+                    writeln!(f, "{}", call)?;
+                }
+                Ok(())
+            }
+            (None, true) => Ok(()),
+        }
+    }
+}
+
+/// One request and the response it got
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+/// A cassette as written to disk
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CassetteFile {
+    schema: String,
+    interactions: Vec<Interaction>,
+}
+
+impl CassetteFile {
+    fn load(path: &Path) -> Result<Vec<Interaction>, CassetteError> {
+        let file: CassetteFile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if file.schema != CASSETTE_SCHEMA {
+            return Err(CassetteError::Schema(file.schema));
+        }
+        Ok(file.interactions)
+    }
+
+    /// Write the cassette next to `path` and move it into place, so a write
+    /// cut short by Ctrl-C or a crash leaves the previous cassette intact
+    fn save(path: &Path, interactions: &[Interaction]) -> Result<(), CassetteError> {
+        let file = CassetteFile {
+            schema: CASSETTE_SCHEMA.to_string(),
+            interactions: interactions.to_vec(),
+        };
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        std::fs::write(&partial, serde_json::to_string_pretty(&file)? + "\n")?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+/// Whether a cassette is being written or played back
+enum Tape {
+    /// Pass requests to the real provider and write down what it answers
+    Record {
+        inner: Box<dyn LLMProvider>,
+        path: PathBuf,
+        /// Held while the cassette is written, so writes land in order
+        interactions: tokio::sync::Mutex<Vec<Interaction>>,
+    },
+    /// Answer from the cassette, without any provider
+    Replay {
+        /// Interactions not yet played back, in recorded order
+        interactions: Mutex<Vec<Interaction>>,
+    },
+}
+
+/// An LLM provider that records chat calls to a cassette, or replays them
+///
+/// While recording, every chat request (the messages and a hash of the system
+/// prompt) and its response are added to the cassette file. While replaying,
+/// each request is answered with the first unplayed recording of the same
+/// request, ignoring the preamble of who is present; a request that was never
+/// recorded is an error, so a replayed run that drifts from the recording fails
+/// instead of going off-script.
+pub struct CassetteProvider {
+    system_hash: String,
+    tape: Tape,
+}
+
+impl CassetteProvider {
+    /// Record the chats of `inner` to a new cassette at `path`
+    pub fn record(
+        inner: Box<dyn LLMProvider>,
+        path: &Path,
+        system_prompt: &str,
+    ) -> Result<Self, CassetteError> {
+        CassetteFile::save(path, &[])?;
+        Ok(Self {
+            system_hash: system_hash(system_prompt),
+            tape: Tape::Record {
+                inner,
+                path: path.to_path_buf(),
+                interactions: tokio::sync::Mutex::new(Vec::new()),
+            },
+        })
+    }
+
+    /// Replay the chats recorded in the cassette at `path`
+    pub fn replay(path: &Path, system_prompt: &str) -> Result<Self, CassetteError> {
+        Ok(Self {
+            system_hash: system_hash(system_prompt),
+            tape: Tape::Replay {
+                interactions: Mutex::new(CassetteFile::load(path)?),
+            },
+        })
+    }
+
+    /// The real provider, for calls that are not recorded
+    fn inner(&self, what: &str) -> Result<&dyn LLMProvider, LLMError> {
+        match self.tape {
+            Tape::Record { ref inner, .. } => Ok(inner.as_ref()),
+            Tape::Replay { .. } => Err(LLMError::InvalidRequest(format!(
+                "Cassettes only replay chats, not {}",
+                what
+            ))),
+        }
+    }
+}
+
+/// Hex SHA-256 of a system prompt
+fn system_hash(system_prompt: &str) -> String {
+    to_hex(&Sha256::digest(system_prompt.as_bytes()))
+}
+
+#[async_trait]
+impl ChatProvider for CassetteProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let request = RecordedRequest {
+            system_hash: self.system_hash.clone(),
+            messages: messages.iter().map(RecordedMessage::from).collect(),
+        };
+
+        match self.tape {
+            Tape::Record {
+                ref inner,
+                ref path,
+                ref interactions,
+            } => {
+                let response = inner.chat_with_tools(messages, tools).await?;
+                // Answer with the recording itself, so replay behaves the same
+                let response = RecordedResponse::from_response(response.as_ref());
+                let mut interactions = interactions.lock().await;
+                interactions.push(Interaction {
+                    request,
+                    response: response.clone(),
+                });
+                let (path, recorded) = (path.clone(), interactions.clone());
+                tokio::task::spawn_blocking(move || CassetteFile::save(&path, &recorded))
+                    .await
+                    .map_err(|e| LLMError::ProviderError(e.to_string()))?
+                    .map_err(|e| LLMError::ProviderError(e.to_string()))?;
+                debug!("Recorded interaction {} to cassette", interactions.len());
+                Ok(Box::new(response))
+            }
+            Tape::Replay { ref interactions } => {
+                let mut interactions = interactions.lock().expect("cassette lock poisoned");
+                match interactions
+                    .iter()
+                    .position(|interaction| interaction.request.matches(&request))
+                {
+                    Some(index) => Ok(Box::new(interactions.remove(index).response)),
+                    None => {
+                        let reason = if interactions.iter().any(|interaction| {
+                            interaction.request.conversation() == request.conversation()
+                        }) {
+                            "it was recorded with a different system prompt"
+                        } else {
+                            "it was never recorded, or was already played back"
+                        };
+                        let last = request
+                            .messages
+                            .last()
+                            .map(|message| message.content.as_str())
+                            .unwrap_or_default();
+                        error!(
+                            "Cassette has no response for a request of {} messages ending in {:?}: {}",
+                            request.messages.len(),
+                            last,
+                            reason
+                        );
+                        // Asking again cannot help, so the miss is not retried
+                        Err(LLMError::InvalidRequest(format!(
+                            "Cassette has no response for this request: {}",
+                            reason
+                        )))
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for CassetteProvider {
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.inner("completions")?.complete(request).await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for CassetteProvider {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.inner("embeddings")?.embed(input).await
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for CassetteProvider {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        self.inner("transcriptions")?.transcribe(audio).await
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for CassetteProvider {}
+
+#[async_trait]
+impl ModelsProvider for CassetteProvider {}
+
+impl LLMProvider for CassetteProvider {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockProvider;
+    use crate::retry::{ErrorClass, RetryPolicy};
+    use crate::transcript::CONVERSATION_HEADING;
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    #[tokio::test]
+    async fn test_replay_serves_recorded_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("debate.json");
+
+        let recorder = CassetteProvider::record(
+            Box::new(MockProvider::echo("aff-1")),
+            &path,
+            "You argue the affirmative.",
+        )
+        .unwrap();
+        let first = [user("Constructive: go.")];
+        let second = [user("Rebuttal: go.")];
+        assert_eq!(
+            recorder.chat(&first).await.unwrap().to_string(),
+            "aff-1 heard: Constructive: go."
+        );
+        recorder.chat(&second).await.unwrap();
+
+        // The cassette is moved into place, leaving no partial file behind
+        let files: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+
+        // Replay answers without the provider, in any order
+        let player = CassetteProvider::replay(&path, "You argue the affirmative.").unwrap();
+        assert_eq!(
+            player.chat(&second).await.unwrap().to_string(),
+            "aff-1 heard: Rebuttal: go."
+        );
+        assert_eq!(
+            player.chat(&first).await.unwrap().to_string(),
+            "aff-1 heard: Constructive: go."
+        );
+
+        // Each recording plays back once, and unknown requests fail
+        assert!(player.chat(&first).await.is_err());
+        assert!(player.chat(&[user("Summary: go.")]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_requires_the_recorded_system_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("debate.json");
+
+        let recorder =
+            CassetteProvider::record(Box::new(MockProvider::echo("neg-1")), &path, "Negative.")
+                .unwrap();
+        let request = [user("Constructive: go.")];
+        recorder.chat(&request).await.unwrap();
+
+        let player = CassetteProvider::replay(&path, "Affirmative.").unwrap();
+        let error = player.chat(&request).await.unwrap_err();
+        assert!(error.to_string().contains("different system prompt"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_ignores_who_was_present() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("debate.json");
+
+        let recorder =
+            CassetteProvider::record(Box::new(MockProvider::echo("neg-1")), &path, "Negative.")
+                .unwrap();
+        let opening = |preamble: &str| {
+            user(&format!(
+                "{}\n\n{}\n\n[aff-1]: Go.",
+                preamble, CONVERSATION_HEADING
+            ))
+        };
+        recorder
+            .chat(&[opening(
+                "Participants in the conclave: aff-1.\naff-1 joined the conclave.",
+            )])
+            .await
+            .unwrap();
+
+        let player = CassetteProvider::replay(&path, "Negative.").unwrap();
+        assert!(player.chat(&[user("[aff-1]: Go.")]).await.is_err());
+        let replayed = player
+            .chat(&[opening(
+                "No other participants are currently present in the conclave.",
+            )])
+            .await
+            .unwrap();
+        assert!(
+            replayed
+                .to_string()
+                .starts_with("neg-1 heard: Participants")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_misses_are_not_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("debate.json");
+        CassetteFile::save(&path, &[]).unwrap();
+        let player = CassetteProvider::replay(&path, "Judge.").unwrap();
+
+        let mut attempts = 0;
+        let result = RetryPolicy::default()
+            .run(|| {
+                attempts += 1;
+                let player = &player;
+                async move { Ok(player.chat(&[user("RFD?")]).await?) }
+            })
+            .await;
+        assert_eq!(attempts, 1);
+        assert_eq!(ErrorClass::of(&result.unwrap_err()), ErrorClass::Fatal);
+    }
+
+    #[test]
+    fn test_invalid_cassettes_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.json");
+
+        std::fs::write(&path, r#"{"schema": "other.v1", "interactions": []}"#).unwrap();
+        assert!(matches!(
+            CassetteProvider::replay(&path, ""),
+            Err(CassetteError::Schema(_))
+        ));
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            CassetteProvider::replay(&path, ""),
+            Err(CassetteError::Json(_))
+        ));
+
+        assert!(matches!(
+            CassetteProvider::replay(&dir.path().join("missing.json"), ""),
+            Err(CassetteError::Io(_))
+        ));
+    }
+}
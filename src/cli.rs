@@ -1,8 +1,21 @@
+use crate::auth::{SWARM_KEY_ENV, SwarmKey};
+use crate::crypto::{MIN_PASSPHRASE_LENGTH, SWARM_PASSPHRASE_ENV};
+use crate::health::ErrorReply;
+use crate::message::Heartbeat;
+use crate::moderator::MODERATOR;
+use crate::policy::{ResponseMode, ResponsePolicy};
+use crate::reliability::DeliveryMode;
+use crate::retry::RetryPolicy;
+use crate::session::derive_multicast_address;
+use crate::tools::{CalculatorTool, ReadFileTool, SearchTool, ToolKind, ToolRegistry};
+use crate::transcript::TranscriptLimits;
+use crate::transport::TransportKind;
 use anyhow::{Result, anyhow};
 use clap::{Parser, ValueEnum};
 use std::fs;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Supported LLM backend types
 #[derive(Debug, Clone, ValueEnum)]
@@ -24,6 +37,10 @@ pub enum LLMBackend {
     /// Local models via Ollama
     #[value(name = "local")]
     Local,
+
+    /// Scripted answers without any network access, for tests and offline demos
+    #[value(name = "mock")]
+    Mock,
 }
 
 impl std::fmt::Display for LLMBackend {
@@ -34,10 +51,26 @@ impl std::fmt::Display for LLMBackend {
             LLMBackend::Google => write!(f, "google"),
             LLMBackend::OpenRouter => write!(f, "openrouter"),
             LLMBackend::Local => write!(f, "local"),
+            LLMBackend::Mock => write!(f, "mock"),
         }
     }
 }
 
+/// What this process does in the swarm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum RunMode {
+    /// Take part in the conversation through an LLM
+    #[default]
+    #[value(name = "agent")]
+    Agent,
+    /// Run a Public Forum debate between the other agents, without an LLM
+    #[value(name = "moderator")]
+    Moderator,
+    /// Take part as the debate judge, deciding with structured ballots
+    #[value(name = "judge")]
+    Judge,
+}
+
 /// Command-line arguments for the AI Agent Swarm
 #[derive(Parser, Debug)]
 #[command(
@@ -60,19 +93,29 @@ pub struct AgentArgs {
     #[arg(
         short = 'a',
         long = "multicast-address",
-        help = "UDP multicast address for agent communication",
+        help = "UDP multicast address for agent communication (IPv4 or IPv6, e.g. '[ff05::1234]:8080')",
         default_value = "239.255.255.250:8080",
         value_name = "ADDRESS:PORT"
     )]
     pub multicast_address: SocketAddr,
 
-    /// Network interface to bind to (optional)
+    /// Network interfaces to join the multicast group on (optional, repeatable)
     #[arg(
         long = "interface",
-        help = "Network interface to bind to (e.g., 'eth0', '192.168.1.100')",
-        value_name = "INTERFACE"
+        help = "Network interface to join on by name, address or IPv6 index (e.g., 'eth0', '192.168.1.100'); repeat or comma-separate for several",
+        value_name = "INTERFACE",
+        value_delimiter = ','
+    )]
+    pub interfaces: Vec<String>,
+
+    /// Multicast TTL (IPv4) or hop limit (IPv6)
+    #[arg(
+        long = "multicast-hops",
+        help = "Multicast TTL (IPv4) or hop limit (IPv6) for outgoing messages",
+        default_value = "1",
+        value_name = "HOPS"
     )]
-    pub interface: Option<String>,
+    pub multicast_hops: u32,
 
     /// LLM backend type to use
     #[arg(
@@ -129,6 +172,58 @@ pub struct AgentArgs {
     )]
     pub max_retries: u32,
 
+    /// Wait before the first retry, in milliseconds
+    #[arg(
+        long = "retry-base-delay",
+        help = "Wait before the first retry of a failed LLM request in milliseconds, doubled for each retry after it",
+        default_value = "500",
+        value_name = "MILLISECONDS"
+    )]
+    pub retry_base_delay_ms: u64,
+
+    /// Longest wait between retries, in seconds
+    #[arg(
+        long = "retry-max-delay",
+        help = "Longest wait between retries of a failed LLM request in seconds, even when the provider asks for longer",
+        default_value = "30",
+        value_name = "SECONDS"
+    )]
+    pub retry_max_delay_seconds: u64,
+
+    /// Retry at exactly the backoff delay instead of a random point before it
+    #[arg(
+        long = "no-retry-jitter",
+        help = "Wait exactly the backoff delay between retries instead of a random fraction of it"
+    )]
+    pub no_retry_jitter: bool,
+
+    /// What to say when the LLM fails to answer
+    #[arg(
+        long = "on-llm-error",
+        help = "What to say when the LLM fails to answer: 'silent', 'fallback' (a polite stand-in reply) or 'announce' (that this agent is unavailable); peers always get a status message",
+        value_enum,
+        default_value_t = ErrorReply::Silent
+    )]
+    pub error_reply: ErrorReply,
+
+    /// Failures in a row before the agent is degraded
+    #[arg(
+        long = "degraded-after",
+        help = "Failed LLM requests in a row before the agent is degraded and stops asking its LLM for a while",
+        default_value = "3",
+        value_name = "COUNT"
+    )]
+    pub degraded_after: u32,
+
+    /// How long a degraded agent leaves its LLM alone
+    #[arg(
+        long = "degraded-cooldown",
+        help = "Seconds a degraded agent waits before asking its LLM again",
+        default_value = "60",
+        value_name = "SECONDS"
+    )]
+    pub degraded_cooldown_seconds: u64,
+
     /// Log level filter
     #[arg(
         long = "log-level",
@@ -172,6 +267,263 @@ pub struct AgentArgs {
         help = "true | false on whether to have speech or not",
     )]
     pub voice: bool,
+
+    /// Read the pre-shared swarm key from file (or set CONCLAVE_SWARM_KEY)
+    #[arg(
+        long = "swarm-key-file",
+        help = "Read the pre-shared swarm key used to sign messages from file (or set CONCLAVE_SWARM_KEY env var)",
+        value_name = "FILE_PATH"
+    )]
+    pub swarm_key_file: Option<PathBuf>,
+
+    /// Directory for this agent's Ed25519 keypair and trusted peer keys
+    #[arg(
+        long = "identity-dir",
+        help = "Directory holding this agent's Ed25519 keypair (created on first run) and trusted peer keys; enables signed identities",
+        value_name = "DIR"
+    )]
+    pub identity_dir: Option<PathBuf>,
+
+    /// Read the swarm encryption passphrase from file (or set CONCLAVE_SWARM_PASSPHRASE)
+    #[arg(
+        long = "swarm-passphrase-file",
+        help = "Read the swarm passphrase used to encrypt all traffic from file (or set CONCLAVE_SWARM_PASSPHRASE env var)",
+        value_name = "FILE_PATH"
+    )]
+    pub swarm_passphrase_file: Option<PathBuf>,
+
+    /// Replay protection window in seconds
+    #[arg(
+        long = "replay-window",
+        help = "Reject messages whose timestamp is more than this many seconds from local time, or already seen within it (0 disables)",
+        default_value = "30",
+        value_name = "SECONDS"
+    )]
+    pub replay_window_seconds: u64,
+
+    /// Delivery mode for swarm traffic
+    #[arg(
+        long = "delivery",
+        help = "How messages are delivered: 'best-effort' sends each datagram once, 'reliable' retransmits datagrams peers report missing",
+        default_value = "best-effort",
+        value_enum
+    )]
+    pub delivery_mode: DeliveryMode,
+
+    /// Transport carrying swarm traffic
+    #[arg(
+        long = "transport",
+        help = "Transport for swarm traffic: 'udp' multicast, or 'tcp' through a hub where multicast is blocked",
+        default_value = "udp",
+        value_enum
+    )]
+    pub transport: TransportKind,
+
+    /// Address of the TCP hub
+    #[arg(
+        long = "hub-address",
+        help = "Address of the TCP hub that relays messages between agents (required with --transport tcp)",
+        value_name = "ADDRESS:PORT"
+    )]
+    pub hub_address: Option<SocketAddr>,
+
+    /// Host the TCP hub in this agent
+    #[arg(
+        long = "serve-hub",
+        help = "Host the TCP hub on --hub-address in this agent instead of connecting to an existing one",
+        default_value = "false"
+    )]
+    pub serve_hub: bool,
+
+    /// Role announced to other agents
+    #[arg(
+        long = "role",
+        help = "Role announced to the other agents in heartbeats (e.g., 'debater', 'judge')",
+        default_value = "participant",
+        value_name = "ROLE"
+    )]
+    pub role: String,
+
+    /// Heartbeat interval in seconds
+    #[arg(
+        long = "heartbeat-interval",
+        help = "Seconds between presence heartbeats; agents silent for three intervals are considered departed",
+        default_value = "5",
+        value_name = "SECONDS"
+    )]
+    pub heartbeat_interval_seconds: u64,
+
+    /// Session (room) this agent takes part in
+    #[arg(
+        long = "session",
+        help = "Session (room) to join; agents only talk to others in the same session",
+        value_name = "NAME"
+    )]
+    pub session: Option<String>,
+
+    /// Derive the multicast address from the session name
+    #[arg(
+        long = "session-address",
+        help = "Derive the multicast group and port from --session instead of using --multicast-address",
+        default_value = "false"
+    )]
+    pub session_address: bool,
+
+    /// Number of recent turns sent to the LLM verbatim
+    #[arg(
+        long = "transcript-window",
+        help = "Number of most recent conversation turns sent to the LLM verbatim",
+        default_value = "20",
+        value_name = "TURNS"
+    )]
+    pub transcript_window: usize,
+
+    /// Number of turns after which older turns are summarized
+    #[arg(
+        long = "summarize-after",
+        help = "Summarize turns older than the window once the transcript reaches this many turns (0 drops them instead)",
+        default_value = "40",
+        value_name = "TURNS"
+    )]
+    pub summarize_after: usize,
+
+    /// Which incoming messages this agent answers
+    #[arg(
+        long = "respond",
+        help = "Which messages to answer: 'always', 'mention' (when addressed or @mentioned), 'trigger' (on --trigger phrases), 'probabilistic' or 'llm-check' (ask the LLM first)",
+        value_enum,
+        default_value_t = ResponseMode::Always
+    )]
+    pub respond: ResponseMode,
+
+    /// Phrases that prompt an answer with --respond trigger (repeatable)
+    #[arg(
+        long = "trigger",
+        help = "Phrase that prompts an answer with --respond trigger, matched case-insensitively (repeatable)",
+        value_name = "PHRASE"
+    )]
+    pub triggers: Vec<String>,
+
+    /// Share of messages answered with --respond probabilistic
+    #[arg(
+        long = "response-probability",
+        help = "Share of messages answered with --respond probabilistic, between 0 and 1",
+        default_value = "0.5",
+        value_name = "PROBABILITY"
+    )]
+    pub response_probability: f64,
+
+    /// Wait for the floor before speaking
+    #[arg(
+        long = "floor-control",
+        help = "Take turns: wait for the floor before speaking so only one agent speaks at a time",
+        default_value = "false"
+    )]
+    pub floor_control: bool,
+
+    /// Longest time one agent may hold the floor
+    #[arg(
+        long = "floor-timeout",
+        help = "Seconds an agent may hold the floor before others take it back, so a crashed agent cannot block the swarm",
+        default_value = "30",
+        value_name = "SECONDS"
+    )]
+    pub floor_timeout_seconds: u64,
+
+    /// Run as an LLM agent or as the debate moderator
+    #[arg(
+        long = "mode",
+        help = "'agent' takes part through an LLM; 'moderator' runs a Public Forum debate between agents with roles affirmative, negative and judge; 'judge' decides debates with JSON ballots",
+        value_enum,
+        default_value_t = RunMode::Agent
+    )]
+    pub mode: RunMode,
+
+    /// Speaking rate the moderator turns speech times into word budgets with
+    #[arg(
+        long = "words-per-minute",
+        help = "Speaking rate the moderator turns speech times into word budgets with",
+        default_value = "150",
+        value_name = "WORDS"
+    )]
+    pub words_per_minute: u64,
+
+    /// How long the moderator waits for each speech
+    #[arg(
+        long = "speech-timeout",
+        help = "Seconds the moderator waits for a speech before moving the round on",
+        default_value = "300",
+        value_name = "SECONDS"
+    )]
+    pub speech_timeout_seconds: u64,
+
+    /// File the judge writes its ballots to
+    #[arg(
+        long = "ballot-file",
+        help = "File a judge (--mode judge) writes its JSON ballot to",
+        default_value = "ballot.json",
+        value_name = "FILE"
+    )]
+    pub ballot_file: PathBuf,
+
+    /// Tools the LLM may call (repeatable)
+    #[arg(
+        long = "tool",
+        help = "Tool the LLM may call: 'read-file' (from --workspace), 'calculator' or 'search' (in --corpus) (repeatable)",
+        value_enum,
+        value_name = "TOOL"
+    )]
+    pub tools: Vec<ToolKind>,
+
+    /// Directory the read-file tool is confined to
+    #[arg(
+        long = "workspace",
+        help = "Directory the read-file tool may read from; nothing outside it is reachable",
+        value_name = "DIR"
+    )]
+    pub workspace: Option<PathBuf>,
+
+    /// Directory of text files the search tool searches
+    #[arg(
+        long = "corpus",
+        help = "Directory of text files the search tool searches",
+        value_name = "DIR"
+    )]
+    pub corpus: Option<PathBuf>,
+
+    /// Share tool results with peers
+    #[arg(
+        long = "broadcast-tool-results",
+        help = "Broadcast the results of this agent's tool calls to its peers as structured messages",
+        default_value = "false"
+    )]
+    pub broadcast_tool_results: bool,
+
+    /// Script the mock backend answers from
+    #[arg(
+        long = "script",
+        help = "TOML script the mock backend answers from; without one it echoes every message",
+        value_name = "FILE"
+    )]
+    pub script: Option<PathBuf>,
+
+    /// Record every LLM chat to a cassette
+    #[arg(
+        long = "record",
+        help = "Record every LLM request and response to this cassette file",
+        value_name = "FILE",
+        conflicts_with = "replay"
+    )]
+    pub record: Option<PathBuf>,
+
+    /// Answer LLM chats from a recorded cassette
+    #[arg(
+        long = "replay",
+        help = "Answer from this recorded cassette instead of the LLM, failing on unrecorded requests",
+        value_name = "FILE",
+        conflicts_with = "record"
+    )]
+    pub replay: Option<PathBuf>,
 }
 
 impl AgentArgs {
@@ -208,6 +560,57 @@ impl AgentArgs {
         }
     }
 
+    /// Get the pre-shared swarm key, reading from file if specified or from the environment
+    /// Returns None when message signing is not configured
+    pub fn get_swarm_key(&self) -> Result<Option<SwarmKey>> {
+        let key = if let Some(file_path) = &self.swarm_key_file {
+            let content = fs::read(file_path).map_err(|e| {
+                anyhow!(
+                    "Failed to read swarm key file '{}': {}",
+                    file_path.to_string_lossy(),
+                    e
+                )
+            })?;
+            content.trim_ascii().to_vec()
+        } else {
+            match std::env::var(SWARM_KEY_ENV) {
+                Ok(value) => value.trim().as_bytes().to_vec(),
+                Err(_) => return Ok(None),
+            }
+        };
+
+        SwarmKey::new(&key).map(Some).map_err(|e| anyhow!(e))
+    }
+
+    /// Get the swarm encryption passphrase, reading from file if specified or from the environment
+    /// Returns None when encryption is not configured
+    pub fn get_swarm_passphrase(&self) -> Result<Option<String>> {
+        let passphrase = if let Some(file_path) = &self.swarm_passphrase_file {
+            fs::read_to_string(file_path).map_err(|e| {
+                anyhow!(
+                    "Failed to read swarm passphrase file '{}': {}",
+                    file_path.to_string_lossy(),
+                    e
+                )
+            })?
+        } else {
+            match std::env::var(SWARM_PASSPHRASE_ENV) {
+                Ok(value) => value,
+                Err(_) => return Ok(None),
+            }
+        };
+
+        let passphrase = passphrase.trim().to_string();
+        if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+            return Err(anyhow!(
+                "Swarm passphrase must be at least {} characters",
+                MIN_PASSPHRASE_LENGTH
+            ));
+        }
+
+        Ok(Some(passphrase))
+    }
+
     /// Validate the provided arguments
     pub fn validate(&self) -> Result<(), String> {
         // Validate agent ID is not empty
@@ -235,6 +638,132 @@ impl AgentArgs {
             ));
         }
 
+        // IPv6 multicast scopes 0 and F are reserved (RFC 4291)
+        if let IpAddr::V6(multicast_v6) = self.multicast_address.ip() {
+            let scope = multicast_v6.segments()[0] & 0x000f;
+            if scope == 0x0 || scope == 0xf {
+                return Err(format!(
+                    "IPv6 multicast address {} uses reserved scope {:x}",
+                    multicast_v6, scope
+                ));
+            }
+        }
+
+        // Validate interface names are not blank
+        if self.interfaces.iter().any(|interface| interface.trim().is_empty()) {
+            return Err("Interface name cannot be empty".to_string());
+        }
+
+        // Validate multicast hops fits in the IP header
+        if self.multicast_hops == 0 || self.multicast_hops > 255 {
+            return Err("Multicast hops must be between 1 and 255".to_string());
+        }
+
+        // Validate the TCP transport knows where its hub is
+        if self.transport == TransportKind::Tcp && self.hub_address.is_none() {
+            return Err("The TCP transport requires --hub-address".to_string());
+        }
+        if self.serve_hub && self.transport != TransportKind::Tcp {
+            return Err("--serve-hub requires --transport tcp".to_string());
+        }
+
+        // Validate role is not blank
+        if self.role.trim().is_empty() {
+            return Err("Role cannot be empty".to_string());
+        }
+
+        // Validate heartbeat interval is reasonable
+        if self.heartbeat_interval_seconds == 0 || self.heartbeat_interval_seconds > 300 {
+            return Err("Heartbeat interval must be between 1 and 300 seconds".to_string());
+        }
+
+        // Validate session name uses the same characters as agent IDs
+        if let Some(ref session) = self.session
+            && (session.is_empty()
+                || !session
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_'))
+        {
+            return Err(
+                "Session can only contain alphanumeric characters, hyphens, and underscores"
+                    .to_string(),
+            );
+        }
+        if self.session_address && self.session.is_none() {
+            return Err("--session-address requires --session".to_string());
+        }
+        if self.session_address && self.transport != TransportKind::Udp {
+            return Err("--session-address only applies to --transport udp".to_string());
+        }
+
+        // Validate the transcript keeps something and summarizes beyond its window
+        if self.transcript_window == 0 || self.transcript_window > 1000 {
+            return Err("Transcript window must be between 1 and 1000 turns".to_string());
+        }
+        if self.summarize_after != 0 && self.summarize_after <= self.transcript_window {
+            return Err("--summarize-after must be larger than --transcript-window".to_string());
+        }
+
+        // Validate the response policy has what its mode needs
+        if self.respond == ResponseMode::Trigger
+            && !self
+                .triggers
+                .iter()
+                .any(|trigger| !trigger.trim().is_empty())
+        {
+            return Err("--respond trigger requires at least one --trigger phrase".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.response_probability) {
+            return Err("Response probability must be between 0 and 1".to_string());
+        }
+
+        // Validate the floor is given back in reasonable time
+        if self.floor_timeout_seconds == 0 || self.floor_timeout_seconds > 600 {
+            return Err("Floor timeout must be between 1 and 600 seconds".to_string());
+        }
+
+        // Validate the moderator's budgets are reasonable
+        if self.words_per_minute < 50 || self.words_per_minute > 400 {
+            return Err("Words per minute must be between 50 and 400".to_string());
+        }
+        if self.speech_timeout_seconds == 0 || self.speech_timeout_seconds > 3600 {
+            return Err("Speech timeout must be between 1 and 3600 seconds".to_string());
+        }
+
+        // Validate the mock script exists, and only the mock backend gets one
+        if let Some(ref script) = self.script {
+            if !matches!(self.llm_backend, LLMBackend::Mock) {
+                return Err("--script only applies to --llm-backend mock".to_string());
+            }
+            if !script.is_file() {
+                return Err(format!("Mock script '{}' is not a file", script.display()));
+            }
+        }
+
+        // Validate the cassette to replay exists
+        if let Some(ref cassette) = self.replay
+            && !cassette.is_file()
+        {
+            return Err(format!("Cassette '{}' is not a file", cassette.display()));
+        }
+
+        // Validate every tool has the directory it works in
+        for (tool, name, flag, dir) in [
+            (ToolKind::ReadFile, "read-file", "--workspace", &self.workspace),
+            (ToolKind::Search, "search", "--corpus", &self.corpus),
+        ] {
+            if !self.tools.contains(&tool) {
+                continue;
+            }
+            match dir {
+                Some(dir) if dir.is_dir() => {}
+                Some(dir) => {
+                    return Err(format!("{} '{}' is not a directory", flag, dir.display()));
+                }
+                None => return Err(format!("--tool {} requires {}", name, flag)),
+            }
+        }
+
         // Validate timeout is reasonable
         if self.timeout_seconds == 0 || self.timeout_seconds > 300 {
             return Err("Timeout must be between 1 and 300 seconds".to_string());
@@ -245,30 +774,160 @@ impl AgentArgs {
             return Err("Max retries cannot exceed 10".to_string());
         }
 
+        // Validate retry delays are reasonable and in order
+        if self.retry_base_delay_ms == 0 || self.retry_base_delay_ms > 60_000 {
+            return Err("Retry base delay must be between 1 and 60000 milliseconds".to_string());
+        }
+        if self.retry_max_delay_seconds == 0 || self.retry_max_delay_seconds > 600 {
+            return Err("Retry max delay must be between 1 and 600 seconds".to_string());
+        }
+        if self.retry_base_delay_ms > self.retry_max_delay_seconds * 1000 {
+            return Err("Retry base delay cannot exceed the retry max delay".to_string());
+        }
+
+        // Validate the degraded state can be reached and left
+        if self.degraded_after == 0 || self.degraded_after > 100 {
+            return Err("Degraded after must be between 1 and 100 failures".to_string());
+        }
+        if self.degraded_cooldown_seconds == 0 || self.degraded_cooldown_seconds > 3600 {
+            return Err("Degraded cooldown must be between 1 and 3600 seconds".to_string());
+        }
+
         // Validate model name is not empty
         if self.model.trim().is_empty() {
             return Err("Model name cannot be empty".to_string());
         }
 
+        // Validate replay window is reasonable
+        if self.replay_window_seconds > 3600 {
+            return Err("Replay window cannot exceed 3600 seconds".to_string());
+        }
+
         // Validate processing delay is reasonable
         if self.processing_delay_ms > 60000 {
             return Err("Processing delay cannot exceed 60 seconds".to_string());
         }
 
         // Validate personality file can be read if specified
-        if let Some(ref file_path) = self.personality_file {
-            if let Err(e) = self.get_personality() {
-                return Err(format!(
-                    "Invalid personality file '{}': {}",
-                    file_path.to_string_lossy(),
-                    e
-                ));
-            }
+        if let Some(ref file_path) = self.personality_file
+            && let Err(e) = self.get_personality()
+        {
+            return Err(format!(
+                "Invalid personality file '{}': {}",
+                file_path.to_string_lossy(),
+                e
+            ));
+        }
+
+        // Validate the identity directory is usable if specified
+        if let Some(ref dir) = self.identity_dir
+            && dir.exists()
+            && !dir.is_dir()
+        {
+            return Err(format!(
+                "Identity directory '{}' is not a directory",
+                dir.to_string_lossy()
+            ));
+        }
+
+        // Validate the swarm key can be loaded if one is configured
+        if let Err(e) = self.get_swarm_key() {
+            return Err(format!("Invalid swarm key: {}", e));
+        }
+
+        // Validate the swarm passphrase can be loaded if one is configured
+        if let Err(e) = self.get_swarm_passphrase() {
+            return Err(format!("Invalid swarm passphrase: {}", e));
         }
 
         Ok(())
     }
 
+    /// Get the replay protection window, or None if disabled
+    pub fn get_replay_window(&self) -> Option<Duration> {
+        (self.replay_window_seconds > 0).then(|| Duration::from_secs(self.replay_window_seconds))
+    }
+
+    /// Get the multicast address, derived from the session name if requested
+    pub fn get_multicast_address(&self) -> SocketAddr {
+        match self.session {
+            Some(ref session) if self.session_address => {
+                derive_multicast_address(session, self.multicast_address)
+            }
+            _ => self.multicast_address,
+        }
+    }
+
+    /// Get how much of the conversation the LLM sees and when it is summarized
+    pub fn get_transcript_limits(&self) -> TranscriptLimits {
+        TranscriptLimits {
+            window: self.transcript_window,
+            summarize_after: self.summarize_after,
+        }
+    }
+
+    /// Get how failed LLM requests are retried
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            retries: self.max_retries,
+            base_delay: Duration::from_millis(self.retry_base_delay_ms),
+            max_delay: Duration::from_secs(self.retry_max_delay_seconds),
+            jitter: !self.no_retry_jitter,
+        }
+    }
+
+    /// Get the policy deciding which messages this agent answers
+    pub fn get_response_policy(&self) -> ResponsePolicy {
+        let triggers = self
+            .triggers
+            .iter()
+            .filter(|trigger| !trigger.trim().is_empty())
+            .cloned()
+            .collect();
+        ResponsePolicy::new(self.respond, triggers, self.response_probability)
+    }
+
+    /// Get the tools the LLM may call
+    pub fn get_tool_registry(&self) -> Result<ToolRegistry> {
+        let mut registry = ToolRegistry::new();
+        for tool in &self.tools {
+            match tool {
+                ToolKind::ReadFile => {
+                    let workspace = self
+                        .workspace
+                        .as_deref()
+                        .ok_or_else(|| anyhow!("The read-file tool requires --workspace"))?;
+                    registry.register(Box::new(ReadFileTool::new(workspace)?));
+                }
+                ToolKind::Calculator => registry.register(Box::new(CalculatorTool)),
+                ToolKind::Search => {
+                    let corpus = self
+                        .corpus
+                        .as_deref()
+                        .ok_or_else(|| anyhow!("The search tool requires --corpus"))?;
+                    registry.register(Box::new(SearchTool::new(corpus)?));
+                }
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Get the heartbeat announcing this agent to the swarm
+    pub fn get_heartbeat(&self) -> Heartbeat {
+        Heartbeat {
+            model: self.model.clone(),
+            backend: self.llm_backend.to_string(),
+            // The moderator always announces the `moderator` role, since judges only answer an RFD call from that role with a ballot
+            role: if self.mode == RunMode::Moderator {
+                MODERATOR.to_string()
+            } else {
+                self.role.clone()
+            },
+            interval_ms: (self.heartbeat_interval_seconds * 1000) as u32,
+            leaving: false,
+        }
+    }
+
     /// Get the effective API key, checking environment variables if not provided
     pub fn get_api_key(&self) -> Option<String> {
         if let Some(key) = &self.api_key {
@@ -282,6 +941,7 @@ impl AgentArgs {
             LLMBackend::Google => std::env::var("GEMINI_API_KEY").ok(),
             LLMBackend::OpenRouter => std::env::var("OPENROUTER_API_KEY").ok(),
             LLMBackend::Local => None, // Local models typically don't need API keys
+            LLMBackend::Mock => None,
         }
     }
 }
@@ -295,18 +955,55 @@ mod tests {
         let args = AgentArgs {
             agent_id: "test-agent".to_string(),
             multicast_address: "239.255.255.250:8080".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
+            multicast_hops: 1,
             llm_backend: LLMBackend::OpenAI,
             model: "gpt-3.5-turbo".to_string(),
             api_key: None,
             endpoint: None,
             timeout_seconds: 30,
             max_retries: 3,
+            retry_base_delay_ms: 500,
+            retry_max_delay_seconds: 30,
+            no_retry_jitter: false,
+            error_reply: ErrorReply::Silent,
+            degraded_after: 3,
+            degraded_cooldown_seconds: 60,
             log_level: "info".to_string(),
             personality: "You are a helpful AI agent.".to_string(),
             personality_file: None,
             processing_delay_ms: 5000,
             voice: false,
+            swarm_key_file: None,
+            identity_dir: None,
+            swarm_passphrase_file: None,
+            replay_window_seconds: 30,
+            delivery_mode: DeliveryMode::BestEffort,
+            transport: TransportKind::Udp,
+            hub_address: None,
+            serve_hub: false,
+            role: "participant".to_string(),
+            heartbeat_interval_seconds: 5,
+            session: None,
+            session_address: false,
+            transcript_window: 20,
+            summarize_after: 40,
+            respond: ResponseMode::Always,
+            triggers: Vec::new(),
+            response_probability: 0.5,
+            floor_control: false,
+            floor_timeout_seconds: 30,
+            mode: RunMode::Agent,
+            words_per_minute: 150,
+            speech_timeout_seconds: 300,
+            ballot_file: PathBuf::from("ballot.json"),
+            tools: Vec::new(),
+            workspace: None,
+            corpus: None,
+            broadcast_tool_results: false,
+            script: None,
+            record: None,
+            replay: None,
         };
 
         assert!(args.validate().is_ok());
@@ -317,18 +1014,55 @@ mod tests {
         let args = AgentArgs {
             agent_id: "".to_string(),
             multicast_address: "239.255.255.250:8080".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
+            multicast_hops: 1,
             llm_backend: LLMBackend::OpenAI,
             model: "gpt-3.5-turbo".to_string(),
             api_key: None,
             endpoint: None,
             timeout_seconds: 30,
             max_retries: 3,
+            retry_base_delay_ms: 500,
+            retry_max_delay_seconds: 30,
+            no_retry_jitter: false,
+            error_reply: ErrorReply::Silent,
+            degraded_after: 3,
+            degraded_cooldown_seconds: 60,
             log_level: "info".to_string(),
             personality: "You are a helpful AI agent.".to_string(),
             personality_file: None,
             processing_delay_ms: 5000,
             voice: false,
+            swarm_key_file: None,
+            identity_dir: None,
+            swarm_passphrase_file: None,
+            replay_window_seconds: 30,
+            delivery_mode: DeliveryMode::BestEffort,
+            transport: TransportKind::Udp,
+            hub_address: None,
+            serve_hub: false,
+            role: "participant".to_string(),
+            heartbeat_interval_seconds: 5,
+            session: None,
+            session_address: false,
+            transcript_window: 20,
+            summarize_after: 40,
+            respond: ResponseMode::Always,
+            triggers: Vec::new(),
+            response_probability: 0.5,
+            floor_control: false,
+            floor_timeout_seconds: 30,
+            mode: RunMode::Agent,
+            words_per_minute: 150,
+            speech_timeout_seconds: 300,
+            ballot_file: PathBuf::from("ballot.json"),
+            tools: Vec::new(),
+            workspace: None,
+            corpus: None,
+            broadcast_tool_results: false,
+            script: None,
+            record: None,
+            replay: None,
         };
 
         assert!(args.validate().is_err());
@@ -340,18 +1074,55 @@ mod tests {
         let args = AgentArgs {
             agent_id: "invalid@agent".to_string(),
             multicast_address: "239.255.255.250:8080".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
+            multicast_hops: 1,
             llm_backend: LLMBackend::OpenAI,
             model: "gpt-3.5-turbo".to_string(),
             api_key: None,
             endpoint: None,
             timeout_seconds: 30,
             max_retries: 3,
+            retry_base_delay_ms: 500,
+            retry_max_delay_seconds: 30,
+            no_retry_jitter: false,
+            error_reply: ErrorReply::Silent,
+            degraded_after: 3,
+            degraded_cooldown_seconds: 60,
             log_level: "info".to_string(),
             personality: "You are a helpful AI agent.".to_string(),
             personality_file: None,
             processing_delay_ms: 5000,
             voice: false,
+            swarm_key_file: None,
+            identity_dir: None,
+            swarm_passphrase_file: None,
+            replay_window_seconds: 30,
+            delivery_mode: DeliveryMode::BestEffort,
+            transport: TransportKind::Udp,
+            hub_address: None,
+            serve_hub: false,
+            role: "participant".to_string(),
+            heartbeat_interval_seconds: 5,
+            session: None,
+            session_address: false,
+            transcript_window: 20,
+            summarize_after: 40,
+            respond: ResponseMode::Always,
+            triggers: Vec::new(),
+            response_probability: 0.5,
+            floor_control: false,
+            floor_timeout_seconds: 30,
+            mode: RunMode::Agent,
+            words_per_minute: 150,
+            speech_timeout_seconds: 300,
+            ballot_file: PathBuf::from("ballot.json"),
+            tools: Vec::new(),
+            workspace: None,
+            corpus: None,
+            broadcast_tool_results: false,
+            script: None,
+            record: None,
+            replay: None,
         };
 
         assert!(args.validate().is_err());
@@ -366,18 +1137,55 @@ mod tests {
         let args = AgentArgs {
             agent_id: "test-agent".to_string(),
             multicast_address: "192.168.1.1:8080".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
+            multicast_hops: 1,
             llm_backend: LLMBackend::OpenAI,
             model: "gpt-3.5-turbo".to_string(),
             api_key: None,
             endpoint: None,
             timeout_seconds: 30,
             max_retries: 3,
+            retry_base_delay_ms: 500,
+            retry_max_delay_seconds: 30,
+            no_retry_jitter: false,
+            error_reply: ErrorReply::Silent,
+            degraded_after: 3,
+            degraded_cooldown_seconds: 60,
             log_level: "info".to_string(),
             personality: "You are a helpful AI agent.".to_string(),
             personality_file: None,
             processing_delay_ms: 5000,
             voice: false,
+            swarm_key_file: None,
+            identity_dir: None,
+            swarm_passphrase_file: None,
+            replay_window_seconds: 30,
+            delivery_mode: DeliveryMode::BestEffort,
+            transport: TransportKind::Udp,
+            hub_address: None,
+            serve_hub: false,
+            role: "participant".to_string(),
+            heartbeat_interval_seconds: 5,
+            session: None,
+            session_address: false,
+            transcript_window: 20,
+            summarize_after: 40,
+            respond: ResponseMode::Always,
+            triggers: Vec::new(),
+            response_probability: 0.5,
+            floor_control: false,
+            floor_timeout_seconds: 30,
+            mode: RunMode::Agent,
+            words_per_minute: 150,
+            speech_timeout_seconds: 300,
+            ballot_file: PathBuf::from("ballot.json"),
+            tools: Vec::new(),
+            workspace: None,
+            corpus: None,
+            broadcast_tool_results: false,
+            script: None,
+            record: None,
+            replay: None,
         };
 
         assert!(args.validate().is_err());
@@ -393,18 +1201,55 @@ mod tests {
         let args = AgentArgs {
             agent_id: "test-agent".to_string(),
             multicast_address: "239.255.255.250:8080".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
+            multicast_hops: 1,
             llm_backend: LLMBackend::OpenAI,
             model: "gpt-3.5-turbo".to_string(),
             api_key: None,
             endpoint: None,
             timeout_seconds: 30,
             max_retries: 3,
+            retry_base_delay_ms: 500,
+            retry_max_delay_seconds: 30,
+            no_retry_jitter: false,
+            error_reply: ErrorReply::Silent,
+            degraded_after: 3,
+            degraded_cooldown_seconds: 60,
             log_level: "info".to_string(),
             personality: "You are a helpful AI agent.".to_string(),
             personality_file: None,
             processing_delay_ms: 5000,
             voice: false,
+            swarm_key_file: None,
+            identity_dir: None,
+            swarm_passphrase_file: None,
+            replay_window_seconds: 30,
+            delivery_mode: DeliveryMode::BestEffort,
+            transport: TransportKind::Udp,
+            hub_address: None,
+            serve_hub: false,
+            role: "participant".to_string(),
+            heartbeat_interval_seconds: 5,
+            session: None,
+            session_address: false,
+            transcript_window: 20,
+            summarize_after: 40,
+            respond: ResponseMode::Always,
+            triggers: Vec::new(),
+            response_probability: 0.5,
+            floor_control: false,
+            floor_timeout_seconds: 30,
+            mode: RunMode::Agent,
+            words_per_minute: 150,
+            speech_timeout_seconds: 300,
+            ballot_file: PathBuf::from("ballot.json"),
+            tools: Vec::new(),
+            workspace: None,
+            corpus: None,
+            broadcast_tool_results: false,
+            script: None,
+            record: None,
+            replay: None,
         };
 
         assert_eq!(
@@ -417,7 +1262,7 @@ mod tests {
     fn test_personality_file_mutual_exclusivity() {
         // This test verifies that clap's conflicts_with attribute works
         // When both flags are provided, clap will return an error
-        let result = AgentArgs::try_parse_from(&[
+        let result = AgentArgs::try_parse_from([
             "conclave",
             "--agent-id",
             "test-agent",
@@ -435,7 +1280,7 @@ mod tests {
     #[test]
     fn test_personality_file_flag_alone() {
         // Test that only --personality-file flag works
-        let result = AgentArgs::try_parse_from(&[
+        let result = AgentArgs::try_parse_from([
             "conclave",
             "--agent-id",
             "test-agent",
@@ -456,7 +1301,7 @@ mod tests {
     #[test]
     fn test_personality_inline_flag_alone() {
         // Test that only --personality flag works (default behavior)
-        let result = AgentArgs::try_parse_from(&[
+        let result = AgentArgs::try_parse_from([
             "conclave",
             "--agent-id",
             "test-agent",
@@ -473,11 +1318,588 @@ mod tests {
     #[test]
     fn test_no_personality_flags() {
         // Test default behavior when neither flag is provided
-        let result = AgentArgs::try_parse_from(&["conclave", "--agent-id", "test-agent"]);
+        let result = AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent"]);
 
         assert!(result.is_ok());
         let args = result.unwrap();
         assert!(!args.personality.is_empty());
         assert_eq!(args.personality_file, None);
     }
+
+    #[test]
+    fn test_agent_args_validation_ipv6_multicast() {
+        let mut args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--multicast-address",
+            "[ff02::1234]:8080",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+
+        args.multicast_address = "[ff05::1234]:8080".parse().unwrap();
+        assert!(args.validate().is_ok());
+
+        // Scope 0 is reserved
+        args.multicast_address = "[ff00::1234]:8080".parse().unwrap();
+        assert!(args.validate().unwrap_err().contains("reserved scope"));
+
+        // Unicast IPv6 addresses are rejected
+        args.multicast_address = "[fd00::2]:8080".parse().unwrap();
+        assert!(
+            args.validate()
+                .unwrap_err()
+                .contains("is not a valid multicast address")
+        );
+    }
+
+    #[test]
+    fn test_agent_args_validation_multicast_hops() {
+        let mut args =
+            AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent"]).unwrap();
+        assert_eq!(args.multicast_hops, 1);
+
+        args.multicast_hops = 0;
+        assert!(args.validate().is_err());
+
+        args.multicast_hops = 256;
+        assert_eq!(
+            args.validate().unwrap_err(),
+            "Multicast hops must be between 1 and 255"
+        );
+    }
+
+    #[test]
+    fn test_multiple_interfaces() {
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--interface",
+            "eth0,wlan0",
+            "--interface",
+            "192.168.1.100",
+        ])
+        .unwrap();
+
+        assert_eq!(args.interfaces, vec!["eth0", "wlan0", "192.168.1.100"]);
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_swarm_key_from_file() {
+        let mut key_file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut key_file, b"a-long-enough-swarm-key\n").unwrap();
+
+        let mut args =
+            AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent"]).unwrap();
+        args.swarm_key_file = Some(key_file.path().to_path_buf());
+
+        assert!(args.get_swarm_key().unwrap().is_some());
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_swarm_key_file_too_short() {
+        let mut key_file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut key_file, b"short").unwrap();
+
+        let mut args =
+            AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent"]).unwrap();
+        args.swarm_key_file = Some(key_file.path().to_path_buf());
+
+        assert!(args.validate().unwrap_err().contains("Invalid swarm key"));
+    }
+
+    #[test]
+    fn test_swarm_passphrase_from_file() {
+        let mut passphrase_file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut passphrase_file, b"correct horse battery staple\n").unwrap();
+
+        let mut args =
+            AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent"]).unwrap();
+        args.swarm_passphrase_file = Some(passphrase_file.path().to_path_buf());
+
+        assert_eq!(
+            args.get_swarm_passphrase().unwrap().as_deref(),
+            Some("correct horse battery staple")
+        );
+        assert!(args.validate().is_ok());
+
+        let short_file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(short_file.path(), "short").unwrap();
+        args.swarm_passphrase_file = Some(short_file.path().to_path_buf());
+        assert!(
+            args.validate()
+                .unwrap_err()
+                .contains("Invalid swarm passphrase")
+        );
+    }
+
+    #[test]
+    fn test_replay_window() {
+        let mut args =
+            AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent"]).unwrap();
+        assert_eq!(args.get_replay_window(), Some(Duration::from_secs(30)));
+
+        args.replay_window_seconds = 0;
+        assert_eq!(args.get_replay_window(), None);
+
+        args.replay_window_seconds = 7200;
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_delivery_mode() {
+        let args = AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent"]).unwrap();
+        assert_eq!(args.delivery_mode, DeliveryMode::BestEffort);
+
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--delivery",
+            "reliable",
+        ])
+        .unwrap();
+        assert_eq!(args.delivery_mode, DeliveryMode::Reliable);
+
+        assert!(
+            AgentArgs::try_parse_from([
+                "conclave",
+                "--agent-id",
+                "test-agent",
+                "--delivery",
+                "guaranteed",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_tcp_transport_requires_hub_address() {
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--transport",
+            "tcp",
+        ])
+        .unwrap();
+        assert!(args.validate().is_err());
+
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--transport",
+            "tcp",
+            "--hub-address",
+            "10.0.0.5:7878",
+            "--serve-hub",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.transport, TransportKind::Tcp);
+        assert!(args.serve_hub);
+
+        // Hosting a hub makes no sense over multicast
+        let args =
+            AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent", "--serve-hub"])
+                .unwrap();
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_session_address_derivation() {
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--session",
+            "team-red",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(
+            args.get_multicast_address(),
+            "239.255.255.250:8080".parse().unwrap()
+        );
+
+        let derived = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--session",
+            "team-red",
+            "--session-address",
+        ])
+        .unwrap();
+        assert!(derived.validate().is_ok());
+        assert_eq!(
+            derived.get_multicast_address(),
+            derive_multicast_address("team-red", args.multicast_address)
+        );
+
+        // Deriving an address needs a session name to derive it from
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--session-address",
+        ])
+        .unwrap();
+        assert!(args.validate().is_err());
+
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--session",
+            "team red",
+        ])
+        .unwrap();
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let args = AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent"]).unwrap();
+        assert_eq!(args.get_retry_policy(), RetryPolicy::default());
+
+        let mut args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--max-retries",
+            "5",
+            "--retry-base-delay",
+            "250",
+            "--retry-max-delay",
+            "10",
+            "--no-retry-jitter",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(
+            args.get_retry_policy(),
+            RetryPolicy {
+                retries: 5,
+                base_delay: Duration::from_millis(250),
+                max_delay: Duration::from_secs(10),
+                jitter: false,
+            }
+        );
+
+        // The first wait cannot be longer than the longest one
+        args.retry_base_delay_ms = 20_000;
+        assert!(args.validate().is_err());
+
+        args.retry_base_delay_ms = 0;
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_llm_error_options() {
+        let args = AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent"]).unwrap();
+        assert_eq!(args.error_reply, ErrorReply::Silent);
+
+        let mut args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--on-llm-error",
+            "announce",
+            "--degraded-after",
+            "5",
+            "--degraded-cooldown",
+            "120",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.error_reply, ErrorReply::Announce);
+        assert_eq!(args.degraded_after, 5);
+
+        // An agent that degrades without failing, or never recovers, is misconfigured
+        args.degraded_after = 0;
+        assert!(args.validate().is_err());
+        args.degraded_after = 5;
+        args.degraded_cooldown_seconds = 0;
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_transcript_limits() {
+        let args = AgentArgs::try_parse_from(["conclave", "--agent-id", "test-agent"]).unwrap();
+        assert_eq!(args.get_transcript_limits(), TranscriptLimits::default());
+
+        let mut args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--transcript-window",
+            "10",
+            "--summarize-after",
+            "0",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.get_transcript_limits().window, 10);
+
+        // Summarizing within the window would never leave anything to summarize
+        args.summarize_after = 10;
+        assert!(args.validate().is_err());
+
+        args.summarize_after = 40;
+        args.transcript_window = 0;
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_response_policy_options() {
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "judge",
+            "--respond",
+            "trigger",
+            "--trigger",
+            "Please provide your RFD",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.respond, ResponseMode::Trigger);
+        assert_eq!(args.triggers, vec!["Please provide your RFD"]);
+
+        // Trigger mode without a trigger would never answer
+        let args =
+            AgentArgs::try_parse_from(["conclave", "--agent-id", "judge", "--respond", "trigger"])
+                .unwrap();
+        assert!(args.validate().is_err());
+
+        let mut args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--respond",
+            "llm-check",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.respond, ResponseMode::LlmCheck);
+
+        args.response_probability = 1.5;
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_floor_control_options() {
+        let mut args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--floor-control",
+            "--floor-timeout",
+            "45",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert!(args.floor_control);
+        assert_eq!(args.floor_timeout_seconds, 45);
+
+        // A floor that is never taken back would let a crashed agent silence everyone
+        args.floor_timeout_seconds = 0;
+        assert!(args.validate().is_err());
+        args.floor_timeout_seconds = 601;
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_moderator_options() {
+        let mut args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "moderator",
+            "--mode",
+            "moderator",
+            "--words-per-minute",
+            "200",
+            "--speech-timeout",
+            "120",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.mode, RunMode::Moderator);
+        assert_eq!(args.words_per_minute, 200);
+        assert_eq!(args.speech_timeout_seconds, 120);
+        assert_eq!(args.get_heartbeat().role, MODERATOR);
+
+        args.words_per_minute = 10;
+        assert!(args.validate().is_err());
+        args.words_per_minute = 150;
+        args.speech_timeout_seconds = 0;
+        assert!(args.validate().is_err());
+
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "judge-1",
+            "--mode",
+            "judge",
+            "--ballot-file",
+            "round-1.json",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.mode, RunMode::Judge);
+        assert_eq!(args.ballot_file, PathBuf::from("round-1.json"));
+    }
+
+    #[test]
+    fn test_tool_options() {
+        let workspace = tempfile::tempdir().unwrap();
+        let workspace_path = workspace.path().to_str().unwrap();
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "researcher",
+            "--tool",
+            "read-file",
+            "--tool",
+            "calculator",
+            "--workspace",
+            workspace_path,
+            "--broadcast-tool-results",
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.tools, vec![ToolKind::ReadFile, ToolKind::Calculator]);
+        assert!(args.broadcast_tool_results);
+        let definitions = args.get_tool_registry().unwrap().definitions();
+        assert_eq!(definitions.len(), 2);
+
+        // Each sandboxed tool needs its directory
+        let args =
+            AgentArgs::try_parse_from(["conclave", "--agent-id", "researcher", "--tool", "search"])
+                .unwrap();
+        assert!(args.validate().is_err());
+        assert!(args.get_tool_registry().is_err());
+
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "researcher",
+            "--tool",
+            "search",
+            "--corpus",
+            "/nonexistent/corpus",
+        ])
+        .unwrap();
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_mock_backend_options() {
+        let script = tempfile::NamedTempFile::new().unwrap();
+        let script_path = script.path().to_str().unwrap();
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "offline",
+            "--llm-backend",
+            "mock",
+            "--script",
+            script_path,
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert!(matches!(args.llm_backend, LLMBackend::Mock));
+        assert_eq!(args.get_api_key(), None);
+
+        // A script only makes sense for the mock backend
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "online",
+            "--script",
+            script_path,
+        ])
+        .unwrap();
+        assert!(args.validate().is_err());
+
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "offline",
+            "--llm-backend",
+            "mock",
+            "--script",
+            "/nonexistent/script.toml",
+        ])
+        .unwrap();
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_cassette_options() {
+        let cassette = tempfile::NamedTempFile::new().unwrap();
+        let cassette_path = cassette.path().to_str().unwrap();
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "aff-1",
+            "--replay",
+            cassette_path,
+        ])
+        .unwrap();
+        assert!(args.validate().is_ok());
+        assert_eq!(args.replay.as_deref(), Some(cassette.path()));
+
+        // The cassette to replay has to exist, and cannot be recorded at the same time
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "aff-1",
+            "--replay",
+            "/nonexistent/debate.json",
+        ])
+        .unwrap();
+        assert!(args.validate().is_err());
+        assert!(
+            AgentArgs::try_parse_from([
+                "conclave",
+                "--agent-id",
+                "aff-1",
+                "--record",
+                "debate.json",
+                "--replay",
+                cassette_path,
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_heartbeat_from_args() {
+        let args = AgentArgs::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "test-agent",
+            "--llm-backend",
+            "anthropic",
+            "--model",
+            "claude-3-sonnet",
+            "--role",
+            "judge",
+        ])
+        .unwrap();
+
+        let heartbeat = args.get_heartbeat();
+        assert_eq!(heartbeat.model, "claude-3-sonnet");
+        assert_eq!(heartbeat.backend, "anthropic");
+        assert_eq!(heartbeat.role, "judge");
+        assert_eq!(heartbeat.interval_ms, 5000);
+        assert!(!heartbeat.leaving);
+    }
 }
@@ -0,0 +1,281 @@
+use crate::auth::SwarmKey;
+use crate::crypto::SwarmCipher;
+use crate::identity::{AgentIdentity, IdentityDirectory, TrustStore};
+use crate::message::{
+    AgentMessage, CompressedAgentMessage, Datagram, Frame, FrameBody, SignedMessage, now_millis,
+};
+use crate::network::{NetworkConfig, NetworkError};
+use crate::replay::{ReplayCounters, ReplayGuard};
+use lru::LruCache;
+use prost::Message;
+use std::fmt::Display;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of senders remembered as speaking an unsupported protocol
+const UNSUPPORTED_SENDER_CAPACITY: usize = 256;
+
+/// Turns agent messages into authenticated, optionally encrypted frames and back
+///
+/// Every transport shares this pipeline, so signing, encryption and replay
+/// protection behave the same whichever way the bytes travel.
+pub struct MessageCodec {
+    /// Message size above which bodies are gzip-compressed
+    compression_threshold: usize,
+    /// Session every outgoing message is tagged with
+    session: String,
+    /// Pre-shared swarm key; when set, every datagram is signed and verified
+    swarm_key: Option<SwarmKey>,
+    /// Cipher derived from the swarm passphrase; when set, every frame is encrypted
+    cipher: Option<SwarmCipher>,
+    /// Number of datagrams rejected because of a missing or bad signature
+    rejected_signatures: AtomicU64,
+    /// This agent's keypair, used to sign outgoing messages
+    identity: Option<AgentIdentity>,
+    /// Known peer keys, used to verify incoming messages
+    trust_store: Option<Mutex<TrustStore>>,
+    /// Recently seen message ids, used to reject replayed messages
+    replay_guard: Option<Mutex<ReplayGuard>>,
+    /// Senders already reported as speaking a protocol this agent does not understand
+    unsupported_senders: Mutex<LruCache<String, ()>>,
+}
+
+impl MessageCodec {
+    /// Create a codec for the given agent, loading or creating its signing identity
+    pub fn new(config: &NetworkConfig, agent_id: &str) -> Result<Self, NetworkError> {
+        let (identity, trust_store) = match config.identity_dir {
+            Some(ref dir) => {
+                let directory = IdentityDirectory::open(dir, agent_id).map_err(|e| {
+                    NetworkError::ConfigError(format!(
+                        "Failed to load agent identity from '{}': {}",
+                        dir.display(),
+                        e
+                    ))
+                })?;
+                (
+                    Some(directory.identity),
+                    Some(Mutex::new(directory.trust_store)),
+                )
+            }
+            None => (None, None),
+        };
+
+        Ok(Self {
+            compression_threshold: config.compression_threshold,
+            session: config.session.clone(),
+            swarm_key: config.swarm_key.clone(),
+            cipher: config.cipher.clone(),
+            rejected_signatures: AtomicU64::new(0),
+            identity,
+            trust_store,
+            replay_guard: config
+                .replay_window
+                .map(|window| Mutex::new(ReplayGuard::new(window))),
+            unsupported_senders: Mutex::new(LruCache::new(
+                NonZeroUsize::new(UNSUPPORTED_SENDER_CAPACITY).expect("capacity must be non-zero"),
+            )),
+        })
+    }
+
+    /// Number of datagrams rejected so far because of a missing or bad signature
+    pub fn rejected_signature_count(&self) -> u64 {
+        self.rejected_signatures.load(Ordering::Relaxed)
+    }
+
+    /// Counters of messages rejected as stale or replayed
+    pub fn replay_counters(&self) -> ReplayCounters {
+        self.replay_guard
+            .as_ref()
+            .map(|guard| guard.lock().expect("replay guard lock poisoned").counters())
+            .unwrap_or_default()
+    }
+
+    /// Sign a datagram with the swarm key, if one is configured
+    pub fn sign_datagram(&self, datagram: &mut Datagram) {
+        if let Some(ref swarm_key) = self.swarm_key {
+            swarm_key.sign(datagram);
+        }
+    }
+
+    /// Reject datagrams that were not signed with our swarm key
+    pub fn verify_datagram(
+        &self,
+        datagram: &Datagram,
+        origin: impl Display,
+    ) -> Result<(), NetworkError> {
+        if let Some(ref swarm_key) = self.swarm_key
+            && !swarm_key.verify(datagram)
+        {
+            let rejected = self.rejected_signatures.fetch_add(1, Ordering::Relaxed) + 1;
+            let error_msg = format!(
+                "Rejected datagram from {} claiming message {} with a missing or invalid signature ({} rejected so far)",
+                origin, datagram.message_id, rejected
+            );
+            tracing::warn!("{}", error_msg);
+            return Err(NetworkError::AuthenticationError(error_msg));
+        }
+        Ok(())
+    }
+
+    /// Compress, sign and (optionally) encrypt a message into a serialized frame
+    pub fn encode(&self, message: &AgentMessage) -> Result<Vec<u8>, NetworkError> {
+        // Convert to compressed message based on threshold
+        let mut compressed_message = message
+            .to_compressed(self.compression_threshold)
+            .map_err(|e| NetworkError::ConfigError(format!("Failed to compress message: {}", e)))?;
+
+        // The session belongs to the connection, so messages cannot leak into another one
+        compressed_message.session = self.session.clone();
+
+        // Serialize the compressed message using protobuf
+        let serialized = compressed_message
+            .serialize()
+            .map_err(NetworkError::SerializationError)?;
+
+        // Attach this agent's signature so peers can tell it apart from impostors
+        let signed = match self.identity {
+            Some(ref identity) => identity.sign(serialized),
+            None => SignedMessage {
+                message: serialized,
+                ..Default::default()
+            },
+        };
+
+        // Compression already happened above, so encrypting last keeps the ciphertext small
+        let body = match self.cipher {
+            Some(ref cipher) => FrameBody::Sealed(
+                cipher
+                    .seal(&signed.encode_to_vec())
+                    .map_err(NetworkError::SendError)?,
+            ),
+            None => FrameBody::Signed(signed),
+        };
+
+        tracing::debug!(
+            "Encoded message {} from agent {} (encoding: {:?}, original size: {})",
+            message.message_id,
+            message.sender_id,
+            compressed_message.encoding,
+            compressed_message.original_size
+        );
+        Ok(Frame { body: Some(body) }.encode_to_vec())
+    }
+
+    /// Decode a serialized frame, verifying the sender's identity and decompressing the body if needed
+    pub fn decode(
+        &self,
+        serialized: &[u8],
+        origin: impl Display,
+    ) -> Result<AgentMessage, NetworkError> {
+        let frame = Frame::decode(serialized).map_err(|e| {
+            tracing::warn!("Failed to decode frame from {}: {}", origin, e);
+            NetworkError::DeserializationError(e)
+        })?;
+
+        let signed = match (frame.body, &self.cipher) {
+            (Some(FrameBody::Signed(signed)), None) => signed,
+            (Some(FrameBody::Sealed(sealed)), Some(cipher)) => {
+                let plaintext = cipher
+                    .open(&sealed)
+                    .map_err(NetworkError::DecryptionError)?;
+                SignedMessage::decode(plaintext.as_slice())
+                    .map_err(NetworkError::DeserializationError)?
+            }
+            (Some(FrameBody::Signed(_)), Some(_)) => {
+                return Err(NetworkError::DecryptionError(format!(
+                    "Ignoring unencrypted message from {} because encryption is required",
+                    origin
+                )));
+            }
+            (Some(FrameBody::Sealed(_)), None) => {
+                return Err(NetworkError::DecryptionError(format!(
+                    "Ignoring encrypted message from {} because no swarm passphrase is configured",
+                    origin
+                )));
+            }
+            (None, _) => {
+                return Err(NetworkError::DeserializationError(prost::DecodeError::new(
+                    format!("Empty frame from {}", origin),
+                )));
+            }
+        };
+
+        // The encoding is carried explicitly on the wire, so no content sniffing is needed
+        let compressed_message = match CompressedAgentMessage::deserialize(&signed.message) {
+            Ok(compressed_message) => compressed_message,
+            Err(e) => {
+                if let Some(sender_id) = e.unsupported_sender() {
+                    // A mixed-version swarm is expected during upgrades, so only warn once per sender
+                    let first_report = self
+                        .unsupported_senders
+                        .lock()
+                        .expect("unsupported sender lock poisoned")
+                        .put(sender_id.to_string(), ())
+                        .is_none();
+                    if first_report {
+                        tracing::warn!("Ignoring messages from {}: {}", origin, e);
+                    } else {
+                        tracing::debug!("Ignoring message from {}: {}", origin, e);
+                    }
+                    return Err(NetworkError::UnsupportedProtocol(e.to_string()));
+                }
+
+                let error_msg = format!("Failed to deserialize message from {}: {}", origin, e);
+                tracing::warn!("{}", error_msg);
+                return Err(NetworkError::DeserializationError(prost::DecodeError::new(
+                    error_msg,
+                )));
+            }
+        };
+
+        // Check the signature against the key pinned for the claimed sender before decompressing
+        if let Some(ref trust_store) = self.trust_store {
+            trust_store
+                .lock()
+                .expect("trust store lock poisoned")
+                .verify(&compressed_message.sender_id, &signed)
+                .map_err(|e| {
+                    tracing::warn!("Rejected message from {}: {}", origin, e);
+                    NetworkError::IdentityError(e.to_string())
+                })?;
+        }
+
+        // Only authenticated messages reach the replay check, so forged ids cannot poison it
+        if let Some(ref replay_guard) = self.replay_guard {
+            replay_guard
+                .lock()
+                .expect("replay guard lock poisoned")
+                .check(
+                    &compressed_message.message_id,
+                    compressed_message.timestamp,
+                    now_millis(),
+                )
+                .map_err(|e| {
+                    tracing::warn!(
+                        "Rejected message from {} claiming to be '{}': {}",
+                        origin,
+                        compressed_message.sender_id,
+                        e
+                    );
+                    NetworkError::ReplayError(e.to_string())
+                })?;
+        }
+
+        let message = compressed_message.to_agent_message().map_err(|e| {
+            NetworkError::DeserializationError(prost::DecodeError::new(format!(
+                "Failed to decompress message: {}",
+                e
+            )))
+        })?;
+
+        tracing::debug!(
+            "Successfully deserialized message from agent {} (encoding: {:?}, original size: {}) with content: '{}'",
+            message.sender_id,
+            compressed_message.encoding,
+            compressed_message.original_size,
+            message.content.chars().take(50).collect::<String>()
+        );
+        Ok(message)
+    }
+}
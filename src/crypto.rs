@@ -0,0 +1,130 @@
+use crate::message::SealedMessage;
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+/// Environment variable that can hold the swarm passphrase instead of a passphrase file
+pub const SWARM_PASSPHRASE_ENV: &str = "CONCLAVE_SWARM_PASSPHRASE";
+
+/// Minimum accepted swarm passphrase length in characters
+pub const MIN_PASSPHRASE_LENGTH: usize = 12;
+
+/// Fixed salt so every agent derives the same key from the same passphrase
+const KEY_DERIVATION_SALT: &[u8] = b"conclave-swarm-encryption-v1";
+
+/// Length of the random XChaCha20-Poly1305 nonce in bytes
+const NONCE_LENGTH: usize = 24;
+
+/// Authenticated encryption of swarm traffic, keyed by a shared passphrase
+#[derive(Clone)]
+pub struct SwarmCipher {
+    cipher: XChaCha20Poly1305,
+}
+
+impl std::fmt::Debug for SwarmCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print key material
+        f.debug_struct("SwarmCipher")
+            .field("algorithm", &"XChaCha20-Poly1305")
+            .finish()
+    }
+}
+
+impl SwarmCipher {
+    /// Derive the swarm encryption key from a passphrase with Argon2id
+    pub fn from_passphrase(passphrase: &str) -> Result<Self, String> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+            return Err(format!(
+                "Swarm passphrase must be at least {} characters",
+                MIN_PASSPHRASE_LENGTH
+            ));
+        }
+
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), KEY_DERIVATION_SALT, &mut key)
+            .map_err(|e| format!("Failed to derive swarm key: {}", e))?;
+
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new(&Key::from(key)),
+        })
+    }
+
+    /// Encrypt a serialized message under a fresh random nonce
+    pub fn seal(&self, plaintext: &[u8]) -> Result<SealedMessage, String> {
+        // 192-bit random nonces make collisions negligible even with many agents sharing a key
+        let mut nonce = [0u8; NONCE_LENGTH];
+        getrandom::fill(&mut nonce).map_err(|e| format!("Failed to generate nonce: {}", e))?;
+
+        let ciphertext = self
+            .cipher
+            .encrypt(&XNonce::from(nonce), plaintext)
+            .map_err(|_| "Encryption failed".to_string())?;
+
+        Ok(SealedMessage {
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    /// Decrypt and authenticate a sealed message
+    pub fn open(&self, sealed: &SealedMessage) -> Result<Vec<u8>, String> {
+        let nonce: [u8; NONCE_LENGTH] = sealed
+            .nonce
+            .as_slice()
+            .try_into()
+            .map_err(|_| format!("Nonce must be {} bytes", NONCE_LENGTH))?;
+
+        self.cipher
+            .decrypt(&XNonce::from(nonce), sealed.ciphertext.as_slice())
+            .map_err(|_| "Decryption failed (wrong swarm passphrase or tampered message)".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let cipher = SwarmCipher::from_passphrase("correct horse battery staple").unwrap();
+
+        let sealed = cipher.seal(b"proprietary material").unwrap();
+        assert_eq!(sealed.nonce.len(), NONCE_LENGTH);
+        assert!(
+            !sealed
+                .ciphertext
+                .windows(11)
+                .any(|window| window == b"proprietary")
+        );
+
+        assert_eq!(cipher.open(&sealed).unwrap(), b"proprietary material");
+    }
+
+    #[test]
+    fn test_nonces_are_unique() {
+        let cipher = SwarmCipher::from_passphrase("correct horse battery staple").unwrap();
+
+        let first = cipher.seal(b"same plaintext").unwrap();
+        let second = cipher.seal(b"same plaintext").unwrap();
+        assert_ne!(first.nonce, second.nonce);
+        assert_ne!(first.ciphertext, second.ciphertext);
+    }
+
+    #[test]
+    fn test_wrong_passphrase_and_tampering_fail() {
+        let cipher = SwarmCipher::from_passphrase("correct horse battery staple").unwrap();
+        let other = SwarmCipher::from_passphrase("incorrect horse battery staple").unwrap();
+
+        let mut sealed = cipher.seal(b"proprietary material").unwrap();
+        assert!(other.open(&sealed).is_err());
+
+        sealed.ciphertext[0] ^= 0xFF;
+        assert!(cipher.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_short_passphrase_is_rejected() {
+        assert!(SwarmCipher::from_passphrase("short").is_err());
+    }
+}
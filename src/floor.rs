@@ -0,0 +1,286 @@
+use crate::message::{AgentMessage, ControlMessage, MessageKind, now_millis};
+use crate::network::NetworkError;
+use crate::transport::Transport;
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{debug, warn};
+
+/// Control command asking for the floor
+pub const FLOOR_REQUEST: &str = "floor-request";
+
+/// Control command giving the floor back
+pub const FLOOR_RELEASE: &str = "floor-release";
+
+/// How long a requester waits for competing requests before checking whose turn it is
+const SETTLE_TIME: Duration = Duration::from_millis(250);
+
+/// Pending floor requests, ordered the same way on every agent
+///
+/// Requests are ordered by the requester's timestamp, then agent id, so every
+/// agent that has seen the same requests agrees on who speaks next. The request
+/// at the head holds the floor until it is released, or until it has held it
+/// for longer than the timeout, so a crashed agent cannot block the swarm.
+struct FloorQueue {
+    requests: BTreeSet<(i64, String)>,
+    /// Request at the head of the queue and when it got there
+    head: Option<((i64, String), Instant)>,
+}
+
+impl FloorQueue {
+    fn new() -> Self {
+        Self {
+            requests: BTreeSet::new(),
+            head: None,
+        }
+    }
+
+    /// Queue a request, replacing any earlier one from the same agent
+    fn request(&mut self, agent_id: &str, requested_at: i64) {
+        self.release(agent_id);
+        self.requests.insert((requested_at, agent_id.to_string()));
+    }
+
+    /// Drop the request of `agent_id`, if any
+    fn release(&mut self, agent_id: &str) {
+        self.requests.retain(|(_, requester)| requester != agent_id);
+    }
+
+    /// The agent holding the floor, dropping holders that kept it past `timeout`
+    fn speaker(&mut self, now: Instant, timeout: Duration) -> Option<String> {
+        loop {
+            let first = self.requests.first()?.clone();
+            let since = match self.head {
+                Some((ref key, since)) if *key == first => since,
+                _ => {
+                    self.head = Some((first.clone(), now));
+                    now
+                }
+            };
+
+            if now.duration_since(since) <= timeout {
+                return Some(first.1);
+            }
+            warn!(
+                "Agent '{}' held the floor for more than {:?}, passing it on",
+                first.1, timeout
+            );
+            self.requests.remove(&first);
+        }
+    }
+
+    /// When the current holder's time runs out
+    fn deadline(&self, timeout: Duration) -> Option<Instant> {
+        self.head.as_ref().map(|(_, since)| *since + timeout)
+    }
+}
+
+/// Distributed turn-taking, so only one agent speaks at a time
+///
+/// An agent that wants to speak multicasts a floor request, waits briefly for
+/// competing requests, and speaks once its request is first in line. It then
+/// releases the floor so the next agent in line can speak.
+pub struct FloorControl {
+    transport: Arc<dyn Transport>,
+    agent_id: String,
+    /// Longest time one agent may hold the floor
+    timeout: Duration,
+    queue: Mutex<FloorQueue>,
+    /// Woken whenever the queue changes
+    changed: Notify,
+}
+
+impl FloorControl {
+    /// Create floor control for `agent_id`, sending requests over `transport`
+    pub fn new(transport: Arc<dyn Transport>, agent_id: String, timeout: Duration) -> Self {
+        Self {
+            transport,
+            agent_id,
+            timeout,
+            queue: Mutex::new(FloorQueue::new()),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Record a floor request or release seen on the network
+    ///
+    /// Returns false if the message is not about the floor.
+    pub fn observe(&self, message: &AgentMessage) -> bool {
+        let Some(MessageKind::Control(ref control)) = message.kind else {
+            return false;
+        };
+        if message.sender_id == self.agent_id {
+            // Our own requests are queued when they are sent
+            return matches!(control.command.as_str(), FLOOR_REQUEST | FLOOR_RELEASE);
+        }
+
+        let mut queue = self.queue.lock().expect("floor queue lock poisoned");
+        match control.command.as_str() {
+            FLOOR_REQUEST => queue.request(&message.sender_id, message.timestamp),
+            FLOOR_RELEASE => queue.release(&message.sender_id),
+            _ => return false,
+        }
+        drop(queue);
+
+        debug!("Received {} from '{}'", control.command, message.sender_id);
+        self.changed.notify_waiters();
+        true
+    }
+
+    /// Ask for the floor and wait until it is this agent's turn to speak
+    pub async fn acquire(&self) -> Result<(), NetworkError> {
+        let requested_at = now_millis();
+        self.queue
+            .lock()
+            .expect("floor queue lock poisoned")
+            .request(&self.agent_id, requested_at);
+        self.send(FLOOR_REQUEST, requested_at).await?;
+
+        // Requests sent at about the same time need a moment to reach everyone
+        tokio::time::sleep(SETTLE_TIME).await;
+
+        loop {
+            // Register interest before checking, so a change in between is not missed
+            let changed = self.changed.notified();
+
+            let (speaker, deadline) = {
+                let mut queue = self.queue.lock().expect("floor queue lock poisoned");
+                let speaker = queue.speaker(Instant::now(), self.timeout);
+                (speaker, queue.deadline(self.timeout))
+            };
+
+            match speaker {
+                Some(ref speaker) if *speaker != self.agent_id => {
+                    debug!("Waiting for '{}' to give up the floor", speaker)
+                }
+                _ => return Ok(()),
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    let _ = tokio::time::timeout_at(deadline.into(), changed).await;
+                }
+                None => changed.await,
+            }
+        }
+    }
+
+    /// Give the floor back to the swarm
+    pub async fn release(&self) -> Result<(), NetworkError> {
+        self.queue
+            .lock()
+            .expect("floor queue lock poisoned")
+            .release(&self.agent_id);
+        self.changed.notify_waiters();
+        self.send(FLOOR_RELEASE, now_millis()).await
+    }
+
+    async fn send(&self, command: &str, timestamp: i64) -> Result<(), NetworkError> {
+        let message = AgentMessage {
+            timestamp,
+            ..AgentMessage::new_control(
+                self.agent_id.clone(),
+                ControlMessage {
+                    command: command.to_string(),
+                    ..Default::default()
+                },
+            )
+        };
+        self.transport.send(&message).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::NetworkConfig;
+    use crate::transport::{BUS_CAPACITY, MemoryTransport, MessageBus};
+
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn test_earliest_request_speaks_first() {
+        let mut queue = FloorQueue::new();
+        let now = Instant::now();
+        assert_eq!(queue.speaker(now, TIMEOUT), None);
+
+        queue.request("agent-b", 1000);
+        queue.request("agent-a", 1000);
+        queue.request("agent-c", 999);
+
+        // Earlier timestamps go first, ties are broken by agent id
+        assert_eq!(queue.speaker(now, TIMEOUT).as_deref(), Some("agent-c"));
+        queue.release("agent-c");
+        assert_eq!(queue.speaker(now, TIMEOUT).as_deref(), Some("agent-a"));
+        queue.release("agent-a");
+        assert_eq!(queue.speaker(now, TIMEOUT).as_deref(), Some("agent-b"));
+    }
+
+    #[test]
+    fn test_crashed_holder_loses_the_floor_after_timeout() {
+        let mut queue = FloorQueue::new();
+        let start = Instant::now();
+        queue.request("crashed", 1000);
+        queue.request("waiting", 2000);
+
+        assert_eq!(queue.speaker(start, TIMEOUT).as_deref(), Some("crashed"));
+        assert_eq!(queue.deadline(TIMEOUT), Some(start + TIMEOUT));
+
+        let later = start + TIMEOUT + Duration::from_millis(1);
+        assert_eq!(queue.speaker(later, TIMEOUT).as_deref(), Some("waiting"));
+
+        // The new holder gets a full timeout of its own
+        assert_eq!(queue.deadline(TIMEOUT), Some(later + TIMEOUT));
+    }
+
+    #[tokio::test]
+    async fn test_agents_take_turns() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let first: Arc<dyn Transport> =
+            Arc::new(MemoryTransport::new(&bus, &config, "first".to_string()).unwrap());
+        let second: Arc<dyn Transport> =
+            Arc::new(MemoryTransport::new(&bus, &config, "second".to_string()).unwrap());
+
+        let first_floor = Arc::new(FloorControl::new(
+            Arc::clone(&first),
+            "first".to_string(),
+            TIMEOUT,
+        ));
+        let second_floor = Arc::new(FloorControl::new(
+            Arc::clone(&second),
+            "second".to_string(),
+            TIMEOUT,
+        ));
+
+        // Stand-ins for the intake tasks feeding control messages to floor control
+        for (transport, floor) in [
+            (Arc::clone(&first), Arc::clone(&first_floor)),
+            (Arc::clone(&second), Arc::clone(&second_floor)),
+        ] {
+            tokio::spawn(async move {
+                while let Ok(message) = transport.recv().await {
+                    floor.observe(&message);
+                }
+            });
+        }
+
+        first_floor.acquire().await.unwrap();
+
+        // The second agent has to wait until the first one is done
+        let waiting = {
+            let second_floor = Arc::clone(&second_floor);
+            tokio::spawn(async move { second_floor.acquire().await })
+        };
+        tokio::time::sleep(SETTLE_TIME * 2).await;
+        assert!(!waiting.is_finished());
+
+        first_floor.release().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(2), waiting)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+use crate::message::{AgentMessage, AgentStatus, StatusMessage};
+use clap::ValueEnum;
+use std::time::{Duration, Instant};
+
+/// Polite stand-in for a reply the LLM failed to give
+pub const FALLBACK_REPLY: &str =
+    "I need a moment to gather my thoughts. Please go on without me for now.";
+
+/// What an agent says in the conversation when its LLM fails to answer
+///
+/// Peers are always sent a status message; this decides whether a chat
+/// message goes with it. The error text itself is never sent as chat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ErrorReply {
+    /// Say nothing
+    #[default]
+    Silent,
+    /// Answer with a polite fallback reply
+    Fallback,
+    /// Announce that this agent is unavailable
+    Announce,
+}
+
+impl ErrorReply {
+    /// Chat text `agent_id` sends after a failure, if any
+    pub fn text(&self, agent_id: &str) -> Option<String> {
+        match self {
+            ErrorReply::Silent => None,
+            ErrorReply::Fallback => Some(FALLBACK_REPLY.to_string()),
+            ErrorReply::Announce => Some(format!(
+                "{} is unavailable right now and will rejoin when it can.",
+                agent_id
+            )),
+        }
+    }
+}
+
+/// Counts failed LLM requests and trips the agent into a degraded state
+///
+/// After `degraded_after` failures in a row the agent is degraded and stops
+/// asking its LLM for `cooldown`, so a broken provider is not hammered with
+/// requests that would fail too. Once the cooldown is over one request is let
+/// through; if it succeeds the agent recovers, otherwise the cooldown restarts.
+#[derive(Debug)]
+pub struct Health {
+    degraded_after: u32,
+    cooldown: Duration,
+    /// LLM requests that failed in a row
+    failures: u32,
+    /// When the agent last became, or stayed, degraded
+    degraded_since: Option<Instant>,
+}
+
+impl Health {
+    pub fn new(degraded_after: u32, cooldown: Duration) -> Self {
+        Self {
+            degraded_after,
+            cooldown,
+            failures: 0,
+            degraded_since: None,
+        }
+    }
+
+    /// Whether the LLM may be asked at `now`
+    pub fn allows_request(&self, now: Instant) -> bool {
+        match self.degraded_since {
+            Some(since) => now.duration_since(since) >= self.cooldown,
+            None => true,
+        }
+    }
+
+    /// Record a failed request, returning the status to report
+    pub fn record_failure(&mut self, detail: String, now: Instant) -> StatusMessage {
+        self.failures += 1;
+        let status = if self.failures >= self.degraded_after {
+            self.degraded_since = Some(now);
+            AgentStatus::Degraded
+        } else {
+            AgentStatus::Error
+        };
+        self.status(status, detail)
+    }
+
+    /// Status to report for a message a degraded agent does not answer
+    pub fn degraded_status(&self) -> StatusMessage {
+        self.status(AgentStatus::Degraded, "cooling down".to_string())
+    }
+
+    /// Record a successful request, returning a status to report if the agent recovered
+    pub fn record_success(&mut self) -> Option<StatusMessage> {
+        let recovered = self.degraded_since.take().is_some();
+        self.failures = 0;
+        recovered.then(|| self.status(AgentStatus::Ok, "recovered".to_string()))
+    }
+
+    fn status(&self, status: AgentStatus, detail: String) -> StatusMessage {
+        let mut message = StatusMessage {
+            detail,
+            consecutive_failures: self.failures,
+            ..Default::default()
+        };
+        message.set_status(status);
+        message
+    }
+}
+
+/// Describe a status report from `message` for the log
+pub fn describe(message: &AgentMessage, status: &StatusMessage) -> String {
+    match status.status() {
+        AgentStatus::Ok => format!("Agent '{}' has recovered", message.sender_id),
+        AgentStatus::Error => format!(
+            "Agent '{}' failed to answer ({}, {} in a row)",
+            message.sender_id, status.detail, status.consecutive_failures
+        ),
+        AgentStatus::Degraded => format!(
+            "Agent '{}' is degraded after {} failures in a row ({})",
+            message.sender_id, status.consecutive_failures, status.detail
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_repeated_failures_trip_the_degraded_state() {
+        let mut health = Health::new(3, COOLDOWN);
+        let start = Instant::now();
+        assert!(health.allows_request(start));
+
+        assert_eq!(
+            health
+                .record_failure("transient error".to_string(), start)
+                .status(),
+            AgentStatus::Error
+        );
+        assert_eq!(
+            health
+                .record_failure("transient error".to_string(), start)
+                .status(),
+            AgentStatus::Error
+        );
+        assert!(health.allows_request(start));
+
+        let status = health.record_failure("rate limited".to_string(), start);
+        assert_eq!(status.status(), AgentStatus::Degraded);
+        assert_eq!(status.consecutive_failures, 3);
+
+        // Degraded agents leave the LLM alone until the cooldown is over
+        assert!(!health.allows_request(start + COOLDOWN / 2));
+        let skipped = health.degraded_status();
+        assert_eq!(skipped.status(), AgentStatus::Degraded);
+        assert_eq!(skipped.consecutive_failures, 3);
+        assert!(health.allows_request(start + COOLDOWN));
+
+        // A failed probe restarts the cooldown
+        let later = start + COOLDOWN;
+        health.record_failure("rate limited".to_string(), later);
+        assert!(!health.allows_request(later + COOLDOWN / 2));
+
+        let recovered = health.record_success().unwrap();
+        assert_eq!(recovered.status(), AgentStatus::Ok);
+        assert_eq!(recovered.consecutive_failures, 0);
+        assert!(health.allows_request(later));
+
+        // Successes that recover nothing are not reported
+        assert!(health.record_success().is_none());
+    }
+
+    #[test]
+    fn test_error_replies() {
+        assert_eq!(ErrorReply::default().text("aff-1"), None);
+        assert_eq!(
+            ErrorReply::Fallback.text("aff-1").as_deref(),
+            Some(FALLBACK_REPLY)
+        );
+        assert!(
+            ErrorReply::Announce
+                .text("aff-1")
+                .unwrap()
+                .starts_with("aff-1 is unavailable")
+        );
+    }
+}
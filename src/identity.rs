@@ -0,0 +1,381 @@
+use crate::message::SignedMessage;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{error, info};
+
+/// File, inside the identity directory, holding trusted peer keys
+const KNOWN_AGENTS_FILE: &str = "known_agents";
+
+/// Identity-related error types
+#[derive(Error, Debug)]
+pub enum IdentityError {
+    #[error("Identity file error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid key material: {0}")]
+    InvalidKey(String),
+
+    #[error("Message from '{0}' is not signed")]
+    MissingSignature(String),
+
+    #[error("Message from '{0}' carries a fingerprint that does not match its public key")]
+    FingerprintMismatch(String),
+
+    #[error("Message from '{0}' has an invalid signature")]
+    BadSignature(String),
+
+    #[error(
+        "Identity conflict for '{sender_id}': trusted key {trusted_fingerprint}, message signed by {presented_fingerprint}"
+    )]
+    Conflict {
+        sender_id: String,
+        trusted_fingerprint: String,
+        presented_fingerprint: String,
+    },
+}
+
+/// Lowercase hex encoding
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode lowercase or uppercase hex into exactly N bytes
+fn from_hex<const N: usize>(hex: &str) -> Result<[u8; N], IdentityError> {
+    let hex = hex.trim();
+    if hex.len() != N * 2 || !hex.is_ascii() {
+        return Err(IdentityError::InvalidKey(format!(
+            "expected {} hex characters, got {}",
+            N * 2,
+            hex.len()
+        )));
+    }
+
+    let mut bytes = [0u8; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|e| IdentityError::InvalidKey(e.to_string()))?;
+    }
+    Ok(bytes)
+}
+
+/// Short, human-readable fingerprint of a public key (first 16 bytes of its SHA-256)
+pub fn fingerprint(public_key: &VerifyingKey) -> String {
+    to_hex(&Sha256::digest(public_key.as_bytes())[..16])
+}
+
+/// This agent's Ed25519 keypair
+#[derive(Clone)]
+pub struct AgentIdentity {
+    signing_key: SigningKey,
+}
+
+impl std::fmt::Debug for AgentIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Only the fingerprint is safe to print
+        f.debug_struct("AgentIdentity")
+            .field("fingerprint", &self.fingerprint())
+            .finish()
+    }
+}
+
+impl AgentIdentity {
+    /// Load the keypair from `path`, generating and storing a new one on first run
+    pub fn load_or_generate(path: &Path) -> Result<Self, IdentityError> {
+        if path.exists() {
+            let seed = from_hex::<32>(&fs::read_to_string(path)?)?;
+            let identity = Self {
+                signing_key: SigningKey::from_bytes(&seed),
+            };
+            info!(
+                "Loaded agent identity {} from '{}'",
+                identity.fingerprint(),
+                path.display()
+            );
+            return Ok(identity);
+        }
+
+        let mut seed = [0u8; 32];
+        getrandom::fill(&mut seed)
+            .map_err(|e| IdentityError::InvalidKey(format!("failed to generate key: {}", e)))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600); // Private key must not be world-readable
+        }
+        let mut file = options.open(path)?;
+        writeln!(file, "{}", to_hex(&seed))?;
+
+        let identity = Self {
+            signing_key: SigningKey::from_bytes(&seed),
+        };
+        info!(
+            "Generated new agent identity {} at '{}'",
+            identity.fingerprint(),
+            path.display()
+        );
+        Ok(identity)
+    }
+
+    /// Fingerprint of this agent's public key
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.signing_key.verifying_key())
+    }
+
+    /// Sign a serialized message
+    pub fn sign(&self, message: Vec<u8>) -> SignedMessage {
+        let public_key = self.signing_key.verifying_key();
+        let signature = self.signing_key.sign(&message);
+
+        SignedMessage {
+            message,
+            public_key: public_key.to_bytes().to_vec(),
+            key_fingerprint: fingerprint(&public_key),
+            signature: signature.to_bytes().to_vec(),
+        }
+    }
+}
+
+/// Trust-on-first-use map of sender ids to their public keys
+pub struct TrustStore {
+    trusted: HashMap<String, VerifyingKey>,
+    /// File that newly trusted keys are appended to, if persistent
+    path: Option<PathBuf>,
+}
+
+impl TrustStore {
+    /// Load trusted keys from `path` (one "sender_id public_key_hex" per line)
+    /// Newly trusted keys are appended to the same file
+    pub fn load(path: &Path) -> Result<Self, IdentityError> {
+        let mut store = Self {
+            trusted: HashMap::new(),
+            path: Some(path.to_path_buf()),
+        };
+
+        if path.exists() {
+            for line in fs::read_to_string(path)?.lines() {
+                let Some((sender_id, public_key_hex)) = line.trim().split_once(' ') else {
+                    continue;
+                };
+                let public_key = VerifyingKey::from_bytes(&from_hex::<32>(public_key_hex)?)
+                    .map_err(|e| IdentityError::InvalidKey(e.to_string()))?;
+                store.trusted.insert(sender_id.to_string(), public_key);
+            }
+        }
+
+        info!(
+            "Loaded {} trusted agent key(s) from '{}'",
+            store.trusted.len(),
+            path.display()
+        );
+        Ok(store)
+    }
+
+    /// Verify a signed message claiming to come from `sender_id`
+    /// The first key seen for a sender is trusted; any other key is rejected as a conflict
+    pub fn verify(
+        &mut self,
+        sender_id: &str,
+        signed: &SignedMessage,
+    ) -> Result<(), IdentityError> {
+        if signed.signature.is_empty() || signed.public_key.is_empty() {
+            return Err(IdentityError::MissingSignature(sender_id.to_string()));
+        }
+
+        let public_key_bytes: [u8; 32] = signed
+            .public_key
+            .as_slice()
+            .try_into()
+            .map_err(|_| IdentityError::InvalidKey("public key must be 32 bytes".to_string()))?;
+        let public_key = VerifyingKey::from_bytes(&public_key_bytes)
+            .map_err(|e| IdentityError::InvalidKey(e.to_string()))?;
+
+        let presented_fingerprint = fingerprint(&public_key);
+        if presented_fingerprint != signed.key_fingerprint {
+            return Err(IdentityError::FingerprintMismatch(sender_id.to_string()));
+        }
+
+        let signature = Signature::from_slice(&signed.signature)
+            .map_err(|_| IdentityError::BadSignature(sender_id.to_string()))?;
+        public_key
+            .verify_strict(&signed.message, &signature)
+            .map_err(|_| IdentityError::BadSignature(sender_id.to_string()))?;
+
+        match self.trusted.get(sender_id) {
+            Some(trusted) if *trusted == public_key => Ok(()),
+            Some(trusted) => {
+                let conflict = IdentityError::Conflict {
+                    sender_id: sender_id.to_string(),
+                    trusted_fingerprint: fingerprint(trusted),
+                    presented_fingerprint,
+                };
+                error!("POSSIBLE IMPERSONATION: {}", conflict);
+                Err(conflict)
+            }
+            None => {
+                info!(
+                    "Trusting new agent '{}' with key {} on first use",
+                    sender_id, presented_fingerprint
+                );
+                self.trust(sender_id, public_key)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Record a trusted key, persisting it if the store is file-backed
+    fn trust(&mut self, sender_id: &str, public_key: VerifyingKey) -> Result<(), IdentityError> {
+        if let Some(path) = &self.path {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            writeln!(file, "{} {}", sender_id, to_hex(public_key.as_bytes()))?;
+        }
+
+        self.trusted.insert(sender_id.to_string(), public_key);
+        Ok(())
+    }
+}
+
+/// Identity material loaded from an identity directory
+pub struct IdentityDirectory {
+    pub identity: AgentIdentity,
+    pub trust_store: TrustStore,
+}
+
+impl IdentityDirectory {
+    /// Load (or create on first run) the keypair for `agent_id` and the shared trust store in `dir`
+    pub fn open(dir: &Path, agent_id: &str) -> Result<Self, IdentityError> {
+        let identity = AgentIdentity::load_or_generate(&dir.join(format!("{}.key", agent_id)))?;
+        let trust_store = TrustStore::load(&dir.join(KNOWN_AGENTS_FILE))?;
+
+        Ok(Self {
+            identity,
+            trust_store,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_memory_store() -> TrustStore {
+        TrustStore {
+            trusted: HashMap::new(),
+            path: None,
+        }
+    }
+
+    #[test]
+    fn test_identity_is_generated_once_and_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.key");
+
+        let first = AgentIdentity::load_or_generate(&path).unwrap();
+        assert!(path.exists());
+
+        let second = AgentIdentity::load_or_generate(&path).unwrap();
+        assert_eq!(first.fingerprint(), second.fingerprint());
+        assert_eq!(first.fingerprint().len(), 32);
+    }
+
+    #[test]
+    fn test_sign_and_verify_trusts_on_first_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let identity = AgentIdentity::load_or_generate(&dir.path().join("a.key")).unwrap();
+        let mut store = in_memory_store();
+
+        let signed = identity.sign(b"hello".to_vec());
+        assert!(store.verify("agent-a", &signed).is_ok());
+        assert!(store.verify("agent-a", &signed).is_ok());
+    }
+
+    #[test]
+    fn test_tampered_message_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let identity = AgentIdentity::load_or_generate(&dir.path().join("a.key")).unwrap();
+        let mut store = in_memory_store();
+
+        let mut signed = identity.sign(b"hello".to_vec());
+        signed.message = b"goodbye".to_vec();
+        assert!(matches!(
+            store.verify("agent-a", &signed),
+            Err(IdentityError::BadSignature(_))
+        ));
+
+        let mut unsigned = identity.sign(b"hello".to_vec());
+        unsigned.signature.clear();
+        assert!(matches!(
+            store.verify("agent-a", &unsigned),
+            Err(IdentityError::MissingSignature(_))
+        ));
+
+        let mut wrong_fingerprint = identity.sign(b"hello".to_vec());
+        wrong_fingerprint.key_fingerprint = "0".repeat(32);
+        assert!(matches!(
+            store.verify("agent-a", &wrong_fingerprint),
+            Err(IdentityError::FingerprintMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_impersonation_is_a_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let judge = AgentIdentity::load_or_generate(&dir.path().join("judge.key")).unwrap();
+        let impostor = AgentIdentity::load_or_generate(&dir.path().join("impostor.key")).unwrap();
+        let mut store = in_memory_store();
+
+        assert!(store.verify("judge", &judge.sign(b"RFD".to_vec())).is_ok());
+
+        // A validly signed message under a different key cannot claim the judge's id
+        match store.verify("judge", &impostor.sign(b"RFD".to_vec())) {
+            Err(IdentityError::Conflict {
+                sender_id,
+                trusted_fingerprint,
+                presented_fingerprint,
+            }) => {
+                assert_eq!(sender_id, "judge");
+                assert_eq!(trusted_fingerprint, judge.fingerprint());
+                assert_eq!(presented_fingerprint, impostor.fingerprint());
+            }
+            other => panic!("Expected Conflict, got {:?}", other),
+        }
+
+        // But the impostor can still speak under its own id
+        assert!(store.verify("impostor", &impostor.sign(b"hi".to_vec())).is_ok());
+    }
+
+    #[test]
+    fn test_trust_store_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let judge = AgentIdentity::load_or_generate(&dir.path().join("judge.key")).unwrap();
+        let impostor = AgentIdentity::load_or_generate(&dir.path().join("impostor.key")).unwrap();
+
+        let mut store = IdentityDirectory::open(dir.path(), "observer")
+            .unwrap()
+            .trust_store;
+        assert!(store.verify("judge", &judge.sign(b"RFD".to_vec())).is_ok());
+
+        // After a restart the previously trusted key is still pinned
+        let mut reloaded = IdentityDirectory::open(dir.path(), "observer")
+            .unwrap()
+            .trust_store;
+        assert!(matches!(
+            reloaded.verify("judge", &impostor.sign(b"RFD".to_vec())),
+            Err(IdentityError::Conflict { .. })
+        ));
+    }
+}
@@ -1,9 +1,11 @@
 use anyhow::{Result, anyhow};
 use llm::{
-    LLMProvider,
+    FunctionCall, LLMProvider, ToolCall,
     builder::{LLMBackend, LLMBuilder},
     chat::ChatMessage,
+    error::LLMError,
 };
+use std::sync::Arc;
 use tracing::debug;
 
 use elevenlabs_rs::endpoints::genai::tts::{TextToSpeech, TextToSpeechBody};
@@ -11,17 +13,66 @@ use elevenlabs_rs::utils::play;
 use elevenlabs_rs::{DefaultVoice, ElevenLabsClient, Model};
 
 // Import project-specific types
+use crate::cassette::CassetteProvider;
 use crate::cli::{AgentArgs, LLMBackend as CliBackend};
+use crate::mock::MockProvider;
+use crate::retry::RetryPolicy;
+use crate::tools::{ToolOutcome, ToolRegistry};
+use crate::transcript::{PromptMessage, PromptRole};
+
+/// Most rounds of tool calls before the LLM has to answer
+const MAX_TOOL_ROUNDS: usize = 5;
+
+/// A response together with the tool calls that led to it
+pub struct Answer {
+    pub content: String,
+    pub tool_outcomes: Vec<ToolOutcome>,
+}
 
 /// Common LLM module for handling different backends
 pub struct LLMModule {
     provider: Box<dyn LLMProvider>,
     elevenlabs_client: Option<ElevenLabsClient>,
+    /// Tools the LLM may call while answering, shared with the blocking tasks that run them
+    tools: Arc<ToolRegistry>,
 }
 
 impl LLMModule {
     /// Creates a new LLM module instance based on command-line arguments
     pub fn new(args: &AgentArgs) -> Result<Self> {
+        let elevenlabs_client = if args.voice {
+            Some(ElevenLabsClient::from_env().map_err(|e| anyhow!("ElevenLabsClient: {e}"))?)
+        } else {
+            None
+        };
+
+        // Get personality prompt (either from inline flag or file)
+        let personality = args
+            .get_personality()
+            .map_err(|e| anyhow!("Failed to load personality: {}", e))?;
+
+        debug!("Personality: {}", personality);
+
+        // Wrap the provider in a cassette when recording or replaying
+        let provider: Box<dyn LLMProvider> = match (&args.record, &args.replay) {
+            (_, Some(cassette)) => Box::new(CassetteProvider::replay(cassette, &personality)?),
+            (Some(cassette), None) => Box::new(CassetteProvider::record(
+                Self::build_provider(args, &personality)?,
+                cassette,
+                &personality,
+            )?),
+            (None, None) => Self::build_provider(args, &personality)?,
+        };
+
+        Ok(Self {
+            provider,
+            elevenlabs_client,
+            tools: Arc::new(args.get_tool_registry()?),
+        })
+    }
+
+    /// Builds the provider for the configured backend
+    fn build_provider(args: &AgentArgs, personality: &str) -> Result<Box<dyn LLMProvider>> {
         let mut builder = LLMBuilder::new();
 
         // Map project backend to provider backend
@@ -31,6 +82,14 @@ impl LLMModule {
             CliBackend::Google => LLMBackend::Google,
             CliBackend::Local => LLMBackend::Ollama,
             CliBackend::OpenRouter => LLMBackend::OpenRouter,
+            CliBackend::Mock => {
+                // Answer from the mock script, or echo without one
+                let provider = match &args.script {
+                    Some(script) => MockProvider::from_file(script, &args.agent_id)?,
+                    None => MockProvider::echo(&args.agent_id),
+                };
+                return Ok(Box::new(provider));
+            }
         };
 
         builder = builder.backend(backend);
@@ -40,28 +99,14 @@ impl LLMModule {
             builder = builder.api_key(key);
         }
 
-        let elevenlabs_client = if args.voice {
-            Some(ElevenLabsClient::from_env().map_err(|e| anyhow!("ElevenLabsClient: {e}"))?)
-        } else {
-            None
-        };
-
-        // Get personality prompt (either from inline flag or file)
-        let personality = args
-            .get_personality()
-            .map_err(|e| anyhow!("Failed to load personality: {}", e))?;
-
-        debug!("Personality: {}", personality);
-
         // Configure common parameters
         builder = builder
             .model(&args.model)
             .timeout_seconds(args.timeout_seconds)
             .max_tokens(8192)
             .temperature(0.7)
-            .sliding_window_with_strategy(20, llm::memory::TrimStrategy::Summarize)
             // set the system message for the LLM to the personality prompt
-            .system(&personality);
+            .system(personality);
 
         // Set custom endpoint if provided.
         // The underlying `llm` crate uses `Url::join()` to append paths like
@@ -82,12 +127,7 @@ impl LLMModule {
             builder = builder.base_url(&normalised);
         }
 
-        let provider = builder.build()?;
-
-        Ok(Self {
-            provider,
-            elevenlabs_client,
-        })
+        Ok(builder.build()?)
     }
 
     /// Generates a response based on the provided message history
@@ -97,11 +137,108 @@ impl LLMModule {
         Ok(response.to_string())
     }
 
+    /// Generates a response, running the tools the LLM calls along the way
+    ///
+    /// Tool results are fed back to the LLM until it answers in text. Each
+    /// request is retried by `retry_policy` on its own, so a retry neither
+    /// repeats earlier tool rounds nor loses their outcomes.
+    pub async fn generate_llm_response_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        retry_policy: &RetryPolicy,
+    ) -> Result<Answer> {
+        if self.tools.is_empty() {
+            return Ok(Answer {
+                content: retry_policy
+                    .run(|| self.generate_llm_response(messages))
+                    .await?,
+                tool_outcomes: Vec::new(),
+            });
+        }
+
+        let definitions = self.tools.definitions();
+        let mut messages = messages.to_vec();
+        let mut tool_outcomes = Vec::new();
+        for _ in 0..MAX_TOOL_ROUNDS {
+            debug!(
+                "Sending {:?} messages with {} tools.",
+                messages,
+                definitions.len()
+            );
+            let response = retry_policy
+                .run(|| async {
+                    Ok(self
+                        .provider
+                        .chat_with_tools(&messages, Some(&definitions))
+                        .await?)
+                })
+                .await?;
+            let calls = match response.tool_calls() {
+                Some(calls) if !calls.is_empty() => calls,
+                _ => {
+                    return Ok(Answer {
+                        content: response.to_string(),
+                        tool_outcomes,
+                    });
+                }
+            };
+
+            let mut results = Vec::new();
+            for call in &calls {
+                // Tools read and walk the filesystem, which must not stall the runtime
+                let tools = Arc::clone(&self.tools);
+                let (name, arguments) =
+                    (call.function.name.clone(), call.function.arguments.clone());
+                let outcome =
+                    tokio::task::spawn_blocking(move || tools.execute(&name, &arguments)).await?;
+                debug!("Tool call {:?} returned {:?}", call, outcome);
+                results.push(ToolCall {
+                    function: FunctionCall {
+                        name: call.function.name.clone(),
+                        arguments: outcome.output.clone(),
+                    },
+                    ..call.clone()
+                });
+                tool_outcomes.push(outcome);
+            }
+            messages.push(
+                ChatMessage::assistant()
+                    .tool_use(calls)
+                    .content(response.text().unwrap_or_default())
+                    .build(),
+            );
+            messages.push(ChatMessage::user().tool_result(results).build());
+        }
+
+        // Asking again would only start the same rounds over, so this is not retried
+        Err(LLMError::InvalidRequest(format!(
+            "No answer after {} rounds of tool calls",
+            MAX_TOOL_ROUNDS
+        ))
+        .into())
+    }
+
     /// Create a user ChatMessage from content
     pub fn create_user_message(&self, content: &str) -> ChatMessage {
         ChatMessage::user().content(content).build()
     }
 
+    /// Create an assistant ChatMessage from content
+    pub fn create_assistant_message(&self, content: &str) -> ChatMessage {
+        ChatMessage::assistant().content(content).build()
+    }
+
+    /// Create ChatMessages from a multi-turn prompt
+    pub fn create_chat_messages(&self, prompt: &[PromptMessage]) -> Vec<ChatMessage> {
+        prompt
+            .iter()
+            .map(|message| match message.role {
+                PromptRole::User => self.create_user_message(&message.content),
+                PromptRole::Assistant => self.create_assistant_message(&message.content),
+            })
+            .collect()
+    }
+
     pub async fn say(&self, response: &str) -> Result<()> {
         let body = TextToSpeechBody::new(response).with_model_id(Model::ElevenTurboV2_5);
 
@@ -121,3 +258,121 @@ impl LLMModule {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::{ErrorClass, RetryPolicy};
+    use crate::tools::CalculatorTool;
+    use async_trait::async_trait;
+    use llm::{
+        chat::{ChatProvider, ChatResponse, Tool},
+        completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+        embedding::EmbeddingProvider,
+        models::ModelsProvider,
+        stt::SpeechToTextProvider,
+        tts::TextToSpeechProvider,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers every request with another calculator call
+    #[derive(Debug)]
+    struct ToolCallResponse;
+
+    impl std::fmt::Display for ToolCallResponse {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "calculator(1 + 1)")
+        }
+    }
+
+    impl ChatResponse for ToolCallResponse {
+        fn text(&self) -> Option<String> {
+            None
+        }
+
+        fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+            Some(vec![ToolCall {
+                id: "call-1".to_string(),
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name: "calculator".to_string(),
+                    arguments: r#"{"expression": "1 + 1"}"#.to_string(),
+                },
+            }])
+        }
+    }
+
+    /// A provider that never stops calling tools, counting its requests
+    struct ToolLoopProvider {
+        requests: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ChatProvider for ToolLoopProvider {
+        async fn chat_with_tools(
+            &self,
+            _messages: &[ChatMessage],
+            _tools: Option<&[Tool]>,
+        ) -> Result<Box<dyn ChatResponse>, LLMError> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            Ok(Box::new(ToolCallResponse))
+        }
+    }
+
+    #[async_trait]
+    impl CompletionProvider for ToolLoopProvider {
+        async fn complete(
+            &self,
+            _request: &CompletionRequest,
+        ) -> Result<CompletionResponse, LLMError> {
+            Err(LLMError::ProviderError("not supported".to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for ToolLoopProvider {
+        async fn embed(&self, _input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Err(LLMError::ProviderError("not supported".to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl SpeechToTextProvider for ToolLoopProvider {
+        async fn transcribe(&self, _audio: Vec<u8>) -> Result<String, LLMError> {
+            Err(LLMError::ProviderError("not supported".to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl TextToSpeechProvider for ToolLoopProvider {}
+
+    #[async_trait]
+    impl ModelsProvider for ToolLoopProvider {}
+
+    impl LLMProvider for ToolLoopProvider {}
+
+    #[tokio::test]
+    async fn test_endless_tool_calls_are_not_retried() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let mut tools = ToolRegistry::new();
+        tools.register(Box::new(CalculatorTool));
+        let module = LLMModule {
+            provider: Box::new(ToolLoopProvider {
+                requests: Arc::clone(&requests),
+            }),
+            elevenlabs_client: None,
+            tools: Arc::new(tools),
+        };
+
+        let error = module
+            .generate_llm_response_with_tools(
+                &[module.create_user_message("What is 1 + 1?")],
+                &RetryPolicy::default(),
+            )
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(ErrorClass::of(&error), ErrorClass::Fatal);
+        assert_eq!(requests.load(Ordering::SeqCst), MAX_TOOL_ROUNDS);
+    }
+}
@@ -1,16 +1,44 @@
 use clap::Parser;
 
+mod auth;
+mod ballot;
+mod cassette;
 mod cli;
+mod codec;
+mod crypto;
+mod floor;
+mod health;
+mod identity;
 pub mod llm;
+mod mentions;
 mod message;
 mod message_handler;
+mod mock;
+mod moderator;
 mod network;
+mod policy;
 mod processor;
+mod reliability;
+mod retry;
+mod replay;
+mod roster;
+mod session;
+mod tools;
+mod transcript;
+mod transport;
 mod validator;
 use crate::{
-    cli::AgentArgs, message_handler::MessageHandler, network::NetworkConfig, processor::Processor,
+    cli::{AgentArgs, RunMode},
+    message_handler::MessageHandler,
+    moderator::Moderator,
+    network::NetworkConfig,
+    processor::Processor,
+    transport::{
+        BUS_CAPACITY, MemoryTransport, MessageBus, TcpHub, TcpTransport, Transport, TransportKind,
+    },
 };
 use std::sync::Arc;
+use std::time::Duration;
 // We'll use the ChatMessage from the llm crate through our llm module
 
 use tracing::{Level, debug, error, info};
@@ -40,59 +68,179 @@ async fn main() -> anyhow::Result<()> {
 
     info!("Starting agent '{}' with args {:?}", args.agent_id, args);
 
-    // Validate LLM access (API key format) before building the provider
-    if let Err(e) = validator::validate_llm_access(&args) {
-        error!("LLM access validation failed: {}", e);
-        std::process::exit(1);
+    // The moderator only relays the debate and never talks to an LLM
+    if args.mode != RunMode::Moderator {
+        // Validate LLM access (API key format) before building the provider
+        if let Err(e) = validator::validate_llm_access(&args) {
+            error!("LLM access validation failed: {}", e);
+            std::process::exit(1);
+        }
+        info!("LLM access format validation passed");
+
+        // Probe the LLM provider with a test message to verify the token works
+        if let Err(e) = validator::validate_llm_connection(&args).await {
+            error!("LLM connection validation failed: {}", e);
+            std::process::exit(1);
+        }
+        info!("LLM connection validation passed");
     }
-    info!("LLM access format validation passed");
 
-    // Probe the LLM provider with a test message to verify the token works
-    if let Err(e) = validator::validate_llm_connection(&args).await {
-        error!("LLM connection validation failed: {}", e);
-        std::process::exit(1);
+    // Load the optional pre-shared swarm key used to sign messages
+    let swarm_key = args.get_swarm_key()?;
+    if swarm_key.is_some() {
+        info!("Swarm key loaded, messages will be signed and verified");
     }
-    info!("LLM connection validation passed");
 
-    // Initialize LLM module
-    let llm_module = llm::LLMModule::new(&args)?;
-    info!("LLM module initialized successfully");
+    // Derive the optional swarm encryption key from the passphrase
+    let cipher = match args.get_swarm_passphrase()? {
+        Some(passphrase) => {
+            let cipher = crypto::SwarmCipher::from_passphrase(&passphrase)
+                .map_err(|e| anyhow::anyhow!(e))?;
+            info!("Swarm passphrase loaded, all traffic will be encrypted");
+            Some(cipher)
+        }
+        None => None,
+    };
 
     // Create network configuration
     let network_config = NetworkConfig {
-        multicast_address: args.multicast_address,
-        interface: args.interface.clone(),
+        multicast_address: args.get_multicast_address(),
+        interfaces: args.interfaces.clone(),
+        multicast_hops: args.multicast_hops,
+        swarm_key,
+        identity_dir: args.identity_dir.clone(),
+        cipher,
+        replay_window: args.get_replay_window(),
+        delivery_mode: args.delivery_mode,
+        session: args.session.clone().unwrap_or_default(),
         buffer_size: 65536,          // 64KB buffer for better performance
         compression_threshold: 1024, // Compress messages larger than 1KB
+        ..NetworkConfig::default()
     };
 
-    // Initialize network manager
-    let network_manager =
-        Arc::new(network::NetworkManager::new(network_config, args.agent_id.clone()).await?);
-    info!("Network manager initialized successfully");
+    // Initialize the transport selected on the command line
+    let transport: Arc<dyn Transport> = match args.transport {
+        TransportKind::Udp => {
+            Arc::new(network::NetworkManager::new(network_config, args.agent_id.clone()).await?)
+        }
+        TransportKind::Tcp => {
+            let hub_address = args
+                .hub_address
+                .ok_or_else(|| anyhow::anyhow!("The TCP transport requires --hub-address"))?;
+
+            if args.serve_hub {
+                // This agent hosts the hub and joins it directly instead of over TCP
+                let listener = tokio::net::TcpListener::bind(hub_address).await?;
+                let bus = MessageBus::new(BUS_CAPACITY);
+                TcpHub::spawn(listener, bus.clone(), network_config.max_reassembly_bytes);
+                info!("Hosting TCP hub on {}", hub_address);
+                Arc::new(MemoryTransport::new(
+                    &bus,
+                    &network_config,
+                    args.agent_id.clone(),
+                )?)
+            } else {
+                Arc::new(
+                    TcpTransport::connect(hub_address, &network_config, args.agent_id.clone())
+                        .await?,
+                )
+            }
+        }
+    };
+    info!("{:?} transport initialized successfully", args.transport);
 
     let buffer_size = 100; // Buffer up to 1000 messages
 
-    let message_handler = Arc::new(MessageHandler::new(args.agent_id.clone(), buffer_size));
+    let message_handler = Arc::new(
+        MessageHandler::new(args.agent_id.clone(), buffer_size)
+            .with_role(args.role.clone())
+            .with_session(args.session.clone().unwrap_or_default()),
+    );
     debug!("Message handler initialized with MPSC channel");
 
-    let processor = Processor::new(
+    let mut processor = Processor::new(
         Arc::clone(&message_handler),
-        Arc::clone(&network_manager),
+        Arc::clone(&transport),
         args.agent_id.clone(),
         args.processing_delay_ms,
+    )
+    .with_transcript_limits(args.get_transcript_limits())
+    .with_response_policy(args.get_response_policy())
+    .with_retry_policy(args.get_retry_policy())
+    .with_error_reply(args.error_reply)
+    .with_degraded_state(
+        args.degraded_after,
+        Duration::from_secs(args.degraded_cooldown_seconds),
     );
+    if args.floor_control {
+        processor =
+            processor.with_floor_control(Duration::from_secs(args.floor_timeout_seconds));
+    }
+    if args.broadcast_tool_results {
+        processor = processor.with_tool_result_broadcast();
+    }
+    if args.mode == RunMode::Judge {
+        processor = processor.with_ballots(args.ballot_file.clone());
+    }
 
-    // Spawn UDP message intake task
-    let udp_intake_handle = processor.spawn_udp_intake_task().await;
-    info!("UDP message intake task spawned");
+    // Spawn presence task announcing this agent to the swarm
+    let heartbeat = args.get_heartbeat();
+    let presence_handle = processor
+        .spawn_presence_task(
+            heartbeat.clone(),
+            Duration::from_secs(args.heartbeat_interval_seconds),
+        )
+        .await;
+    info!("Presence task spawned");
+
+    if args.mode == RunMode::Moderator {
+        let mut moderator = Moderator::new(
+            Arc::clone(&transport),
+            args.agent_id.clone(),
+            args.words_per_minute,
+            Duration::from_secs(args.speech_timeout_seconds),
+        )
+        .with_session(args.session.clone().unwrap_or_default());
+
+        // The moderator leaves once the round is over
+        tokio::select! {
+            result = moderator.run() => match result {
+                Ok(()) => info!("Debate finished"),
+                Err(e) => error!("Moderator failed: {}", e),
+            },
+            _ = tokio::signal::ctrl_c() => info!("Shutting down, announcing departure"),
+        }
+        presence_handle.abort();
+        if let Err(e) = processor.announce_departure(heartbeat).await {
+            error!("Failed to announce departure: {}", e);
+        }
+        return Ok(());
+    }
+
+    // Initialize LLM module
+    let llm_module = llm::LLMModule::new(&args)?;
+    info!("LLM module initialized successfully");
+
+    // Spawn message intake task
+    let intake_handle = processor.spawn_intake_task().await;
+    info!("Message intake task spawned");
 
     // Spawn LLM processing task
     let llm_processing_handle = processor.spawn_llm_processing_task(llm_module).await;
     info!("LLM processing task spawned");
 
-    // Wait for tasks to complete (they run indefinitely)
-    tokio::select!(_ = udp_intake_handle => error!("UDP intake crashed."), _ = llm_processing_handle => error!("LLM processing crashed."));
+    // Wait for tasks to complete (they run indefinitely), or for Ctrl-C
+    tokio::select! {
+        _ = intake_handle => error!("Message intake crashed."),
+        _ = llm_processing_handle => error!("LLM processing crashed."),
+        _ = tokio::signal::ctrl_c() => {
+            info!("Shutting down, announcing departure");
+            presence_handle.abort();
+            if let Err(e) = processor.announce_departure(heartbeat).await {
+                error!("Failed to announce departure: {}", e);
+            }
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,65 @@
+/// Extract `@name` mentions from text, in order of first appearance
+///
+/// A name is made of letters, digits, `-`, `_` and `.`; trailing punctuation
+/// is not part of it. An `@` preceded by a name character (as in an email
+/// address) does not start a mention.
+pub fn parse_mentions(text: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        if c == '@' && !previous.is_some_and(is_name_char) {
+            let start = index + c.len_utf8();
+            let mut end = start;
+            while let Some(&(next_index, next)) = chars.peek() {
+                if !is_name_char(next) {
+                    break;
+                }
+                end = next_index + next.len_utf8();
+                chars.next();
+            }
+
+            let name = text[start..end].trim_end_matches(['.', '-', '_']);
+            if !name.is_empty() && !mentions.iter().any(|mention| mention == name) {
+                mentions.push(name.to_string());
+            }
+            previous = text[..end].chars().next_back();
+            continue;
+        }
+        previous = Some(c);
+    }
+    mentions
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mentions_are_found_in_order() {
+        assert_eq!(
+            parse_mentions("@judge, I put it to @agent-2: where is your evidence?"),
+            vec!["judge", "agent-2"]
+        );
+        assert!(parse_mentions("No one in particular.").is_empty());
+    }
+
+    #[test]
+    fn test_trailing_punctuation_and_repeats_are_ignored() {
+        assert_eq!(
+            parse_mentions("Well, @agent-2. And again, @agent-2!"),
+            vec!["agent-2"]
+        );
+        assert!(parse_mentions("A lone @ sign").is_empty());
+    }
+
+    #[test]
+    fn test_email_addresses_are_not_mentions() {
+        assert!(parse_mentions("Write to judge@example.com").is_empty());
+    }
+}
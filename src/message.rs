@@ -1,14 +1,59 @@
 use prost::Message;
 use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
 
-use base64::{Engine as _, engine::general_purpose::STANDARD};
-
-// Include the generated protobuf code
+// Include the generated protobuf code. The `AgentMessage.kind` oneof is generated
+// as `agent_message::agent_message`, which is fine for generated code.
+#[allow(clippy::module_inception)]
 pub mod agent_message {
     include!(concat!(env!("OUT_DIR"), "/agent_swarm.rs"));
 }
 
-pub use agent_message::AgentMessage;
+pub use agent_message::agent_message::Kind as MessageKind;
+pub use agent_message::envelope::Payload as EnvelopePayload;
+pub use agent_message::frame::Body as FrameBody;
+pub use agent_message::{
+    AgentMessage, AgentStatus, ChatMessage, ControlMessage, Datagram, Encoding, Envelope, Frame,
+    Heartbeat, Nack, SealedMessage, SignedMessage, StatusMessage, StructuredMessage,
+};
+
+/// Version of the envelope format this agent speaks
+///
+/// Bump it whenever a change would make older agents misread a message; agents
+/// drop envelopes carrying any other version instead of guessing at them.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Envelope decoding error types
+#[derive(Error, Debug)]
+pub enum EnvelopeError {
+    #[error("Malformed envelope: {0}")]
+    Decode(#[from] prost::DecodeError),
+
+    #[error(
+        "Agent '{sender_id}' speaks protocol version {version}, this agent speaks version {PROTOCOL_VERSION}"
+    )]
+    UnsupportedVersion { sender_id: String, version: u32 },
+
+    #[error("Message {message_id} from agent '{sender_id}' carries an unknown kind of payload")]
+    UnknownPayload {
+        sender_id: String,
+        message_id: String,
+    },
+
+    #[error("Unsupported message encoding: {0}")]
+    UnknownEncoding(i32),
+}
+
+impl EnvelopeError {
+    /// Sender of a well-formed envelope written for a protocol this agent does not speak
+    pub fn unsupported_sender(&self) -> Option<&str> {
+        match self {
+            EnvelopeError::UnsupportedVersion { sender_id, .. }
+            | EnvelopeError::UnknownPayload { sender_id, .. } => Some(sender_id),
+            _ => None,
+        }
+    }
+}
 
 /// Compression utilities for message content
 pub mod compression {
@@ -47,22 +92,98 @@ pub mod compression {
     pub fn should_compress(content: &str, threshold: usize) -> bool {
         content.len() > threshold
     }
+
+    /// Read the uncompressed size from the gzip trailer (ISIZE, RFC 1952)
+    pub fn gzip_original_size(compressed_data: &[u8]) -> usize {
+        match compressed_data.len().checked_sub(4) {
+            Some(start) => {
+                let mut isize = [0u8; 4];
+                isize.copy_from_slice(&compressed_data[start..]);
+                u32::from_le_bytes(isize) as usize
+            }
+            None => 0,
+        }
+    }
+}
+
+/// Current time in milliseconds since the Unix epoch
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Failed to get current time. Time to panic because this is a basic machine right.")
+        .as_millis() as i64
 }
 
 impl AgentMessage {
-    /// Create a new AgentMessage with the current timestamp
+    /// Create a new AgentMessage with the current timestamp (milliseconds since the Unix epoch)
     pub fn new(sender_id: String, content: String) -> Self {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect(
-                "Failed to get current time. Time to panic because this is a basic machine right.",
-            )
-            .as_secs() as i64;
+        let timestamp = now_millis();
+
+        Self {
+            sender_id,
+            timestamp,
+            content,
+            message_id: uuid::Uuid::new_v4().to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new AgentMessage answering the message with id `in_reply_to`
+    pub fn new_reply(sender_id: String, content: String, in_reply_to: String) -> Self {
+        Self {
+            in_reply_to: Some(in_reply_to),
+            ..Self::new(sender_id, content)
+        }
+    }
+
+    /// Create a new presence heartbeat from `sender_id`
+    pub fn new_heartbeat(sender_id: String, heartbeat: Heartbeat) -> Self {
+        Self {
+            kind: Some(MessageKind::Heartbeat(heartbeat)),
+            ..Self::new(sender_id, String::new())
+        }
+    }
+
+    /// Create a new control message from `sender_id`
+    pub fn new_control(sender_id: String, control: ControlMessage) -> Self {
+        Self {
+            kind: Some(MessageKind::Control(control)),
+            ..Self::new(sender_id, String::new())
+        }
+    }
+
+    /// Create a new structured message from `sender_id`
+    pub fn new_structured(sender_id: String, structured: StructuredMessage) -> Self {
+        Self {
+            kind: Some(MessageKind::Structured(structured)),
+            ..Self::new(sender_id, String::new())
+        }
+    }
+
+    /// Create a new status report from `sender_id`
+    pub fn new_status(sender_id: String, status: StatusMessage) -> Self {
+        Self {
+            kind: Some(MessageKind::Status(status)),
+            ..Self::new(sender_id, String::new())
+        }
+    }
+
+    /// Whether this message is meant for the agent with the given id and role
+    pub fn is_addressed_to(&self, agent_id: &str, role: &str) -> bool {
+        self.recipients.is_empty()
+            || self
+                .recipients
+                .iter()
+                .any(|recipient| recipient == agent_id || recipient == role)
+    }
 
+    /// Create a new AgentMessage with an explicit timestamp
+    pub fn new_with_timestamp(sender_id: String, timestamp: i64, content: String) -> Self {
         Self {
             sender_id,
             timestamp,
             content,
+            ..Default::default()
         }
     }
 
@@ -76,8 +197,13 @@ impl AgentMessage {
             Ok(CompressedAgentMessage {
                 sender_id: self.sender_id.clone(),
                 timestamp: self.timestamp,
+                message_id: self.message_id.clone(),
+                in_reply_to: self.in_reply_to.clone(),
+                recipients: self.recipients.clone(),
+                session: self.session.clone(),
+                kind: self.kind.clone(),
                 compressed_data,
-                is_compressed: true,
+                encoding: Encoding::Gzip,
                 original_size: self.content.len(),
             })
         } else {
@@ -85,23 +211,158 @@ impl AgentMessage {
             Ok(CompressedAgentMessage {
                 sender_id: self.sender_id.clone(),
                 timestamp: self.timestamp,
+                message_id: self.message_id.clone(),
+                in_reply_to: self.in_reply_to.clone(),
+                recipients: self.recipients.clone(),
+                session: self.session.clone(),
+                kind: self.kind.clone(),
                 compressed_data: self.content.as_bytes().to_vec(),
-                is_compressed: false,
+                encoding: Encoding::None,
                 original_size: self.content.len(),
             })
         }
     }
+}
+
+/// A message that can be either compressed or uncompressed
+pub struct CompressedAgentMessage {
+    pub sender_id: String,
+    pub timestamp: i64,
+    pub message_id: String,
+    pub in_reply_to: Option<String>,
+    /// Agent ids or roles the message is meant for; empty means everyone
+    pub recipients: Vec<String>,
+    /// Conversation the message belongs to; empty for the default session
+    pub session: String,
+    /// What the message carries besides chat text; `None` for chat
+    pub kind: Option<MessageKind>,
+    pub compressed_data: Vec<u8>,
+    pub encoding: Encoding,
+    pub original_size: usize,
+}
+
+impl CompressedAgentMessage {
+    /// Convert back to regular AgentMessage (decompress if needed)
+    pub fn to_agent_message(&self) -> Result<AgentMessage, Box<dyn std::error::Error>> {
+        let content = match self.encoding {
+            Encoding::Gzip => compression::decompress_content(&self.compressed_data)?,
+            Encoding::None => String::from_utf8(self.compressed_data.clone())?,
+        };
+
+        Ok(AgentMessage {
+            message_id: self.message_id.clone(),
+            in_reply_to: self.in_reply_to.clone(),
+            recipients: self.recipients.clone(),
+            session: self.session.clone(),
+            kind: self.kind.clone(),
+            ..AgentMessage::new_with_timestamp(self.sender_id.clone(), self.timestamp, content)
+        })
+    }
 
-    /// Serialize the message to bytes using protobuf
+    /// Serialize the compressed message as a versioned envelope
     pub fn serialize(&self) -> Result<Vec<u8>, prost::EncodeError> {
+        let payload = match self.kind {
+            Some(MessageKind::Heartbeat(ref heartbeat)) => {
+                EnvelopePayload::Presence(heartbeat.clone())
+            }
+            Some(MessageKind::Control(ref control)) => EnvelopePayload::Control(control.clone()),
+            Some(MessageKind::Structured(ref structured)) => {
+                EnvelopePayload::Structured(structured.clone())
+            }
+            Some(MessageKind::Status(ref status)) => EnvelopePayload::Status(status.clone()),
+            None => {
+                // Plain text travels in `content`, encoded bodies travel as raw bytes in `payload`
+                let mut chat = ChatMessage::default();
+                chat.set_encoding(self.encoding);
+                match self.encoding {
+                    Encoding::None => {
+                        chat.content = String::from_utf8_lossy(&self.compressed_data).to_string()
+                    }
+                    _ => chat.payload = self.compressed_data.clone(),
+                }
+                EnvelopePayload::Chat(chat)
+            }
+        };
+
+        let envelope = Envelope {
+            protocol_version: PROTOCOL_VERSION,
+            sender_id: self.sender_id.clone(),
+            timestamp: self.timestamp,
+            message_id: self.message_id.clone(),
+            in_reply_to: self.in_reply_to.clone(),
+            recipients: self.recipients.clone(),
+            session: self.session.clone(),
+            payload: Some(payload),
+        };
+
         let mut buf = Vec::new();
-        self.encode(&mut buf)?;
+        envelope.encode(&mut buf)?;
         Ok(buf)
     }
 
-    /// Deserialize bytes to AgentMessage using protobuf
-    pub fn deserialize(bytes: &[u8]) -> Result<Self, prost::DecodeError> {
-        Self::decode(bytes)
+    /// Deserialize an envelope, using the encoding carried on the wire
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        let envelope = Envelope::decode(bytes)?;
+
+        if envelope.protocol_version != PROTOCOL_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion {
+                sender_id: envelope.sender_id,
+                version: envelope.protocol_version,
+            });
+        }
+
+        // Payload kinds added by newer agents decode as an empty oneof
+        let (kind, chat) = match envelope.payload {
+            Some(EnvelopePayload::Chat(chat)) => (None, chat),
+            Some(EnvelopePayload::Presence(heartbeat)) => (
+                Some(MessageKind::Heartbeat(heartbeat)),
+                ChatMessage::default(),
+            ),
+            Some(EnvelopePayload::Control(control)) => {
+                (Some(MessageKind::Control(control)), ChatMessage::default())
+            }
+            Some(EnvelopePayload::Structured(structured)) => (
+                Some(MessageKind::Structured(structured)),
+                ChatMessage::default(),
+            ),
+            Some(EnvelopePayload::Status(status)) => {
+                (Some(MessageKind::Status(status)), ChatMessage::default())
+            }
+            None => {
+                return Err(EnvelopeError::UnknownPayload {
+                    sender_id: envelope.sender_id,
+                    message_id: envelope.message_id,
+                });
+            }
+        };
+
+        let encoding = Encoding::try_from(chat.encoding)
+            .map_err(|_| EnvelopeError::UnknownEncoding(chat.encoding))?;
+
+        let (compressed_data, original_size) = match encoding {
+            Encoding::None => {
+                let data = chat.content.into_bytes();
+                let size = data.len();
+                (data, size)
+            }
+            Encoding::Gzip => {
+                let size = compression::gzip_original_size(&chat.payload);
+                (chat.payload, size)
+            }
+        };
+
+        Ok(CompressedAgentMessage {
+            sender_id: envelope.sender_id,
+            timestamp: envelope.timestamp,
+            message_id: envelope.message_id,
+            in_reply_to: envelope.in_reply_to,
+            recipients: envelope.recipients,
+            session: envelope.session,
+            kind,
+            compressed_data,
+            encoding,
+            original_size,
+        })
     }
 }
 
@@ -109,6 +370,19 @@ impl AgentMessage {
 mod tests {
     use super::*;
 
+    /// Send a message through the envelope wire format and back
+    fn roundtrip(message: &AgentMessage, compression_threshold: usize) -> AgentMessage {
+        let bytes = message
+            .to_compressed(compression_threshold)
+            .expect("Failed to compress message")
+            .serialize()
+            .expect("Failed to serialize message");
+        CompressedAgentMessage::deserialize(&bytes)
+            .expect("Failed to deserialize message")
+            .to_agent_message()
+            .expect("Failed to decompress message")
+    }
+
     #[test]
     fn test_agent_message_creation() {
         let message = AgentMessage::new("agent-1".to_string(), "Hello, world!".to_string());
@@ -116,6 +390,75 @@ mod tests {
         assert_eq!(message.sender_id, "agent-1");
         assert_eq!(message.content, "Hello, world!");
         assert!(message.timestamp > 0);
+        // Millisecond precision: well past 2001-09-09 when counted in milliseconds
+        assert!(message.timestamp > 1_000_000_000_000);
+        assert!(uuid::Uuid::parse_str(&message.message_id).is_ok());
+        assert_eq!(message.in_reply_to, None);
+    }
+
+    #[test]
+    fn test_message_ids_are_unique() {
+        let first = AgentMessage::new("agent-1".to_string(), "Same".to_string());
+        let second = AgentMessage::new("agent-1".to_string(), "Same".to_string());
+        assert_ne!(first.message_id, second.message_id);
+    }
+
+    #[test]
+    fn test_reply_threading_survives_compression() {
+        let original = AgentMessage::new("agent-1".to_string(), "Question?".to_string());
+        let reply = AgentMessage::new_reply(
+            "agent-2".to_string(),
+            "Answer. ".repeat(50),
+            original.message_id.clone(),
+        );
+        assert_eq!(
+            reply.in_reply_to.as_deref(),
+            Some(original.message_id.as_str())
+        );
+
+        let received = roundtrip(&reply, 50);
+
+        assert_eq!(received.message_id, reply.message_id);
+        assert_eq!(received.in_reply_to, reply.in_reply_to);
+    }
+
+    #[test]
+    fn test_recipients_survive_serialization() {
+        let message = AgentMessage {
+            recipients: vec!["agent-2".to_string(), "judge".to_string()],
+            ..AgentMessage::new("agent-1".to_string(), "Over to you".to_string())
+        };
+
+        let received = roundtrip(&message, 1024);
+        assert_eq!(received.recipients, message.recipients);
+        assert!(received.is_addressed_to("agent-2", "debater"));
+        assert!(received.is_addressed_to("agent-3", "judge"));
+        assert!(!received.is_addressed_to("agent-3", "debater"));
+
+        // Messages without recipients are meant for everyone
+        assert!(
+            AgentMessage::new("agent-1".to_string(), "Hi".to_string())
+                .is_addressed_to("agent-3", "debater")
+        );
+    }
+
+    #[test]
+    fn test_heartbeat_survives_serialization() {
+        let heartbeat = AgentMessage::new_heartbeat(
+            "agent-1".to_string(),
+            Heartbeat {
+                model: "gpt-4".to_string(),
+                backend: "openai".to_string(),
+                role: "moderator".to_string(),
+                interval_ms: 5000,
+                leaving: false,
+            },
+        );
+
+        let received = roundtrip(&heartbeat, 1024);
+
+        assert_eq!(received.kind, heartbeat.kind);
+        assert!(received.content.is_empty());
     }
 
     #[test]
@@ -123,13 +466,7 @@ mod tests {
         let original =
             AgentMessage::new("test-agent".to_string(), "Test message content".to_string());
 
-        // Serialize the message
-        let serialized = original.serialize().expect("Failed to serialize message");
-        assert!(!serialized.is_empty());
-
-        // Deserialize the message
-        let deserialized =
-            AgentMessage::deserialize(&serialized).expect("Failed to deserialize message");
+        let deserialized = roundtrip(&original, 1024);
 
         // Verify all fields match
         assert_eq!(deserialized.sender_id, original.sender_id);
@@ -141,11 +478,7 @@ mod tests {
     fn test_message_serialization_with_empty_content() {
         let message = AgentMessage::new("agent-2".to_string(), "".to_string());
 
-        let serialized = message
-            .serialize()
-            .expect("Failed to serialize empty message");
-        let deserialized =
-            AgentMessage::deserialize(&serialized).expect("Failed to deserialize empty message");
+        let deserialized = roundtrip(&message, 1024);
 
         assert_eq!(deserialized.sender_id, "agent-2");
         assert_eq!(deserialized.content, "");
@@ -155,11 +488,7 @@ mod tests {
     fn test_message_serialization_with_unicode() {
         let message = AgentMessage::new("agent-unicode".to_string(), "Hello 世界! 🌍".to_string());
 
-        let serialized = message
-            .serialize()
-            .expect("Failed to serialize unicode message");
-        let deserialized =
-            AgentMessage::deserialize(&serialized).expect("Failed to deserialize unicode message");
+        let deserialized = roundtrip(&message, 1024);
 
         assert_eq!(deserialized.content, "Hello 世界! 🌍");
     }
@@ -167,27 +496,91 @@ mod tests {
     #[test]
     fn test_invalid_deserialization() {
         let invalid_bytes = vec![0xFF, 0xFF, 0xFF, 0xFF];
-        let result = AgentMessage::deserialize(&invalid_bytes);
+        let result = CompressedAgentMessage::deserialize(&invalid_bytes);
 
-        assert!(result.is_err());
+        assert!(matches!(result, Err(EnvelopeError::Decode(_))));
     }
 
     #[test]
     fn test_message_with_custom_timestamp() {
-        let custom_timestamp = 1640995200; // Jan 1, 2022 00:00:00 UTC
+        let custom_timestamp = 1640995200000; // Jan 1, 2022 00:00:00 UTC in milliseconds
+        let message = AgentMessage::new_with_timestamp(
+            "agent-custom".to_string(),
+            custom_timestamp,
+            "Custom timestamp test".to_string(),
+        );
+
+        let deserialized = roundtrip(&message, 1024);
+
+        assert_eq!(deserialized.timestamp, custom_timestamp);
+    }
+
+    #[test]
+    fn test_control_and_structured_messages_survive_serialization() {
+        let control = AgentMessage {
+            kind: Some(MessageKind::Control(ControlMessage {
+                command: "pause".to_string(),
+                arguments: [("seconds".to_string(), "30".to_string())].into(),
+            })),
+            ..AgentMessage::new("moderator".to_string(), String::new())
+        };
+        assert_eq!(roundtrip(&control, 1024).kind, control.kind);
+
+        let vote = AgentMessage {
+            kind: Some(MessageKind::Structured(StructuredMessage {
+                schema: "vote".to_string(),
+                json: r#"{"choice":"yes"}"#.to_string(),
+            })),
+            ..AgentMessage::new("agent-1".to_string(), String::new())
+        };
+        assert_eq!(roundtrip(&vote, 1024).kind, vote.kind);
+    }
+
+    #[test]
+    fn test_status_messages_survive_serialization() {
+        let mut status = StatusMessage {
+            detail: "rate limited".to_string(),
+            consecutive_failures: 2,
+            ..Default::default()
+        };
+        status.set_status(AgentStatus::Error);
         let message = AgentMessage {
-            sender_id: "agent-custom".to_string(),
-            timestamp: custom_timestamp,
-            content: "Custom timestamp test".to_string(),
+            in_reply_to: Some("call-1".to_string()),
+            ..AgentMessage::new_status("agent-1".to_string(), status)
         };
 
-        let serialized = message
-            .serialize()
-            .expect("Failed to serialize custom timestamp message");
-        let deserialized = AgentMessage::deserialize(&serialized)
-            .expect("Failed to deserialize custom timestamp message");
+        let deserialized = roundtrip(&message, 1024);
+        assert_eq!(deserialized.kind, message.kind);
+        assert_eq!(deserialized.in_reply_to.as_deref(), Some("call-1"));
+        assert!(deserialized.content.is_empty());
+    }
 
-        assert_eq!(deserialized.timestamp, custom_timestamp);
+    #[test]
+    fn test_other_protocol_versions_are_reported_not_misread() {
+        let bytes = AgentMessage::new("agent-1".to_string(), "Hello".to_string())
+            .to_compressed(1024)
+            .expect("Failed to compress message")
+            .serialize()
+            .expect("Failed to serialize message");
+        let mut envelope = Envelope::decode(bytes.as_slice()).expect("Failed to decode envelope");
+        envelope.protocol_version = PROTOCOL_VERSION + 1;
+
+        let error = CompressedAgentMessage::deserialize(&envelope.encode_to_vec())
+            .err()
+            .expect("Newer protocol version was accepted");
+        assert!(matches!(
+            error,
+            EnvelopeError::UnsupportedVersion { version, .. } if version == PROTOCOL_VERSION + 1
+        ));
+        assert_eq!(error.unsupported_sender(), Some("agent-1"));
+
+        // A payload kind added by a newer agent decodes as an empty oneof
+        envelope.protocol_version = PROTOCOL_VERSION;
+        envelope.payload = None;
+        let error = CompressedAgentMessage::deserialize(&envelope.encode_to_vec())
+            .err()
+            .expect("Unknown payload was accepted");
+        assert_eq!(error.unsupported_sender(), Some("agent-1"));
     }
 
     #[cfg(test)]
@@ -228,7 +621,7 @@ mod tests {
             let compressed_msg = original_message
                 .to_compressed(50)
                 .expect("Failed to create compressed message");
-            assert!(!compressed_msg.is_compressed);
+            assert_eq!(compressed_msg.encoding, Encoding::None);
             assert_eq!(compressed_msg.original_size, 12);
 
             // Test decompression back to regular message
@@ -247,7 +640,7 @@ mod tests {
             let compressed_msg = original_message
                 .to_compressed(50)
                 .expect("Failed to create compressed message");
-            assert!(!compressed_msg.is_compressed);
+            assert_eq!(compressed_msg.encoding, Encoding::None);
             assert_eq!(compressed_msg.original_size, 5);
 
             // Test decompression back to regular message
@@ -256,70 +649,78 @@ mod tests {
                 .expect("Failed to decompress message");
             assert_eq!(decompressed_msg.content, original_message.content);
         }
-    }
-}
 
-/// A message that can be either compressed or uncompressed
-pub struct CompressedAgentMessage {
-    pub sender_id: String,
-    pub timestamp: i64,
-    pub compressed_data: Vec<u8>,
-    pub is_compressed: bool,
-    pub original_size: usize,
-}
+        #[test]
+        fn test_gzip_message_wire_roundtrip() {
+            let original_message =
+                AgentMessage::new("test-agent".to_string(), "Compress me please. ".repeat(20));
 
-impl CompressedAgentMessage {
-    /// Convert back to regular AgentMessage (decompress if needed)
-    pub fn to_agent_message(&self) -> Result<AgentMessage, Box<dyn std::error::Error>> {
-        let content = if self.is_compressed {
-            compression::decompress_content(&self.compressed_data)?
-        } else {
-            String::from_utf8(self.compressed_data.clone())?
-        };
+            let compressed_msg = original_message
+                .to_compressed(50)
+                .expect("Failed to create compressed message");
+            assert_eq!(compressed_msg.encoding, Encoding::Gzip);
+
+            let bytes = compressed_msg.serialize().expect("Failed to serialize");
+
+            // Compressed bytes travel in `payload`, not base64 inside `content`
+            let envelope = Envelope::decode(bytes.as_slice()).expect("Failed to decode envelope");
+            assert_eq!(envelope.protocol_version, PROTOCOL_VERSION);
+            let Some(EnvelopePayload::Chat(chat)) = envelope.payload else {
+                panic!("Expected a chat payload");
+            };
+            assert_eq!(chat.encoding(), Encoding::Gzip);
+            assert!(chat.content.is_empty());
+            assert_eq!(chat.payload, compressed_msg.compressed_data);
+
+            let received =
+                CompressedAgentMessage::deserialize(&bytes).expect("Failed to deserialize");
+            assert_eq!(received.encoding, Encoding::Gzip);
+            assert_eq!(received.original_size, original_message.content.len());
+
+            let decoded = received.to_agent_message().expect("Failed to decompress");
+            assert_eq!(decoded.sender_id, original_message.sender_id);
+            assert_eq!(decoded.timestamp, original_message.timestamp);
+            assert_eq!(decoded.content, original_message.content);
+        }
 
-        Ok(AgentMessage {
-            sender_id: self.sender_id.clone(),
-            timestamp: self.timestamp,
-            content,
-        })
-    }
+        #[test]
+        fn test_plain_text_resembling_base64_is_not_decoded() {
+            // Used to be mistaken for base64 gzip because of the "H4" prefix
+            let original_message =
+                AgentMessage::new("test-agent".to_string(), "H4ppy to help".to_string());
 
-    /// Serialize the compressed message
-    pub fn serialize(&self) -> Result<Vec<u8>, prost::EncodeError> {
-        // Create a temporary AgentMessage for serialization
-        let temp_message = AgentMessage {
-            sender_id: self.sender_id.clone(),
-            timestamp: self.timestamp,
-            content: if self.is_compressed {
-                // Base64 encode compressed data for safe transmission
-                STANDARD.encode(&self.compressed_data)
-            } else {
-                String::from_utf8_lossy(&self.compressed_data).to_string()
-            },
-        };
-        temp_message.serialize()
-    }
+            let bytes = original_message
+                .to_compressed(1024)
+                .expect("Failed to create compressed message")
+                .serialize()
+                .expect("Failed to serialize");
 
-    /// Deserialize and create CompressedAgentMessage
-    pub fn deserialize(
-        bytes: &[u8],
-        is_compressed: bool,
-        original_size: usize,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let agent_message = AgentMessage::deserialize(bytes)?;
+            let received =
+                CompressedAgentMessage::deserialize(&bytes).expect("Failed to deserialize");
+            assert_eq!(received.encoding, Encoding::None);
 
-        let compressed_data = if is_compressed {
-            STANDARD.decode(&agent_message.content)?
-        } else {
-            agent_message.content.as_bytes().to_vec()
-        };
+            let decoded = received.to_agent_message().expect("Failed to decode");
+            assert_eq!(decoded.content, "H4ppy to help");
+        }
 
-        Ok(CompressedAgentMessage {
-            sender_id: agent_message.sender_id,
-            timestamp: agent_message.timestamp,
-            compressed_data,
-            is_compressed,
-            original_size,
-        })
+        #[test]
+        fn test_unknown_encoding_is_rejected() {
+            let envelope = Envelope {
+                protocol_version: PROTOCOL_VERSION,
+                sender_id: "test-agent".to_string(),
+                payload: Some(EnvelopePayload::Chat(ChatMessage {
+                    encoding: 42,
+                    payload: vec![1, 2, 3],
+                    ..Default::default()
+                })),
+                ..Default::default()
+            };
+
+            let bytes = envelope.encode_to_vec();
+            assert!(matches!(
+                CompressedAgentMessage::deserialize(&bytes),
+                Err(EnvelopeError::UnknownEncoding(42))
+            ));
+        }
     }
 }
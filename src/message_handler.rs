@@ -1,4 +1,6 @@
 use crate::message::AgentMessage;
+use lru::LruCache;
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::{Mutex, mpsc};
@@ -12,16 +14,28 @@ pub enum MessageHandlerError {
 
     #[error("Channel closed")]
     ChannelClosed,
+
+    #[error("Duplicate message: {0}")]
+    DuplicateMessage(String),
 }
 
+/// Number of recently seen message ids remembered for duplicate suppression
+const DEDUPE_CAPACITY: usize = 1024;
+
 /// Message handler that manages MPSC channel communication between UDP intake and LLM processing
 pub struct MessageHandler {
     /// Agent ID for filtering self-messages
     agent_id: String,
+    /// Role of this agent, so messages addressed to the role reach it
+    role: String,
+    /// Session this agent takes part in; traffic from other sessions is dropped
+    session: String,
     /// Sender for UDP intake thread to send messages to LLM processing thread
     message_sender: mpsc::Sender<AgentMessage>,
     /// Receiver for LLM processing thread to receive messages
     message_receiver: Arc<Mutex<mpsc::Receiver<AgentMessage>>>,
+    /// Recently seen message ids, used to drop duplicates from multicast loops
+    seen_message_ids: std::sync::Mutex<LruCache<String, ()>>,
 }
 
 impl MessageHandler {
@@ -36,18 +50,81 @@ impl MessageHandler {
 
         Self {
             agent_id,
+            role: String::new(),
+            session: String::new(),
             message_sender: sender,
             message_receiver: Arc::new(Mutex::new(receiver)),
+            seen_message_ids: std::sync::Mutex::new(LruCache::new(
+                NonZeroUsize::new(DEDUPE_CAPACITY).expect("dedupe capacity is non-zero"),
+            )),
         }
     }
 
+    /// Set the role used to match messages addressed to this agent
+    pub fn with_role(mut self, role: String) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// Set the session whose messages this agent handles
+    pub fn with_session(mut self, session: String) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Whether a message belongs to a session other than this agent's
+    pub fn is_other_session(&self, message: &AgentMessage) -> bool {
+        message.session != self.session
+    }
+
     /// Get the agent ID
     pub fn agent_id(&self) -> &str {
         &self.agent_id
     }
 
-    /// Try to send a message without blocking (used by UDP intake thread)
+    /// Get the role of this agent
+    pub fn role(&self) -> &str {
+        &self.role
+    }
+
+    /// Record a message id, returning true if it was already seen recently
+    fn is_duplicate(&self, message: &AgentMessage) -> bool {
+        // Messages from peers that predate message ids cannot be deduplicated
+        if message.message_id.is_empty() {
+            return false;
+        }
+
+        let mut seen = self
+            .seen_message_ids
+            .lock()
+            .expect("dedupe cache lock poisoned");
+        seen.put(message.message_id.clone(), ()).is_some()
+    }
+
+    /// Record a message as seen, failing if it is a duplicate of a recent one
+    pub fn mark_seen(&self, message: &AgentMessage) -> Result<(), MessageHandlerError> {
+        if self.is_duplicate(message) {
+            debug!(
+                "Dropping duplicate message '{}' from '{}' for agent '{}'",
+                message.message_id, message.sender_id, self.agent_id
+            );
+            return Err(MessageHandlerError::DuplicateMessage(
+                message.message_id.clone(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Try to send a message without blocking
+    /// Messages whose id was seen recently are dropped as duplicates
+    #[cfg(test)]
     pub fn try_send_message(&self, message: AgentMessage) -> Result<(), MessageHandlerError> {
+        self.mark_seen(&message)?;
+        self.forward_message(message)
+    }
+
+    /// Send a message already checked with [`Self::mark_seen`] without blocking (used by UDP intake thread)
+    pub fn forward_message(&self, message: AgentMessage) -> Result<(), MessageHandlerError> {
         match self.message_sender.try_send(message.clone()) {
             Ok(()) => {
                 debug!(
@@ -93,8 +170,27 @@ impl MessageHandler {
                         continue; // Skip self-messages and continue receiving
                     }
 
+                    // Conversations in other sessions sharing the network are not ours
+                    if self.is_other_session(&message) {
+                        debug!(
+                            "Filtered out message '{}' from '{}' in session '{}'",
+                            message.message_id, message.sender_id, message.session
+                        );
+                        continue;
+                    }
+
+                    // Messages addressed to other agents are not ours to answer
+                    if !message.is_addressed_to(&self.agent_id, &self.role) {
+                        debug!(
+                            "Filtered out message '{}' from '{}' addressed to {:?}",
+                            message.message_id, message.sender_id, message.recipients
+                        );
+                        continue;
+                    }
+
                     debug!(
-                        "Received message from '{}' for processing by agent '{}' with content: '{}'",
+                        "Received message '{}' from '{}' for processing by agent '{}' with content: '{}'",
+                        message.message_id,
                         message.sender_id,
                         self.agent_id,
                         message.content.chars().take(50).collect::<String>()
@@ -109,7 +205,6 @@ impl MessageHandler {
             }
         }
     }
-
 }
 
 #[cfg(test)]
@@ -141,4 +236,115 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_duplicate_messages_are_dropped() {
+        let handler = MessageHandler::new("dedupe-agent".to_string(), 10);
+
+        let message = AgentMessage::new("sender".to_string(), "Looped message".to_string());
+        assert!(handler.try_send_message(message.clone()).is_ok());
+
+        // The same message arriving again (e.g. via a multicast loop) is suppressed
+        match handler.try_send_message(message.clone()) {
+            Err(MessageHandlerError::DuplicateMessage(id)) => assert_eq!(id, message.message_id),
+            other => panic!("Expected DuplicateMessage, got {:?}", other),
+        }
+
+        // A distinct message with identical content is still delivered
+        let other = AgentMessage::new("sender".to_string(), "Looped message".to_string());
+        assert!(handler.try_send_message(other.clone()).is_ok());
+
+        assert_eq!(
+            handler.receive_message().await.unwrap().message_id,
+            message.message_id
+        );
+        assert_eq!(
+            handler.receive_message().await.unwrap().message_id,
+            other.message_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_messages_are_checked_before_they_are_forwarded() {
+        let handler = MessageHandler::new("intake-agent".to_string(), 10);
+
+        // Intake records a message between the duplicate check and forwarding it
+        let message = AgentMessage::new("sender".to_string(), "First turn".to_string());
+        assert!(handler.mark_seen(&message).is_ok());
+        assert!(matches!(
+            handler.mark_seen(&message),
+            Err(MessageHandlerError::DuplicateMessage(_))
+        ));
+        assert!(handler.forward_message(message.clone()).is_ok());
+
+        assert_eq!(
+            handler.receive_message().await.unwrap().message_id,
+            message.message_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_messages_addressed_to_others_are_filtered() {
+        let handler = MessageHandler::new("agent-2".to_string(), 10).with_role("judge".to_string());
+
+        let addressed = |recipient: &str, content: &str| AgentMessage {
+            recipients: vec![recipient.to_string()],
+            ..AgentMessage::new("agent-1".to_string(), content.to_string())
+        };
+        for message in [
+            addressed("agent-3", "Cross-examining agent-3"),
+            addressed("agent-2", "For agent-2"),
+            addressed("debater", "For the debaters"),
+            addressed("judge", "For the judge"),
+        ] {
+            handler.try_send_message(message).unwrap();
+        }
+
+        assert_eq!(
+            handler.receive_message().await.unwrap().content,
+            "For agent-2"
+        );
+        assert_eq!(
+            handler.receive_message().await.unwrap().content,
+            "For the judge"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_messages_from_other_sessions_are_filtered() {
+        let handler =
+            MessageHandler::new("agent-2".to_string(), 10).with_session("team-red".to_string());
+
+        let in_session = |session: &str, content: &str| AgentMessage {
+            session: session.to_string(),
+            ..AgentMessage::new("agent-1".to_string(), content.to_string())
+        };
+        for message in [
+            in_session("team-blue", "Blue team opening"),
+            in_session("", "Default session opening"),
+            in_session("team-red", "Red team opening"),
+        ] {
+            handler.try_send_message(message).unwrap();
+        }
+
+        assert_eq!(
+            handler.receive_message().await.unwrap().content,
+            "Red team opening"
+        );
+    }
+
+    #[test]
+    fn test_dedupe_cache_is_bounded() {
+        let handler = MessageHandler::new("bounded-agent".to_string(), DEDUPE_CAPACITY + 10);
+
+        let first = AgentMessage::new("sender".to_string(), "First".to_string());
+        assert!(handler.try_send_message(first.clone()).is_ok());
+
+        // Push enough distinct ids through to evict the first one
+        for i in 0..DEDUPE_CAPACITY {
+            let message = AgentMessage::new("sender".to_string(), format!("Message {}", i));
+            assert!(handler.try_send_message(message).is_ok());
+        }
+
+        assert!(handler.try_send_message(first).is_ok());
+    }
 }
@@ -0,0 +1,329 @@
+use crate::policy::YES_NO_INSTRUCTION;
+use crate::transcript::SUMMARY_INSTRUCTIONS;
+use async_trait::async_trait;
+use llm::{
+    LLMProvider,
+    chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    models::ModelsProvider,
+    stt::SpeechToTextProvider,
+    tts::TextToSpeechProvider,
+};
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use thiserror::Error;
+
+/// Answer used when a script has nothing else to say
+const DEFAULT_TEMPLATE: &str = "{agent_id} heard: {message}";
+
+/// Answer to the agent's own yes-or-no question of whether to respond
+const CHECK_ANSWER: &str = "YES";
+
+/// Errors raised while loading a mock script
+#[derive(Error, Debug)]
+pub enum MockError {
+    #[error("Failed to read mock script: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid mock script: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    #[error("Invalid pattern '{pattern}' in mock script: {source}")]
+    Pattern {
+        pattern: String,
+        source: regex::Error,
+    },
+}
+
+/// A reply given whenever the latest message matches `pattern`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    pattern: String,
+    response: String,
+}
+
+/// Mock script as written in TOML
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScriptSpec {
+    #[serde(default)]
+    rules: Vec<RuleSpec>,
+    #[serde(default)]
+    replies: Vec<String>,
+    template: Option<String>,
+}
+
+/// An LLM stand-in that answers from a script, without any network access
+///
+/// Every answer is chosen from the latest user message: the first rule whose
+/// pattern matches wins; otherwise the next canned reply is used in order; once
+/// those run out, the template answers. Responses may use `{message}`,
+/// `{agent_id}` and `{turn}`, and rule responses may use the pattern's capture
+/// groups as `$1` or `${name}`.
+///
+/// Only conversation turns use up canned replies and count as turns. The
+/// agent's own summary requests are answered by the template, and its question
+/// of whether to respond with YES, unless a rule matches them.
+pub struct MockProvider {
+    agent_id: String,
+    rules: Vec<(Regex, String)>,
+    replies: Vec<String>,
+    template: String,
+    /// Number of conversation turns answered so far
+    turns: AtomicUsize,
+    /// Number of canned replies used so far
+    replies_used: AtomicUsize,
+}
+
+impl MockProvider {
+    /// A mock that echoes the latest message
+    pub fn echo(agent_id: &str) -> Self {
+        Self {
+            agent_id: agent_id.to_string(),
+            rules: Vec::new(),
+            replies: Vec::new(),
+            template: DEFAULT_TEMPLATE.to_string(),
+            turns: AtomicUsize::new(0),
+            replies_used: AtomicUsize::new(0),
+        }
+    }
+
+    /// Load a mock from a TOML script
+    pub fn from_file(path: &Path, agent_id: &str) -> Result<Self, MockError> {
+        Self::from_script(&std::fs::read_to_string(path)?, agent_id)
+    }
+
+    /// Create a mock from the contents of a TOML script
+    pub fn from_script(script: &str, agent_id: &str) -> Result<Self, MockError> {
+        let spec: ScriptSpec = toml::from_str(script)?;
+        let rules = spec
+            .rules
+            .into_iter()
+            .map(|rule| match Regex::new(&rule.pattern) {
+                Ok(regex) => Ok((regex, rule.response)),
+                Err(source) => Err(MockError::Pattern {
+                    pattern: rule.pattern,
+                    source,
+                }),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            rules,
+            replies: spec.replies,
+            template: spec
+                .template
+                .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string()),
+            ..Self::echo(agent_id)
+        })
+    }
+
+    /// Choose the answer to `message`
+    fn answer(&self, message: &str) -> String {
+        let summary = message.starts_with(SUMMARY_INSTRUCTIONS);
+        let check = message.trim_end().ends_with(YES_NO_INSTRUCTION);
+        let turn = if summary || check {
+            self.turns.load(Ordering::SeqCst)
+        } else {
+            self.turns.fetch_add(1, Ordering::SeqCst) + 1
+        };
+
+        let matched = self.rules.iter().find_map(|(pattern, response)| {
+            pattern.captures(message).map(|captures| {
+                let mut expanded = String::new();
+                captures.expand(response, &mut expanded);
+                expanded
+            })
+        });
+        let response = match matched {
+            Some(response) => response,
+            None if check => CHECK_ANSWER.to_string(),
+            None if summary => self.template.clone(),
+            // Rule matches do not use up canned replies
+            None => {
+                let next = self.replies_used.fetch_add(1, Ordering::SeqCst);
+                self.replies
+                    .get(next)
+                    .cloned()
+                    .unwrap_or_else(|| self.template.clone())
+            }
+        };
+
+        response
+            .replace("{message}", message)
+            .replace("{agent_id}", &self.agent_id)
+            .replace("{turn}", &turn.to_string())
+    }
+}
+
+#[async_trait]
+impl ChatProvider for MockProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        _tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let latest = messages
+            .iter()
+            .rev()
+            .find(|message| message.role == ChatRole::User)
+            .map(|message| message.content.as_str())
+            .unwrap_or_default();
+        Ok(Box::new(CompletionResponse {
+            text: self.answer(latest),
+        }))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for MockProvider {
+    async fn complete(&self, request: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        Ok(CompletionResponse {
+            text: self.answer(&request.prompt),
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for MockProvider {
+    async fn embed(&self, _input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        Err(LLMError::ProviderError(
+            "The mock backend does not support embeddings".to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for MockProvider {
+    async fn transcribe(&self, _audio: Vec<u8>) -> Result<String, LLMError> {
+        Err(LLMError::ProviderError(
+            "The mock backend does not support transcription".to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl TextToSpeechProvider for MockProvider {}
+
+#[async_trait]
+impl ModelsProvider for MockProvider {}
+
+impl LLMProvider for MockProvider {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::AgentMessage;
+    use crate::policy::{Decision, ResponseMode, ResponsePolicy, is_affirmative};
+    use crate::transcript::{Transcript, TranscriptLimits};
+
+    fn user(content: &str) -> ChatMessage {
+        ChatMessage::user().content(content).build()
+    }
+
+    async fn ask(mock: &MockProvider, content: &str) -> String {
+        mock.chat(&[user(content)]).await.unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_echo_without_script() {
+        let mock = MockProvider::echo("agent-1");
+        assert_eq!(
+            ask(&mock, "Cats or dogs?").await,
+            "agent-1 heard: Cats or dogs?"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_script_rules_replies_and_template() {
+        let mock = MockProvider::from_script(
+            r#"
+            replies = ["This is the Affirmative Constructive.", "This is the Affirmative Rebuttal."]
+            template = "Turn {turn}: nothing more to add."
+
+            [[rules]]
+            pattern = "(?i)@(?P<who>\\w+), ask"
+            response = "My question for you, ${who}: why?"
+            "#,
+            "aff-1",
+        )
+        .unwrap();
+
+        assert_eq!(
+            ask(&mock, "Affirmative Constructive: go.").await,
+            "This is the Affirmative Constructive."
+        );
+        assert_eq!(
+            ask(
+                &mock,
+                "First Crossfire: @affirmative, ask the negative one question."
+            )
+            .await,
+            "My question for you, affirmative: why?"
+        );
+        assert_eq!(
+            ask(&mock, "Affirmative Rebuttal: go.").await,
+            "This is the Affirmative Rebuttal."
+        );
+        assert_eq!(
+            ask(&mock, "Summary: go.").await,
+            "Turn 4: nothing more to add."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_only_conversation_turns_use_up_replies() {
+        let mock = MockProvider::from_script(
+            r#"
+            replies = ["This is the Affirmative Constructive.", "This is the Affirmative Rebuttal."]
+            template = "Turn {turn}: nothing more to add."
+            "#,
+            "aff-1",
+        )
+        .unwrap();
+
+        assert_eq!(
+            ask(&mock, "Constructive: go.").await,
+            "This is the Affirmative Constructive."
+        );
+
+        let mut transcript = Transcript::new(TranscriptLimits::default());
+        transcript.record_peer("neg-1", "Point one.");
+        let summary = transcript.summary_request(&[]);
+        assert_eq!(ask(&mock, &summary).await, "Turn 1: nothing more to add.");
+
+        let question = match ResponsePolicy::new(ResponseMode::LlmCheck, Vec::new(), 0.5).decide(
+            &AgentMessage::new("neg-1".to_string(), "Point one.".to_string()),
+            "aff-1",
+            "affirmative",
+        ) {
+            Decision::AskLlm(question) => question,
+            decision => panic!("expected a question, got {:?}", decision),
+        };
+        assert!(is_affirmative(
+            &ask(&mock, &format!("[neg-1]: Point one.\n\n{}", question)).await
+        ));
+
+        assert_eq!(
+            ask(&mock, "Rebuttal: go.").await,
+            "This is the Affirmative Rebuttal."
+        );
+    }
+
+    #[test]
+    fn test_invalid_scripts_are_rejected() {
+        assert!(matches!(
+            MockProvider::from_script("[[rules]]\npattern = \"(\"\nresponse = \"x\"", "a"),
+            Err(MockError::Pattern { .. })
+        ));
+        assert!(matches!(
+            MockProvider::from_script("answers = []", "a"),
+            Err(MockError::Toml(_))
+        ));
+    }
+}
@@ -0,0 +1,591 @@
+use crate::message::{AgentMessage, MessageKind};
+use crate::network::NetworkError;
+use crate::roster::Roster;
+use crate::transport::Transport;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Received messages buffered between the reader task and the moderator
+const INBOX_CAPACITY: usize = 256;
+
+/// Role of the agent arguing for the resolution
+pub const AFFIRMATIVE: &str = "affirmative";
+
+/// Role of the agent arguing against the resolution
+pub const NEGATIVE: &str = "negative";
+
+/// Role of the agent deciding the round
+pub const JUDGE: &str = "judge";
+
+/// Role the moderator announces itself with
+pub const MODERATOR: &str = "moderator";
+
+/// How the moderator asks the judge for its reason for decision
+pub const RFD_REQUEST: &str = "Please provide your RFD.";
+
+/// One speech of a round, given by the agent with the speaker's role
+#[derive(Debug, Clone, PartialEq)]
+pub struct Speech {
+    /// Phase announced to the swarm, e.g. "Affirmative Rebuttal"
+    pub phase: String,
+    pub speaker: &'static str,
+    /// What the speaker is asked to do
+    pub instruction: String,
+    /// Speaking time, turned into a word budget; None leaves the speech unlimited
+    pub seconds: Option<u64>,
+}
+
+impl Speech {
+    fn new(phase: &str, speaker: &'static str, instruction: &str, seconds: Option<u64>) -> Self {
+        Self {
+            phase: phase.to_string(),
+            speaker,
+            instruction: instruction.to_string(),
+            seconds,
+        }
+    }
+
+    /// Most words the speech may have when spoken at `words_per_minute`
+    pub fn word_budget(&self, words_per_minute: u64) -> Option<usize> {
+        self.seconds
+            .map(|seconds| (seconds * words_per_minute / 60) as usize)
+    }
+}
+
+/// The Public Forum speech order, ending with the judge's reason for decision
+pub fn public_forum_schedule() -> Vec<Speech> {
+    let mut schedule = vec![
+        Speech::new(
+            "Affirmative Constructive",
+            AFFIRMATIVE,
+            "present your case: contentions with claim, warrant and impact.",
+            Some(240),
+        ),
+        Speech::new(
+            "Negative Constructive",
+            NEGATIVE,
+            "present your case: contentions with claim, warrant and impact.",
+            Some(240),
+        ),
+    ];
+    push_crossfire(&mut schedule, "First Crossfire", 180);
+    schedule.extend([
+        Speech::new(
+            "Affirmative Rebuttal",
+            AFFIRMATIVE,
+            "attack the negative case.",
+            Some(240),
+        ),
+        Speech::new(
+            "Negative Rebuttal",
+            NEGATIVE,
+            "attack the affirmative case and frontline your own.",
+            Some(240),
+        ),
+    ]);
+    push_crossfire(&mut schedule, "Second Crossfire", 180);
+    schedule.extend([
+        Speech::new(
+            "Affirmative Summary",
+            AFFIRMATIVE,
+            "collapse to your strongest arguments, extend them with their defense, and weigh.",
+            Some(180),
+        ),
+        Speech::new(
+            "Negative Summary",
+            NEGATIVE,
+            "collapse to your strongest arguments, extend them with their defense, and weigh.",
+            Some(180),
+        ),
+    ]);
+    push_crossfire(&mut schedule, "Grand Crossfire", 180);
+    schedule.extend([
+        Speech::new(
+            "Affirmative Final Focus",
+            AFFIRMATIVE,
+            "mirror your summary and give the judge your voting issues, with no new arguments.",
+            Some(120),
+        ),
+        Speech::new(
+            "Negative Final Focus",
+            NEGATIVE,
+            "mirror your summary and give the judge your voting issues, with no new arguments.",
+            Some(120),
+        ),
+        Speech::new(
+            "Reason for Decision",
+            JUDGE,
+            &format!("the debate is over. {}", RFD_REQUEST),
+            None,
+        ),
+    ]);
+    schedule
+}
+
+/// Whether `message` is the moderator's call for the RFD of the judge `agent_id` with `role`
+///
+/// `sender_role` is the role the sender announced; only the moderator's own
+/// call counts, not a broadcast or a debater quoting it.
+pub fn is_rfd_call(
+    message: &AgentMessage,
+    sender_role: Option<&str>,
+    agent_id: &str,
+    role: &str,
+) -> bool {
+    sender_role == Some(MODERATOR)
+        && !message.recipients.is_empty()
+        && message.is_addressed_to(agent_id, role)
+        && message.content.contains(RFD_REQUEST)
+}
+
+/// Add a crossfire in which each side asks one question and answers the other's
+fn push_crossfire(schedule: &mut Vec<Speech>, phase: &str, seconds: u64) {
+    let turn = seconds / 4;
+    for (asker, answerer) in [(AFFIRMATIVE, NEGATIVE), (NEGATIVE, AFFIRMATIVE)] {
+        schedule.push(Speech::new(
+            phase,
+            asker,
+            &format!("ask the {} one pointed question.", answerer),
+            Some(turn),
+        ));
+        schedule.push(Speech::new(
+            phase,
+            answerer,
+            &format!("answer the {}'s question.", asker),
+            Some(turn),
+        ));
+    }
+}
+
+/// Drives a Public Forum round over the swarm
+///
+/// The moderator does not debate. It calls each speaker in turn with a message
+/// addressed to their role, waits for the reply to that call, and announces
+/// phases, overruns and missed speeches to everyone, so the agents no longer
+/// have to keep the speech order themselves.
+pub struct Moderator {
+    transport: Arc<dyn Transport>,
+    agent_id: String,
+    session: String,
+    /// Speaking rate that turns speech times into word budgets
+    words_per_minute: u64,
+    /// How long a speaker has to reply before the round moves on
+    speech_timeout: Duration,
+    roster: Roster,
+    /// Messages received by the reader task
+    inbox: mpsc::Receiver<Result<AgentMessage, NetworkError>>,
+    reader: JoinHandle<()>,
+}
+
+impl Moderator {
+    pub fn new(
+        transport: Arc<dyn Transport>,
+        agent_id: String,
+        words_per_minute: u64,
+        speech_timeout: Duration,
+    ) -> Self {
+        let (inbox, reader) = Self::spawn_reader(Arc::clone(&transport));
+        Self {
+            transport,
+            agent_id,
+            session: String::new(),
+            words_per_minute,
+            speech_timeout,
+            roster: Roster::new(),
+            inbox,
+            reader,
+        }
+    }
+
+    /// Receive from `transport` in a task of its own
+    ///
+    /// Speech timeouts then only cancel a channel receive. Cancelling
+    /// `Transport::recv` part way through a frame would desync a TCP stream.
+    fn spawn_reader(
+        transport: Arc<dyn Transport>,
+    ) -> (
+        mpsc::Receiver<Result<AgentMessage, NetworkError>>,
+        JoinHandle<()>,
+    ) {
+        let (sender, inbox) = mpsc::channel(INBOX_CAPACITY);
+        let reader = tokio::spawn(async move {
+            loop {
+                let received = transport.recv().await;
+                let fatal = matches!(received, Err(ref e) if !e.is_message_error());
+                if sender.send(received).await.is_err() || fatal {
+                    break;
+                }
+            }
+        });
+        (inbox, reader)
+    }
+
+    /// Only moderate agents in `session`
+    pub fn with_session(mut self, session: String) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Wait for both debaters and the judge, then run a full Public Forum round
+    pub async fn run(&mut self) -> Result<(), NetworkError> {
+        info!(
+            "Waiting for the {}, the {} and the {}",
+            AFFIRMATIVE, NEGATIVE, JUDGE
+        );
+        while ![AFFIRMATIVE, NEGATIVE, JUDGE]
+            .iter()
+            .all(|role| self.roster.knows(role))
+        {
+            self.next_message().await?;
+        }
+
+        self.run_schedule(&public_forum_schedule()).await
+    }
+
+    /// Call every speech of `schedule` in order
+    async fn run_schedule(&mut self, schedule: &[Speech]) -> Result<(), NetworkError> {
+        self.announce(
+            "The round begins. The moderator calls each speaker in turn; speak only when called."
+                .to_string(),
+            Vec::new(),
+        )
+        .await?;
+
+        for speech in schedule {
+            let budget = speech.word_budget(self.words_per_minute);
+            let mut call = format!(
+                "{}: @{}, {}",
+                speech.phase, speech.speaker, speech.instruction
+            );
+            if let Some(budget) = budget {
+                call.push_str(&format!(" You have {} words.", budget));
+            }
+            let call_id = self
+                .announce(call, vec![speech.speaker.to_string()])
+                .await?;
+
+            match tokio::time::timeout(self.speech_timeout, self.reply_to(&call_id)).await {
+                Ok(reply) => {
+                    let reply = reply?;
+                    if let Some(MessageKind::Status(_)) = reply.kind {
+                        warn!("The {} failed to give the {}", speech.speaker, speech.phase);
+                        self.announce(
+                            format!(
+                                "The {} could not give the {}; the round moves on.",
+                                speech.speaker, speech.phase
+                            ),
+                            Vec::new(),
+                        )
+                        .await?;
+                        continue;
+                    }
+
+                    let words = reply.content.split_whitespace().count();
+                    if let Some(budget) = budget
+                        && words > budget
+                    {
+                        // Peers have already heard the whole speech, so the overrun is only
+                        // announced, for the judge to weigh. Mentions would make agents
+                        // answer, so announcements name roles plainly
+                        self.announce(
+                            format!(
+                                "Time! The {} ran {} words over its {}-word budget; the judge should disregard everything past word {}.",
+                                speech.phase,
+                                words - budget,
+                                budget,
+                                budget
+                            ),
+                            Vec::new(),
+                        )
+                        .await?;
+                    }
+                }
+                Err(_) => {
+                    warn!(
+                        "The {} gave no {} within {:?}",
+                        speech.speaker, speech.phase, self.speech_timeout
+                    );
+                    self.announce(
+                        format!(
+                            "Time! The {} gave no {}; the round moves on.",
+                            speech.speaker, speech.phase
+                        ),
+                        Vec::new(),
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        self.announce("The round is over.".to_string(), Vec::new())
+            .await?;
+        Ok(())
+    }
+
+    /// Broadcast `content` addressed to `recipients`, returning its message id
+    async fn announce(
+        &self,
+        content: String,
+        recipients: Vec<String>,
+    ) -> Result<String, NetworkError> {
+        info!("{}", content);
+        let message = AgentMessage {
+            recipients,
+            ..AgentMessage::new(self.agent_id.clone(), content)
+        };
+        self.transport.send(&message).await?;
+        Ok(message.message_id)
+    }
+
+    /// Wait for the reply to the message with id `call_id`
+    async fn reply_to(&mut self, call_id: &str) -> Result<AgentMessage, NetworkError> {
+        loop {
+            let message = self.next_message().await?;
+            if message.in_reply_to.as_deref() == Some(call_id) {
+                return Ok(message);
+            }
+            debug!(
+                "Ignoring message from '{}' while waiting for a speech",
+                message.sender_id
+            );
+        }
+    }
+
+    /// Next chat or status message of this session, tracking who is present on the way
+    async fn next_message(&mut self) -> Result<AgentMessage, NetworkError> {
+        loop {
+            let received = self.inbox.recv().await.unwrap_or_else(|| {
+                Err(NetworkError::ReceiveError(
+                    "the moderator's reader task has stopped".to_string(),
+                ))
+            });
+            let message = match received {
+                Ok(message) => message,
+                Err(e) if e.is_message_error() => {
+                    debug!("Skipping message: {}", e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if message.session != self.session {
+                continue;
+            }
+
+            match message.kind {
+                Some(MessageKind::Heartbeat(ref heartbeat)) => {
+                    if message.sender_id != self.agent_id
+                        && let Some(event) =
+                            self.roster
+                                .observe(&message.sender_id, heartbeat, Instant::now())
+                    {
+                        info!("{}", event);
+                    }
+                }
+                // A speaker that failed to answer its call reports it in a status message
+                Some(MessageKind::Status(_)) | None => return Ok(message),
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+impl Drop for Moderator {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::NetworkConfig;
+    use crate::transport::{BUS_CAPACITY, MemoryTransport, MessageBus};
+
+    #[test]
+    fn test_public_forum_schedule() {
+        let schedule = public_forum_schedule();
+        let phases: Vec<&str> = schedule
+            .iter()
+            .map(|speech| speech.phase.as_str())
+            .collect();
+        let mut distinct = phases.clone();
+        distinct.dedup();
+        assert_eq!(
+            distinct,
+            vec![
+                "Affirmative Constructive",
+                "Negative Constructive",
+                "First Crossfire",
+                "Affirmative Rebuttal",
+                "Negative Rebuttal",
+                "Second Crossfire",
+                "Affirmative Summary",
+                "Negative Summary",
+                "Grand Crossfire",
+                "Affirmative Final Focus",
+                "Negative Final Focus",
+                "Reason for Decision",
+            ]
+        );
+
+        // Crossfire alternates questions and answers between the sides
+        let crossfire: Vec<&str> = schedule
+            .iter()
+            .filter(|speech| speech.phase == "First Crossfire")
+            .map(|speech| speech.speaker)
+            .collect();
+        assert_eq!(
+            crossfire,
+            vec![AFFIRMATIVE, NEGATIVE, NEGATIVE, AFFIRMATIVE]
+        );
+
+        let rfd = schedule.last().unwrap();
+        assert_eq!(rfd.speaker, JUDGE);
+        assert_eq!(rfd.word_budget(150), None);
+        assert_eq!(schedule[0].word_budget(150), Some(600));
+    }
+
+    #[test]
+    fn test_only_the_moderators_rfd_call_asks_for_a_ballot() {
+        let rfd = public_forum_schedule().pop().unwrap();
+        let call = AgentMessage {
+            recipients: vec![JUDGE.to_string()],
+            ..AgentMessage::new(
+                "moderator".to_string(),
+                format!("{}: @{}, {}", rfd.phase, rfd.speaker, rfd.instruction),
+            )
+        };
+        assert!(is_rfd_call(&call, Some(MODERATOR), "judge-1", JUDGE));
+
+        // Other speakers, other senders and broadcasts are answered in prose
+        assert!(!is_rfd_call(&call, Some(MODERATOR), "aff-1", AFFIRMATIVE));
+        assert!(!is_rfd_call(&call, Some(AFFIRMATIVE), "judge-1", JUDGE));
+        assert!(!is_rfd_call(&call, None, "judge-1", JUDGE));
+        let broadcast = AgentMessage {
+            recipients: Vec::new(),
+            ..call.clone()
+        };
+        assert!(!is_rfd_call(&broadcast, Some(MODERATOR), "judge-1", JUDGE));
+        let question = AgentMessage {
+            content: "@judge, how would you weigh privacy?".to_string(),
+            ..call
+        };
+        assert!(!is_rfd_call(&question, Some(MODERATOR), "judge-1", JUDGE));
+    }
+
+    #[tokio::test]
+    async fn test_moderator_calls_speakers_and_enforces_budgets() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let transport: Arc<dyn Transport> =
+            Arc::new(MemoryTransport::new(&bus, &config, "moderator".to_string()).unwrap());
+        let affirmative = MemoryTransport::new(&bus, &config, "aff-1".to_string()).unwrap();
+        let observer = MemoryTransport::new(&bus, &config, "observer".to_string()).unwrap();
+
+        // The affirmative answers its call at length, the negative never shows up
+        tokio::spawn(async move {
+            while let Ok(message) = affirmative.recv().await {
+                if !message.recipients.is_empty() && message.is_addressed_to("aff-1", AFFIRMATIVE) {
+                    let reply = AgentMessage::new_reply(
+                        "aff-1".to_string(),
+                        "one two three four five".to_string(),
+                        message.message_id.clone(),
+                    );
+                    affirmative.send(&reply).await.unwrap();
+                }
+            }
+        });
+
+        let schedule = vec![
+            Speech::new("Affirmative Constructive", AFFIRMATIVE, "go.", Some(3)),
+            Speech::new("Negative Constructive", NEGATIVE, "go.", Some(3)),
+        ];
+        let mut moderator = Moderator::new(
+            transport,
+            "moderator".to_string(),
+            60,
+            Duration::from_millis(200),
+        );
+        tokio::time::timeout(Duration::from_secs(2), moderator.run_schedule(&schedule))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut announcements = Vec::new();
+        while let Ok(Ok(message)) =
+            tokio::time::timeout(Duration::from_millis(50), observer.recv()).await
+        {
+            if message.sender_id == "moderator" {
+                announcements.push(message.content);
+            }
+        }
+        assert_eq!(
+            announcements,
+            vec![
+                "The round begins. The moderator calls each speaker in turn; speak only when called.",
+                "Affirmative Constructive: @affirmative, go. You have 3 words.",
+                "Time! The Affirmative Constructive ran 2 words over its 3-word budget; the judge should disregard everything past word 3.",
+                "Negative Constructive: @negative, go. You have 3 words.",
+                "Time! The negative gave no Negative Constructive; the round moves on.",
+                "The round is over.",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_moderator_moves_on_when_a_speaker_fails() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let transport: Arc<dyn Transport> =
+            Arc::new(MemoryTransport::new(&bus, &config, "moderator".to_string()).unwrap());
+        let negative = MemoryTransport::new(&bus, &config, "neg-1".to_string()).unwrap();
+        let observer = MemoryTransport::new(&bus, &config, "observer".to_string()).unwrap();
+
+        // The negative's LLM fails, so it reports a status instead of speaking
+        tokio::spawn(async move {
+            while let Ok(message) = negative.recv().await {
+                if !message.recipients.is_empty() && message.is_addressed_to("neg-1", NEGATIVE) {
+                    let status = AgentMessage {
+                        in_reply_to: Some(message.message_id.clone()),
+                        ..AgentMessage::new_status("neg-1".to_string(), Default::default())
+                    };
+                    negative.send(&status).await.unwrap();
+                }
+            }
+        });
+
+        let schedule = vec![Speech::new(
+            "Negative Constructive",
+            NEGATIVE,
+            "go.",
+            Some(3),
+        )];
+        let mut moderator = Moderator::new(
+            transport,
+            "moderator".to_string(),
+            60,
+            Duration::from_secs(30),
+        );
+        // Far sooner than the speech timeout
+        tokio::time::timeout(Duration::from_secs(2), moderator.run_schedule(&schedule))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut announcements = Vec::new();
+        while let Ok(Ok(message)) =
+            tokio::time::timeout(Duration::from_millis(50), observer.recv()).await
+        {
+            if message.sender_id == "moderator" {
+                announcements.push(message.content);
+            }
+        }
+        assert_eq!(
+            announcements[2],
+            "The negative could not give the Negative Constructive; the round moves on."
+        );
+    }
+}
@@ -1,6 +1,18 @@
-use crate::message::{AgentMessage, CompressedAgentMessage};
+use crate::auth::SwarmKey;
+use crate::codec::MessageCodec;
+use crate::crypto::SwarmCipher;
+use crate::message::{AgentMessage, Datagram, Nack, now_millis};
+use crate::reliability::{DeliveryMode, NackLimits, Observation, ReceiveWindow, RetransmitBuffer};
+use crate::replay::ReplayCounters;
+use crate::transport::Transport;
+use async_trait::async_trait;
+use prost::Message;
 use socket2::{Domain, Protocol, Socket, Type};
-use std::net::{Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use thiserror::Error;
 use tokio::net::UdpSocket;
@@ -28,6 +40,42 @@ pub enum NetworkError {
 
     #[error("Invalid network configuration: {0}")]
     ConfigError(String),
+
+    #[error("Failed to reassemble fragmented message: {0}")]
+    ReassemblyError(String),
+
+    #[error("Message authentication failed: {0}")]
+    AuthenticationError(String),
+
+    #[error("Agent identity verification failed: {0}")]
+    IdentityError(String),
+
+    #[error("Failed to decrypt message: {0}")]
+    DecryptionError(String),
+
+    #[error("Rejected possible replay: {0}")]
+    ReplayError(String),
+
+    #[error("Unsupported protocol: {0}")]
+    UnsupportedProtocol(String),
+}
+
+impl NetworkError {
+    /// Whether the error concerns a single received message rather than the transport
+    ///
+    /// Receivers skip such messages and keep listening.
+    pub fn is_message_error(&self) -> bool {
+        matches!(
+            self,
+            NetworkError::DeserializationError(_)
+                | NetworkError::ReassemblyError(_)
+                | NetworkError::AuthenticationError(_)
+                | NetworkError::IdentityError(_)
+                | NetworkError::DecryptionError(_)
+                | NetworkError::ReplayError(_)
+                | NetworkError::UnsupportedProtocol(_)
+        )
+    }
 }
 
 impl From<NetworkError> for String {
@@ -41,22 +89,291 @@ impl From<NetworkError> for String {
 pub struct NetworkConfig {
     /// Multicast address for agent communication
     pub multicast_address: SocketAddr,
-    /// Network interface to bind to (None for default)
-    pub interface: Option<String>,
+    /// Network interfaces to join the group on (empty for the OS default)
+    /// Entries may be interface names, IPv4 addresses, or IPv6 interface indexes
+    /// Outgoing datagrams leave via the first interface
+    pub interfaces: Vec<String>,
+    /// Time-to-live (IPv4) or hop limit (IPv6) for outgoing multicast datagrams
+    pub multicast_hops: u32,
+    /// Whether datagrams are looped back to agents on the same host
+    pub multicast_loopback: bool,
     /// Size of the receive buffer in bytes
     pub buffer_size: usize,
     /// Message size threshold in bytes above which compression will be applied
     /// Messages larger than this threshold will be compressed using gzip before transmission
     pub compression_threshold: usize,
+    /// Maximum number of message bytes carried by a single datagram
+    /// Larger messages are split into numbered fragments and reassembled on receive
+    pub max_fragment_size: usize,
+    /// How long a partially received message is kept while waiting for missing fragments
+    pub reassembly_timeout: Duration,
+    /// Maximum number of partially received messages held at once
+    pub max_pending_reassemblies: usize,
+    /// Maximum number of bytes held across all partially received messages
+    pub max_reassembly_bytes: usize,
+    /// Pre-shared swarm key; when set, every datagram is signed and verified
+    pub swarm_key: Option<SwarmKey>,
+    /// Directory holding this agent's Ed25519 keypair and trusted peer keys
+    /// When set, every message is signed and peers are verified on a trust-on-first-use basis
+    pub identity_dir: Option<PathBuf>,
+    /// Cipher derived from the swarm passphrase; when set, every message is encrypted
+    /// and unencrypted traffic is ignored
+    pub cipher: Option<SwarmCipher>,
+    /// Maximum clock skew accepted between a message timestamp and local time
+    /// Messages outside the window, or whose id was already seen within it, are rejected
+    /// None disables replay protection
+    pub replay_window: Option<Duration>,
+    /// Whether lost datagrams are detected and retransmitted
+    pub delivery_mode: DeliveryMode,
+    /// Number of sent datagrams kept for retransmission in reliable mode
+    pub retransmit_buffer_size: usize,
+    /// Minimum time between two retransmission requests for the same datagram
+    pub nack_interval: Duration,
+    /// Conversation every outgoing message is tagged with; empty for the default session
+    pub session: String,
 }
 
 impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             multicast_address: "239.255.255.250:8080".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
+            multicast_hops: 1, // Stay on the local network segment
+            multicast_loopback: true,
             buffer_size: 65536,          // 64KB buffer
             compression_threshold: 1024, // Compress messages larger than 1KB
+            max_fragment_size: 1200,     // Stay below a typical Ethernet MTU
+            reassembly_timeout: Duration::from_secs(5),
+            max_pending_reassemblies: 64,
+            max_reassembly_bytes: 4 * 1024 * 1024, // 4MB of partial messages
+            swarm_key: None,
+            identity_dir: None,
+            cipher: None,
+            replay_window: Some(Duration::from_secs(30)),
+            delivery_mode: DeliveryMode::BestEffort,
+            retransmit_buffer_size: 1024,
+            nack_interval: Duration::from_millis(200),
+            session: String::new(),
+        }
+    }
+}
+
+/// Fragmentation and reassembly of messages that do not fit in a single datagram
+pub mod fragmentation {
+    use crate::message::Datagram;
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+    use tracing::{debug, warn};
+
+    /// Upper bound on the protobuf framing (including the MAC and sequence number) added
+    /// around each fragment payload, not counting the sender id
+    pub const DATAGRAM_OVERHEAD: usize = 160;
+
+    /// Split a serialized message into datagrams of at most `max_fragment_size` payload bytes
+    pub fn split(message_id: &str, payload: &[u8], max_fragment_size: usize) -> Vec<Datagram> {
+        let chunks: Vec<&[u8]> = if payload.is_empty() {
+            vec![payload]
+        } else {
+            payload.chunks(max_fragment_size.max(1)).collect()
+        };
+        let fragment_count = chunks.len() as u32;
+
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, chunk)| Datagram {
+                message_id: message_id.to_string(),
+                fragment_index: index as u32,
+                fragment_count,
+                payload: chunk.to_vec(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Limits applied while reassembling fragmented messages
+    #[derive(Debug, Clone)]
+    pub struct ReassemblyLimits {
+        /// How long to wait for the remaining fragments of a message
+        pub timeout: Duration,
+        /// Maximum number of fragments a single message may be split into
+        pub max_fragments: u32,
+        /// Maximum number of partially received messages
+        pub max_pending_messages: usize,
+        /// Maximum number of bytes buffered across all partial messages
+        pub max_pending_bytes: usize,
+    }
+
+    /// A message for which only some fragments have arrived
+    struct PartialMessage {
+        fragments: Vec<Option<Vec<u8>>>,
+        received: usize,
+        bytes: usize,
+        first_seen: Instant,
+    }
+
+    /// Collects fragments and yields complete message payloads
+    pub struct Reassembler {
+        limits: ReassemblyLimits,
+        pending: HashMap<String, PartialMessage>,
+        pending_bytes: usize,
+    }
+
+    impl Reassembler {
+        /// Create a new Reassembler with the given limits
+        pub fn new(limits: ReassemblyLimits) -> Self {
+            Self {
+                limits,
+                pending: HashMap::new(),
+                pending_bytes: 0,
+            }
+        }
+
+        /// Number of messages that are still waiting for fragments
+        pub fn pending_messages(&self) -> usize {
+            self.pending.len()
+        }
+
+        /// Accept a datagram, returning the full payload once every fragment has arrived
+        pub fn accept(
+            &mut self,
+            datagram: Datagram,
+            now: Instant,
+        ) -> Result<Option<Vec<u8>>, String> {
+            self.expire(now);
+
+            let Datagram {
+                message_id,
+                fragment_index,
+                fragment_count,
+                payload,
+                ..
+            } = datagram;
+
+            if fragment_count == 0 || fragment_index >= fragment_count {
+                return Err(format!(
+                    "Invalid fragment {} of {} for message {}",
+                    fragment_index, fragment_count, message_id
+                ));
+            }
+
+            if fragment_count > self.limits.max_fragments {
+                return Err(format!(
+                    "Message {} has {} fragments, limit is {}",
+                    message_id, fragment_count, self.limits.max_fragments
+                ));
+            }
+
+            // Unfragmented messages bypass the reassembly buffer entirely
+            if fragment_count == 1 {
+                return Ok(Some(payload));
+            }
+
+            if !self.pending.contains_key(&message_id) {
+                while self.pending.len() >= self.limits.max_pending_messages {
+                    self.evict_oldest();
+                }
+                self.pending.insert(
+                    message_id.clone(),
+                    PartialMessage {
+                        fragments: vec![None; fragment_count as usize],
+                        received: 0,
+                        bytes: 0,
+                        first_seen: now,
+                    },
+                );
+            }
+
+            let partial = self
+                .pending
+                .get_mut(&message_id)
+                .expect("partial message was just inserted");
+
+            if partial.fragments.len() != fragment_count as usize {
+                return Err(format!(
+                    "Fragment count mismatch for message {}: expected {}, got {}",
+                    message_id,
+                    partial.fragments.len(),
+                    fragment_count
+                ));
+            }
+
+            let slot = &mut partial.fragments[fragment_index as usize];
+            if slot.is_some() {
+                debug!(
+                    "Ignoring duplicate fragment {} of message {}",
+                    fragment_index, message_id
+                );
+                return Ok(None);
+            }
+
+            partial.bytes += payload.len();
+            partial.received += 1;
+            self.pending_bytes += payload.len();
+            *slot = Some(payload);
+
+            if partial.received == partial.fragments.len() {
+                let partial = self
+                    .pending
+                    .remove(&message_id)
+                    .expect("complete message is pending");
+                self.pending_bytes -= partial.bytes;
+
+                let mut message = Vec::with_capacity(partial.bytes);
+                for fragment in partial.fragments.into_iter().flatten() {
+                    message.extend_from_slice(&fragment);
+                }
+                return Ok(Some(message));
+            }
+
+            // Keep partial-message memory bounded by dropping the oldest messages first
+            while self.pending_bytes > self.limits.max_pending_bytes {
+                self.evict_oldest();
+            }
+
+            Ok(None)
+        }
+
+        /// Drop partial messages whose remaining fragments did not arrive in time
+        pub fn expire(&mut self, now: Instant) {
+            let timeout = self.limits.timeout;
+            let expired: Vec<String> = self
+                .pending
+                .iter()
+                .filter(|(_, partial)| now.duration_since(partial.first_seen) >= timeout)
+                .map(|(message_id, _)| message_id.clone())
+                .collect();
+
+            for message_id in expired {
+                if let Some(partial) = self.pending.remove(&message_id) {
+                    self.pending_bytes -= partial.bytes;
+                    warn!(
+                        "Dropping message {} after reassembly timeout ({} of {} fragments received)",
+                        message_id,
+                        partial.received,
+                        partial.fragments.len()
+                    );
+                }
+            }
+        }
+
+        /// Drop the partial message that has been waiting the longest
+        fn evict_oldest(&mut self) {
+            let oldest = self
+                .pending
+                .iter()
+                .min_by_key(|(_, partial)| partial.first_seen)
+                .map(|(message_id, _)| message_id.clone());
+
+            if let Some(message_id) = oldest
+                && let Some(partial) = self.pending.remove(&message_id)
+            {
+                self.pending_bytes -= partial.bytes;
+                warn!(
+                    "Evicting partial message {} to stay within reassembly limits",
+                    message_id
+                );
+            }
         }
     }
 }
@@ -67,6 +384,19 @@ pub struct NetworkManager {
     multicast_addr: SocketAddr,
     agent_id: String,
     config: NetworkConfig,
+    /// Effective number of message bytes per datagram
+    fragment_size: usize,
+    reassembler: Mutex<fragmentation::Reassembler>,
+    /// Encodes, authenticates and decodes messages
+    codec: MessageCodec,
+    /// Start of this agent's sequence stream, so peers notice when it restarts
+    epoch: u64,
+    /// Sequence number of the next datagram sent in reliable mode
+    next_sequence: AtomicU64,
+    /// Recently sent datagrams, resent when peers report them missing
+    retransmit_buffer: Mutex<RetransmitBuffer>,
+    /// Gaps detected in peers' sequence streams
+    receive_window: Mutex<ReceiveWindow>,
 }
 
 impl NetworkManager {
@@ -80,17 +410,53 @@ impl NetworkManager {
             )));
         }
 
+        // Fragments must fit in the receive buffer together with their framing
+        let fragment_size = config.max_fragment_size.min(
+            config
+                .buffer_size
+                .saturating_sub(fragmentation::DATAGRAM_OVERHEAD + agent_id.len()),
+        );
+        if fragment_size == 0 {
+            return Err(NetworkError::ConfigError(format!(
+                "Buffer size {} is too small to carry any message fragments",
+                config.buffer_size
+            )));
+        }
+
+        // Load or create this agent's signing identity
+        let codec = MessageCodec::new(&config, &agent_id)?;
+
         // Create the UDP socket using socket2 for advanced configuration
         let socket = Self::create_multicast_socket(&config)?;
 
         // Convert to tokio UdpSocket
         let tokio_socket = UdpSocket::from_std(socket)?;
 
+        let reassembler = fragmentation::Reassembler::new(fragmentation::ReassemblyLimits {
+            timeout: config.reassembly_timeout,
+            max_fragments: (config.max_reassembly_bytes / fragment_size).max(1) as u32,
+            max_pending_messages: config.max_pending_reassemblies,
+            max_pending_bytes: config.max_reassembly_bytes,
+        });
+
+        let retransmit_buffer = RetransmitBuffer::new(config.retransmit_buffer_size);
+        let receive_window = ReceiveWindow::new(NackLimits {
+            interval: config.nack_interval,
+            ..NackLimits::default()
+        });
+
         let manager = Self {
             socket: tokio_socket,
             multicast_addr: config.multicast_address,
             agent_id,
             config,
+            fragment_size,
+            reassembler: Mutex::new(reassembler),
+            codec,
+            epoch: now_millis() as u64,
+            next_sequence: AtomicU64::new(1),
+            retransmit_buffer: Mutex::new(retransmit_buffer),
+            receive_window: Mutex::new(receive_window),
         };
 
         Ok(manager)
@@ -100,8 +466,13 @@ impl NetworkManager {
     fn create_multicast_socket(
         config: &NetworkConfig,
     ) -> Result<std::net::UdpSocket, NetworkError> {
+        let domain = match config.multicast_address {
+            SocketAddr::V4(_) => Domain::IPV4,
+            SocketAddr::V6(_) => Domain::IPV6,
+        };
+
         // Create socket with socket2 for advanced configuration
-        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))
+        let socket = Socket::new(domain, Type::DGRAM, Some(Protocol::UDP))
             .map_err(NetworkError::SocketCreation)?;
 
         // Enable SO_REUSEADDR to allow multiple agents on the same machine
@@ -117,31 +488,129 @@ impl NetworkManager {
             }
         }
 
-        // Bind to the multicast address
-        let bind_addr = SocketAddr::new(
-            std::net::Ipv4Addr::UNSPECIFIED.into(),
-            config.multicast_address.port(),
-        );
+        // Keep IPv6 sockets from also receiving IPv4 traffic on the same port
+        if domain == Domain::IPV6 {
+            socket
+                .set_only_v6(true)
+                .map_err(NetworkError::SocketCreation)?;
+        }
+
+        // Bind to the multicast port on the unspecified address of the matching family
+        let unspecified: IpAddr = match config.multicast_address {
+            SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+            SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+        };
+        let bind_addr = SocketAddr::new(unspecified, config.multicast_address.port());
         socket
             .bind(&bind_addr.into())
             .map_err(NetworkError::SocketCreation)?;
 
         // Join the multicast group
-        if let SocketAddr::V4(multicast_v4) = config.multicast_address {
-            let multicast_ip = *multicast_v4.ip();
-
-            // Determine the interface to use
-            let interface_ip = if let Some(ref interface_str) = config.interface {
-                // Try to parse as IP address first
-                interface_str
-                    .parse::<Ipv4Addr>()
-                    .unwrap_or(Ipv4Addr::UNSPECIFIED)
-            } else {
-                Ipv4Addr::UNSPECIFIED
-            };
+        match config.multicast_address {
+            SocketAddr::V4(multicast_v4) => Self::join_multicast_v4(&socket, multicast_v4, config)?,
+            SocketAddr::V6(multicast_v6) => Self::join_multicast_v6(&socket, multicast_v6, config)?,
+        }
+
+        // Set socket to non-blocking mode for tokio compatibility
+        socket
+            .set_nonblocking(true)
+            .map_err(NetworkError::SocketCreation)?;
+
+        // Convert to std::net::UdpSocket
+        Ok(socket.into())
+    }
+
+    /// List the addresses of this host's network interfaces, only if any interface was configured
+    fn local_interfaces(config: &NetworkConfig) -> Result<Vec<if_addrs::Interface>, NetworkError> {
+        if config.interfaces.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if_addrs::get_if_addrs().map_err(|e| {
+            NetworkError::ConfigError(format!("Failed to list network interfaces: {}", e))
+        })
+    }
+
+    /// Resolve an interface name or IPv4 address to the address used for IPv4 group membership
+    fn resolve_interface_v4(
+        interface: &str,
+        local_interfaces: &[if_addrs::Interface],
+    ) -> Result<Ipv4Addr, NetworkError> {
+        if let Ok(interface_ip) = interface.parse::<Ipv4Addr>() {
+            return Ok(interface_ip);
+        }
+
+        let mut named = local_interfaces
+            .iter()
+            .filter(|local| local.name == interface)
+            .peekable();
+        if named.peek().is_none() {
+            return Err(NetworkError::ConfigError(format!(
+                "Unknown network interface '{}'",
+                interface
+            )));
+        }
+
+        named
+            .find_map(|local| match local.ip() {
+                IpAddr::V4(interface_ip) => Some(interface_ip),
+                IpAddr::V6(_) => None,
+            })
+            .ok_or_else(|| {
+                NetworkError::ConfigError(format!(
+                    "Network interface '{}' has no IPv4 address",
+                    interface
+                ))
+            })
+    }
+
+    /// Resolve an interface name, index or IPv6 address to the index used for IPv6 group membership
+    fn resolve_interface_v6(
+        interface: &str,
+        local_interfaces: &[if_addrs::Interface],
+    ) -> Result<u32, NetworkError> {
+        if let Ok(interface_index) = interface.parse::<u32>() {
+            return Ok(interface_index);
+        }
+
+        let found = match interface.parse::<Ipv6Addr>() {
+            Ok(interface_ip) => local_interfaces
+                .iter()
+                .find(|local| local.ip() == IpAddr::V6(interface_ip)),
+            Err(_) => local_interfaces
+                .iter()
+                .find(|local| local.name == interface),
+        };
+
+        found.and_then(|local| local.index).ok_or_else(|| {
+            NetworkError::ConfigError(format!("Unknown network interface '{}'", interface))
+        })
+    }
+
+    /// Join an IPv4 multicast group on every configured interface and configure TTL and loopback
+    fn join_multicast_v4(
+        socket: &Socket,
+        multicast_v4: SocketAddrV4,
+        config: &NetworkConfig,
+    ) -> Result<(), NetworkError> {
+        let multicast_ip = *multicast_v4.ip();
+
+        // Determine the interfaces to use
+        let local_interfaces = Self::local_interfaces(config)?;
+        let mut interface_ips = Vec::new();
+        for interface in &config.interfaces {
+            let interface_ip = Self::resolve_interface_v4(interface, &local_interfaces)?;
+            if !interface_ips.contains(&interface_ip) {
+                interface_ips.push(interface_ip);
+            }
+        }
+        if interface_ips.is_empty() {
+            interface_ips.push(Ipv4Addr::UNSPECIFIED);
+        }
 
+        for interface_ip in &interface_ips {
             socket
-                .join_multicast_v4(&multicast_ip, &interface_ip)
+                .join_multicast_v4(&multicast_ip, interface_ip)
                 .map_err(|e| {
                     NetworkError::MulticastJoin(format!(
                         "Failed to join multicast group {}:{} on interface {}: {}",
@@ -158,129 +627,347 @@ impl NetworkManager {
                 multicast_v4.port(),
                 interface_ip
             );
-        } else {
-            return Err(NetworkError::ConfigError(
-                "IPv6 multicast not currently supported".to_string(),
-            ));
         }
 
-        // Set socket to non-blocking mode for tokio compatibility
+        // Outgoing datagrams leave via the first configured interface
+        if !interface_ips[0].is_unspecified() {
+            socket
+                .set_multicast_if_v4(&interface_ips[0])
+                .map_err(NetworkError::SocketCreation)?;
+        }
+
         socket
-            .set_nonblocking(true)
+            .set_multicast_ttl_v4(config.multicast_hops)
+            .map_err(NetworkError::SocketCreation)?;
+        socket
+            .set_multicast_loop_v4(config.multicast_loopback)
             .map_err(NetworkError::SocketCreation)?;
 
-        // Convert to std::net::UdpSocket
-        Ok(socket.into())
+        Ok(())
+    }
+
+    /// Join an IPv6 multicast group via interface index on every configured interface
+    /// and configure hop limit and loopback
+    fn join_multicast_v6(
+        socket: &Socket,
+        multicast_v6: SocketAddrV6,
+        config: &NetworkConfig,
+    ) -> Result<(), NetworkError> {
+        let multicast_ip = *multicast_v6.ip();
+
+        // IPv6 group membership is per interface index; 0 lets the OS choose
+        let local_interfaces = Self::local_interfaces(config)?;
+        let mut interface_indexes = Vec::new();
+        for interface in &config.interfaces {
+            let interface_index = Self::resolve_interface_v6(interface, &local_interfaces)?;
+            if !interface_indexes.contains(&interface_index) {
+                interface_indexes.push(interface_index);
+            }
+        }
+        if interface_indexes.is_empty() {
+            interface_indexes.push(multicast_v6.scope_id());
+        }
+
+        for &interface_index in &interface_indexes {
+            socket
+                .join_multicast_v6(&multicast_ip, interface_index)
+                .map_err(|e| {
+                    NetworkError::MulticastJoin(format!(
+                        "Failed to join multicast group [{}]:{} on interface index {}: {}",
+                        multicast_ip,
+                        multicast_v6.port(),
+                        interface_index,
+                        e
+                    ))
+                })?;
+
+            tracing::info!(
+                "Joined multicast group [{}]:{} on interface index {}",
+                multicast_ip,
+                multicast_v6.port(),
+                interface_index
+            );
+        }
+
+        // Send on the first interface we joined on, otherwise link-local groups may leave via another link
+        if interface_indexes[0] != 0 {
+            socket
+                .set_multicast_if_v6(interface_indexes[0])
+                .map_err(NetworkError::SocketCreation)?;
+        }
+
+        socket
+            .set_multicast_hops_v6(config.multicast_hops)
+            .map_err(NetworkError::SocketCreation)?;
+        socket
+            .set_multicast_loop_v6(config.multicast_loopback)
+            .map_err(NetworkError::SocketCreation)?;
+
+        Ok(())
     }
 
     /// Send a message to the multicast group
     pub async fn send_message(&self, message: &AgentMessage) -> Result<(), NetworkError> {
-        // Convert to compressed message based on threshold
-        let compressed_message = message
-            .to_compressed(self.config.compression_threshold)
+        let payload = self.codec.encode(message)?;
+
+        // Split into datagram-sized fragments sharing a fresh message id
+        let message_id = uuid::Uuid::new_v4().to_string();
+        let datagrams = fragmentation::split(&message_id, &payload, self.fragment_size);
+        let fragment_count = datagrams.len();
+
+        let mut bytes_sent = 0;
+        for mut datagram in datagrams {
+            datagram.sender_id = self.agent_id.clone();
+            if self.config.delivery_mode == DeliveryMode::Reliable {
+                datagram.epoch = self.epoch;
+                datagram.sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
+            }
+
+            self.codec.sign_datagram(&mut datagram);
+
+            let encoded = datagram.encode_to_vec();
+            if datagram.sequence > 0 {
+                self.retransmit_buffer
+                    .lock()
+                    .expect("retransmit buffer lock poisoned")
+                    .push(datagram.sequence, encoded.clone());
+            }
+
+            match self.socket.send_to(&encoded, self.multicast_addr).await {
+                Ok(sent) => bytes_sent += sent,
+                Err(e) => {
+                    let error_msg = format!(
+                        "Failed to send fragment {} of {} from agent {} to {}: {}",
+                        datagram.fragment_index + 1,
+                        fragment_count,
+                        self.agent_id,
+                        self.multicast_addr,
+                        e
+                    );
+                    tracing::error!("{}", error_msg);
+                    return Err(NetworkError::SendError(error_msg));
+                }
+            }
+        }
+
+        tracing::debug!(
+            "Sent {} bytes in {} fragment(s) to multicast group {} from agent {}",
+            bytes_sent,
+            fragment_count,
+            self.multicast_addr,
+            self.agent_id
+        );
+        Ok(())
+    }
+
+    /// Ask a peer to resend datagrams that never arrived
+    async fn send_nack(
+        &self,
+        target_id: &str,
+        target_epoch: u64,
+        missing_sequences: Vec<u64>,
+    ) -> Result<(), NetworkError> {
+        tracing::debug!(
+            "Requesting {} missing datagram(s) from agent {}: {:?}",
+            missing_sequences.len(),
+            target_id,
+            missing_sequences
+        );
+
+        let mut datagram = Datagram {
+            sender_id: self.agent_id.clone(),
+            nack: Some(Nack {
+                target_id: target_id.to_string(),
+                target_epoch,
+                missing_sequences,
+            }),
+            ..Default::default()
+        };
+        self.codec.sign_datagram(&mut datagram);
+
+        self.socket
+            .send_to(&datagram.encode_to_vec(), self.multicast_addr)
+            .await
             .map_err(|e| {
-                NetworkError::ConfigError(format!("Failed to compress message: {}", e))
+                NetworkError::SendError(format!(
+                    "Failed to request retransmission from agent {}: {}",
+                    target_id, e
+                ))
             })?;
+        Ok(())
+    }
+
+    /// Resend buffered datagrams that a peer reported missing
+    async fn retransmit(&self, nack: &Nack, requester: SocketAddr) -> Result<(), NetworkError> {
+        // Requests for other agents, or for a stream from before a restart, are not ours to answer
+        if nack.target_id != self.agent_id || nack.target_epoch != self.epoch {
+            return Ok(());
+        }
 
-        // Serialize the compressed message using protobuf
-        let serialized = compressed_message
-            .serialize()
-            .map_err(NetworkError::SerializationError)?;
+        let buffered: Vec<(u64, Option<Vec<u8>>)> = {
+            let buffer = self
+                .retransmit_buffer
+                .lock()
+                .expect("retransmit buffer lock poisoned");
+            nack.missing_sequences
+                .iter()
+                .map(|&sequence| (sequence, buffer.get(sequence).map(<[u8]>::to_vec)))
+                .collect()
+        };
 
-        // Send the serialized message to the multicast address
-        match self.socket.send_to(&serialized, self.multicast_addr).await {
-            Ok(bytes_sent) => {
+        for (sequence, encoded) in buffered {
+            let Some(encoded) = encoded else {
                 tracing::debug!(
-                    "Sent {} bytes to multicast group {} from agent {} (compressed: {}, original size: {})",
-                    bytes_sent,
-                    self.multicast_addr,
-                    self.agent_id,
-                    compressed_message.is_compressed,
-                    compressed_message.original_size
+                    "Cannot retransmit datagram {} requested by {}, it is no longer buffered",
+                    sequence,
+                    requester
                 );
-                Ok(())
-            }
-            Err(e) => {
-                let error_msg = format!(
-                    "Failed to send message from agent {} to {}: {}",
-                    self.agent_id, self.multicast_addr, e
-                );
-                tracing::error!("{}", error_msg);
-                Err(NetworkError::SendError(error_msg))
-            }
+                continue;
+            };
+
+            self.socket
+                .send_to(&encoded, self.multicast_addr)
+                .await
+                .map_err(|e| {
+                    NetworkError::SendError(format!(
+                        "Failed to retransmit datagram {} from agent {}: {}",
+                        sequence, self.agent_id, e
+                    ))
+                })?;
         }
+
+        tracing::debug!(
+            "Retransmitted {} datagram(s) requested by {}",
+            nack.missing_sequences.len(),
+            requester
+        );
+        Ok(())
     }
 
     /// Receive a single message from the multicast group
+    /// Fragments are buffered until every part of a message has arrived
     pub async fn receive_message(&self) -> Result<AgentMessage, NetworkError> {
         let mut buffer = vec![0u8; self.config.buffer_size];
 
-        match self.socket.recv_from(&mut buffer).await {
-            Ok((bytes_received, sender_addr)) => {
-                tracing::debug!(
-                    "Received {} bytes from {} on agent {}",
-                    bytes_received,
-                    sender_addr,
-                    self.agent_id
-                );
+        loop {
+            let (bytes_received, sender_addr) = match self.socket.recv_from(&mut buffer).await {
+                Ok(received) => received,
+                Err(e) => {
+                    let error_msg = format!(
+                        "Failed to receive message on agent {}: {}",
+                        self.agent_id, e
+                    );
+                    tracing::error!("{}", error_msg);
+                    return Err(NetworkError::ReceiveError(error_msg));
+                }
+            };
 
-                // Trim buffer to actual message size
-                buffer.truncate(bytes_received);
-
-                // First deserialize as AgentMessage to check if content is compressed
-                match AgentMessage::deserialize(&buffer) {
-                    Ok(temp_message) => {
-                        // Check if message content is base64 encoded (compressed)
-                        let is_compressed = temp_message.content.starts_with("H4") || // gzip base64 starts with H4
-                                           temp_message.content.starts_with("eJ"); // zlib base64 starts with eJ
-
-                        // Convert to CompressedAgentMessage and then to regular AgentMessage
-                        let compressed_message = CompressedAgentMessage::deserialize(
-                            &buffer,
-                            is_compressed,
-                            temp_message.content.len(),
-                        )
-                        .map_err(|e| {
-                            NetworkError::DeserializationError(prost::DecodeError::new(
-                                format!("Failed to deserialize compressed message: {}", e),
-                            ))
-                        })?;
-
-                        let message = compressed_message.to_agent_message().map_err(|e| {
-                            NetworkError::DeserializationError(prost::DecodeError::new(
-                                format!("Failed to decompress message: {}", e),
-                            ))
-                        })?;
+            tracing::debug!(
+                "Received {} bytes from {} on agent {}",
+                bytes_received,
+                sender_addr,
+                self.agent_id
+            );
+
+            let datagram = Datagram::decode(&buffer[..bytes_received]).map_err(|e| {
+                tracing::warn!("Failed to decode datagram from {}: {}", sender_addr, e);
+                NetworkError::DeserializationError(e)
+            })?;
+
+            // Reject datagrams that were not signed with our swarm key before buffering them
+            self.codec.verify_datagram(&datagram, sender_addr)?;
 
+            if let Some(ref nack) = datagram.nack {
+                if let Err(e) = self.retransmit(nack, sender_addr).await {
+                    tracing::warn!("{}", e);
+                }
+                continue;
+            }
+
+            // Track each peer's sequence stream to drop duplicates and request what went missing
+            if self.config.delivery_mode == DeliveryMode::Reliable
+                && datagram.sequence > 0
+                && datagram.sender_id != self.agent_id
+            {
+                let observation = self
+                    .receive_window
+                    .lock()
+                    .expect("receive window lock poisoned")
+                    .observe(
+                        &datagram.sender_id,
+                        datagram.epoch,
+                        datagram.sequence,
+                        Instant::now(),
+                    );
+
+                match observation {
+                    Observation::Duplicate => {
                         tracing::debug!(
-                            "Successfully deserialized message from agent {} (compressed: {}, original size: {}) with content: '{}'",
-                            message.sender_id,
-                            compressed_message.is_compressed,
-                            compressed_message.original_size,
-                            message.content.chars().take(50).collect::<String>()
+                            "Dropping duplicate datagram {} from agent {}",
+                            datagram.sequence,
+                            datagram.sender_id
                         );
-                        Ok(message)
+                        continue;
                     }
-                    Err(e) => {
-                        let error_msg =
-                            format!("Failed to deserialize message from {}: {}", sender_addr, e);
-                        tracing::warn!("{}", error_msg);
-                        Err(NetworkError::DeserializationError(e))
+                    Observation::New { missing } if !missing.is_empty() => {
+                        if let Err(e) = self
+                            .send_nack(&datagram.sender_id, datagram.epoch, missing)
+                            .await
+                        {
+                            tracing::warn!("{}", e);
+                        }
                     }
+                    Observation::New { .. } => {}
                 }
             }
-            Err(e) => {
-                let error_msg = format!(
-                    "Failed to receive message on agent {}: {}",
-                    self.agent_id, e
+
+            let completed = {
+                let mut reassembler = self
+                    .reassembler
+                    .lock()
+                    .expect("reassembler lock poisoned");
+                let completed = reassembler.accept(datagram, Instant::now());
+                tracing::debug!(
+                    "{} partial message(s) awaiting fragments on agent {}",
+                    reassembler.pending_messages(),
+                    self.agent_id
                 );
-                tracing::error!("{}", error_msg);
-                Err(NetworkError::ReceiveError(error_msg))
-            }
+                completed
+            };
+
+            let serialized = match completed {
+                Ok(Some(serialized)) => serialized,
+                Ok(None) => continue, // Wait for the remaining fragments
+                Err(e) => {
+                    tracing::warn!("Dropping fragment from {}: {}", sender_addr, e);
+                    return Err(NetworkError::ReassemblyError(e));
+                }
+            };
+
+            return self.codec.decode(&serialized, sender_addr);
         }
     }
 }
 
+#[async_trait]
+impl Transport for NetworkManager {
+    async fn send(&self, message: &AgentMessage) -> Result<(), NetworkError> {
+        self.send_message(message).await
+    }
+
+    async fn recv(&self) -> Result<AgentMessage, NetworkError> {
+        self.receive_message().await
+    }
+
+    fn rejected_signature_count(&self) -> u64 {
+        self.codec.rejected_signature_count()
+    }
+
+    fn replay_counters(&self) -> ReplayCounters {
+        self.codec.replay_counters()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -292,15 +979,18 @@ mod tests {
         assert_eq!(config.multicast_address.port(), 8080);
         assert_eq!(config.buffer_size, 65536);
         assert_eq!(config.compression_threshold, 1024);
+        assert_eq!(config.multicast_hops, 1);
+        assert!(config.multicast_loopback);
     }
 
     #[tokio::test]
     async fn test_network_manager_creation_valid_multicast() {
         let config = NetworkConfig {
             multicast_address: "239.255.255.250:8080".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
             buffer_size: 1024,
             compression_threshold: 1024,
+            ..NetworkConfig::default()
         };
 
         let result = NetworkManager::new(config, "test-agent".to_string()).await;
@@ -311,9 +1001,10 @@ mod tests {
     async fn test_network_manager_creation_invalid_multicast() {
         let config = NetworkConfig {
             multicast_address: "192.168.1.1:8080".parse().unwrap(), // Not multicast
-            interface: None,
+            interfaces: Vec::new(),
             buffer_size: 1024,
             compression_threshold: 1024,
+            ..NetworkConfig::default()
         };
 
         let result = NetworkManager::new(config, "test-agent".to_string()).await;
@@ -330,9 +1021,10 @@ mod tests {
     fn test_create_multicast_socket_valid_config() {
         let config = NetworkConfig {
             multicast_address: "239.255.255.250:8080".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
             buffer_size: 1024,
             compression_threshold: 1024,
+            ..NetworkConfig::default()
         };
 
         let result = NetworkManager::create_multicast_socket(&config);
@@ -343,9 +1035,10 @@ mod tests {
     fn test_create_multicast_socket_with_interface() {
         let config = NetworkConfig {
             multicast_address: "239.255.255.250:8080".parse().unwrap(),
-            interface: Some("127.0.0.1".to_string()),
+            interfaces: vec!["127.0.0.1".to_string()],
             buffer_size: 1024,
             compression_threshold: 1024,
+            ..NetworkConfig::default()
         };
 
         let result = NetworkManager::create_multicast_socket(&config);
@@ -368,9 +1061,10 @@ mod tests {
     async fn test_send_message_success() {
         let config = NetworkConfig {
             multicast_address: "239.255.255.250:8080".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
             buffer_size: 1024,
             compression_threshold: 1024,
+            ..NetworkConfig::default()
         };
 
         let manager = NetworkManager::new(config, "test-sender".to_string())
@@ -389,9 +1083,10 @@ mod tests {
     async fn test_send_message_with_empty_content() {
         let config = NetworkConfig {
             multicast_address: "239.255.255.250:8081".parse().unwrap(), // Different port
-            interface: None,
+            interfaces: Vec::new(),
             buffer_size: 1024,
             compression_threshold: 1024,
+            ..NetworkConfig::default()
         };
 
         let manager = NetworkManager::new(config, "test-sender-empty".to_string())
@@ -408,9 +1103,10 @@ mod tests {
     async fn test_send_message_with_unicode() {
         let config = NetworkConfig {
             multicast_address: "239.255.255.250:8082".parse().unwrap(), // Different port
-            interface: None,
+            interfaces: Vec::new(),
             buffer_size: 1024,
             compression_threshold: 1024,
+            ..NetworkConfig::default()
         };
 
         let manager = NetworkManager::new(config, "test-sender-unicode".to_string())
@@ -429,9 +1125,10 @@ mod tests {
     async fn test_send_and_receive_message() {
         let config = NetworkConfig {
             multicast_address: "239.255.255.250:8083".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
             buffer_size: 1024,
             compression_threshold: 1024,
+            ..NetworkConfig::default()
         };
 
         // Create sender and receiver
@@ -476,9 +1173,10 @@ mod tests {
     async fn test_receive_message_with_malformed_data() {
         let config = NetworkConfig {
             multicast_address: "239.255.255.250:8084".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
             buffer_size: 1024,
             compression_threshold: 1024,
+            ..NetworkConfig::default()
         };
 
         let manager = NetworkManager::new(config, "test-malformed".to_string())
@@ -519,9 +1217,10 @@ mod tests {
     async fn test_compression_functionality() {
         let config = NetworkConfig {
             multicast_address: "239.255.255.250:8085".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
             buffer_size: 1024,
             compression_threshold: 100, // Low threshold to force compression
+            ..NetworkConfig::default()
         };
 
         // Create sender and receiver
@@ -569,9 +1268,10 @@ mod tests {
     async fn test_no_compression_for_small_messages() {
         let config = NetworkConfig {
             multicast_address: "239.255.255.250:8086".parse().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
             buffer_size: 1024,
             compression_threshold: 1000, // High threshold to avoid compression
+            ..NetworkConfig::default()
         };
 
         // Create sender and receiver
@@ -613,4 +1313,771 @@ mod tests {
         assert_eq!(received_message.content, test_message.content);
         assert_eq!(received_message.timestamp, test_message.timestamp);
     }
+
+    #[tokio::test]
+    async fn test_plain_text_with_base64_like_prefix() {
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8087".parse().unwrap(),
+            interfaces: Vec::new(),
+            buffer_size: 1024,
+            compression_threshold: 1024,
+            ..NetworkConfig::default()
+        };
+
+        let sender = NetworkManager::new(config.clone(), "test-sender-h4".to_string())
+            .await
+            .unwrap();
+        let receiver = NetworkManager::new(config, "test-receiver-h4".to_string())
+            .await
+            .unwrap();
+
+        // Plain text that the old "H4"/"eJ" prefix heuristic mistook for base64 gzip
+        let test_message = crate::message::AgentMessage::new(
+            "test-sender-h4".to_string(),
+            "H4ppy to help".to_string(),
+        );
+
+        let send_message = test_message.clone();
+        let send_task = tokio::spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            sender.send_message(&send_message).await
+        });
+
+        let receive_task = tokio::time::timeout(
+            tokio::time::Duration::from_secs(2),
+            receiver.receive_message(),
+        );
+
+        let (send_result, receive_result) = tokio::join!(send_task, receive_task);
+
+        assert!(send_result.unwrap().is_ok());
+        let received_message = receive_result.unwrap().unwrap();
+        assert_eq!(received_message.content, "H4ppy to help");
+    }
+
+    fn test_reassembly_limits() -> fragmentation::ReassemblyLimits {
+        fragmentation::ReassemblyLimits {
+            timeout: Duration::from_secs(5),
+            max_fragments: 16,
+            max_pending_messages: 4,
+            max_pending_bytes: 1024,
+        }
+    }
+
+    #[test]
+    fn test_split_small_message_is_single_fragment() {
+        let datagrams = fragmentation::split("msg-1", b"hello", 1200);
+        assert_eq!(datagrams.len(), 1);
+        assert_eq!(datagrams[0].fragment_count, 1);
+        assert_eq!(datagrams[0].payload, b"hello");
+    }
+
+    #[test]
+    fn test_reassembly_out_of_order_fragments() {
+        let payload: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        let mut datagrams = fragmentation::split("msg-ooo", &payload, 100);
+        assert_eq!(datagrams.len(), 10);
+
+        // Deliver in reverse order, with a duplicate thrown in
+        datagrams.reverse();
+        datagrams.insert(3, datagrams[0].clone());
+
+        let mut reassembler = fragmentation::Reassembler::new(test_reassembly_limits());
+        let now = Instant::now();
+        let mut completed = Vec::new();
+        for datagram in datagrams {
+            if let Some(message) = reassembler.accept(datagram, now).unwrap() {
+                completed.push(message);
+            }
+        }
+
+        assert_eq!(completed, vec![payload]);
+        assert_eq!(reassembler.pending_messages(), 0);
+    }
+
+    #[test]
+    fn test_reassembly_lost_fragment_times_out() {
+        let payload = vec![7u8; 500];
+        let mut datagrams = fragmentation::split("msg-lost", &payload, 100);
+        datagrams.remove(2); // Fragment lost in transit
+
+        let mut reassembler = fragmentation::Reassembler::new(test_reassembly_limits());
+        let start = Instant::now();
+        for datagram in datagrams.clone() {
+            assert!(reassembler.accept(datagram, start).unwrap().is_none());
+        }
+        assert_eq!(reassembler.pending_messages(), 1);
+
+        // Once the timeout elapses the partial message is discarded
+        reassembler.expire(start + Duration::from_secs(6));
+        assert_eq!(reassembler.pending_messages(), 0);
+
+        // A late fragment starts a fresh partial message rather than completing the old one
+        let late = fragmentation::split("msg-lost", &payload, 100).remove(2);
+        assert!(
+            reassembler
+                .accept(late, start + Duration::from_secs(6))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_reassembly_enforces_memory_limits() {
+        let mut reassembler = fragmentation::Reassembler::new(test_reassembly_limits());
+        let now = Instant::now();
+
+        // Too many fragments for a single message
+        let mut oversized = fragmentation::split("msg-huge", &[0u8; 40], 2).remove(0);
+        oversized.fragment_count = 20;
+        assert!(reassembler.accept(oversized, now).is_err());
+
+        // Only a bounded number of partial messages are retained
+        for i in 0..6 {
+            let first = fragmentation::split(&format!("msg-{}", i), &[1u8; 200], 100).remove(0);
+            reassembler
+                .accept(first, now + Duration::from_millis(i))
+                .unwrap();
+        }
+        assert_eq!(reassembler.pending_messages(), 4);
+
+        // Buffered bytes are bounded as well
+        let mut reassembler = fragmentation::Reassembler::new(test_reassembly_limits());
+        for i in 0..3 {
+            let first = fragmentation::split(&format!("big-{}", i), &[2u8; 1000], 500).remove(0);
+            reassembler
+                .accept(first, now + Duration::from_millis(i))
+                .unwrap();
+        }
+        assert_eq!(reassembler.pending_messages(), 2);
+    }
+
+    #[test]
+    fn test_reassembly_rejects_invalid_fragment_index() {
+        let mut reassembler = fragmentation::Reassembler::new(test_reassembly_limits());
+        let mut datagram = fragmentation::split("msg-bad", &[0u8; 10], 5).remove(0);
+        datagram.fragment_index = 5;
+        assert!(reassembler.accept(datagram, Instant::now()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_and_receive_fragmented_message() {
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8088".parse().unwrap(),
+            interfaces: Vec::new(),
+            buffer_size: 1024,
+            compression_threshold: usize::MAX, // Keep the body large on the wire
+            ..NetworkConfig::default()
+        };
+
+        let sender = NetworkManager::new(config.clone(), "test-sender-fragment".to_string())
+            .await
+            .unwrap();
+        let receiver = NetworkManager::new(config, "test-receiver-fragment".to_string())
+            .await
+            .unwrap();
+
+        // Several kilobytes of text, far larger than one datagram
+        let long_content = (0..200)
+            .map(|i| format!("Citation {} supports the constructive. ", i))
+            .collect::<String>();
+        let test_message = crate::message::AgentMessage::new(
+            "test-sender-fragment".to_string(),
+            long_content,
+        );
+
+        let send_message = test_message.clone();
+        let send_task = tokio::spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            sender.send_message(&send_message).await
+        });
+
+        let receive_task = tokio::time::timeout(
+            tokio::time::Duration::from_secs(2),
+            receiver.receive_message(),
+        );
+
+        let (send_result, receive_result) = tokio::join!(send_task, receive_task);
+
+        assert!(send_result.unwrap().is_ok());
+        let received_message = receive_result.unwrap().unwrap();
+        assert_eq!(received_message.content, test_message.content);
+    }
+
+    #[test]
+    fn test_create_multicast_socket_ipv6_scopes() {
+        for group in ["[ff02::c0c1]:8090", "[ff05::c0c1]:8090"] {
+            let config = NetworkConfig {
+                multicast_address: group.parse().unwrap(),
+                ..NetworkConfig::default()
+            };
+
+            let result = NetworkManager::create_multicast_socket(&config);
+            assert!(result.is_ok(), "failed to join {}: {:?}", group, result.err());
+        }
+    }
+
+    #[test]
+    fn test_create_multicast_socket_ipv6_invalid_interface() {
+        let config = NetworkConfig {
+            multicast_address: "[ff05::c0c1]:8091".parse().unwrap(),
+            interfaces: vec!["no-such-iface0".to_string()],
+            ..NetworkConfig::default()
+        };
+
+        match NetworkManager::create_multicast_socket(&config) {
+            Err(NetworkError::ConfigError(msg)) => {
+                assert!(msg.contains("Unknown network interface"))
+            }
+            other => panic!("Expected ConfigError, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_and_receive_message_ipv6() {
+        let config = NetworkConfig {
+            multicast_address: "[ff05::c0c1]:8092".parse().unwrap(),
+            multicast_hops: 1,
+            multicast_loopback: true,
+            ..NetworkConfig::default()
+        };
+
+        let sender = NetworkManager::new(config.clone(), "test-sender-v6".to_string())
+            .await
+            .unwrap();
+        let receiver = NetworkManager::new(config, "test-receiver-v6".to_string())
+            .await
+            .unwrap();
+
+        let test_message = crate::message::AgentMessage::new(
+            "test-sender-v6".to_string(),
+            "Hello over IPv6 multicast".to_string(),
+        );
+
+        let send_message = test_message.clone();
+        let send_task = tokio::spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            sender.send_message(&send_message).await
+        });
+
+        let receive_task = tokio::time::timeout(
+            tokio::time::Duration::from_secs(2),
+            receiver.receive_message(),
+        );
+
+        let (send_result, receive_result) = tokio::join!(send_task, receive_task);
+
+        assert!(send_result.unwrap().is_ok());
+        let received_message = receive_result.unwrap().unwrap();
+        assert_eq!(received_message.sender_id, test_message.sender_id);
+        assert_eq!(received_message.content, test_message.content);
+    }
+
+    #[test]
+    fn test_create_multicast_socket_unknown_interface_name() {
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8093".parse().unwrap(),
+            interfaces: vec!["no-such-iface0".to_string()],
+            ..NetworkConfig::default()
+        };
+
+        match NetworkManager::create_multicast_socket(&config) {
+            Err(NetworkError::ConfigError(msg)) => {
+                assert!(msg.contains("Unknown network interface 'no-such-iface0'"))
+            }
+            other => panic!("Expected ConfigError, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_resolve_interface_by_name() {
+        let local_interfaces = if_addrs::get_if_addrs().unwrap();
+
+        for local in &local_interfaces {
+            match local.ip() {
+                IpAddr::V4(interface_ip) => {
+                    let resolved =
+                        NetworkManager::resolve_interface_v4(&local.name, &local_interfaces)
+                            .unwrap();
+                    // Interfaces may carry several addresses; any of them identifies the interface
+                    assert!(local_interfaces.iter().any(|other| other.name == local.name
+                        && other.ip() == IpAddr::V4(resolved)));
+                    assert_eq!(
+                        NetworkManager::resolve_interface_v4(
+                            &interface_ip.to_string(),
+                            &local_interfaces
+                        )
+                        .unwrap(),
+                        interface_ip
+                    );
+                }
+                IpAddr::V6(interface_ip) => {
+                    let resolved =
+                        NetworkManager::resolve_interface_v6(&local.name, &local_interfaces)
+                            .unwrap();
+                    assert_eq!(Some(resolved), local.index);
+                    assert_eq!(
+                        NetworkManager::resolve_interface_v6(
+                            &interface_ip.to_string(),
+                            &local_interfaces
+                        )
+                        .ok(),
+                        local.index
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_create_multicast_socket_on_several_interfaces() {
+        // Join on every local interface that has an IPv4 address, by name
+        let mut names: Vec<String> = if_addrs::get_if_addrs()
+            .unwrap()
+            .into_iter()
+            .filter(|local| local.ip().is_ipv4())
+            .map(|local| local.name)
+            .collect();
+        names.dedup();
+
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8094".parse().unwrap(),
+            interfaces: names,
+            ..NetworkConfig::default()
+        };
+
+        let result = NetworkManager::create_multicast_socket(&config);
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[tokio::test]
+    async fn test_signed_messages_with_matching_key() {
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8095".parse().unwrap(),
+            swarm_key: Some(SwarmKey::new(b"shared-swarm-key-for-tests").unwrap()),
+            ..NetworkConfig::default()
+        };
+
+        let sender = NetworkManager::new(config.clone(), "test-sender-signed".to_string())
+            .await
+            .unwrap();
+        let receiver = NetworkManager::new(config, "test-receiver-signed".to_string())
+            .await
+            .unwrap();
+
+        let test_message = crate::message::AgentMessage::new(
+            "test-sender-signed".to_string(),
+            "Signed message".to_string(),
+        );
+
+        let send_message = test_message.clone();
+        let send_task = tokio::spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            sender.send_message(&send_message).await
+        });
+
+        let receive_task = tokio::time::timeout(
+            tokio::time::Duration::from_secs(2),
+            receiver.receive_message(),
+        );
+
+        let (send_result, receive_result) = tokio::join!(send_task, receive_task);
+
+        assert!(send_result.unwrap().is_ok());
+        assert_eq!(
+            receive_result.unwrap().unwrap().content,
+            test_message.content
+        );
+        assert_eq!(receiver.rejected_signature_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_spoofed_messages_are_rejected() {
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8096".parse().unwrap(),
+            ..NetworkConfig::default()
+        };
+
+        // An unsigned sender (e.g. an attacker on the LAN) and a receiver that requires signatures
+        let spoofer = NetworkManager::new(config.clone(), "judge".to_string())
+            .await
+            .unwrap();
+        let receiver = NetworkManager::new(
+            NetworkConfig {
+                swarm_key: Some(SwarmKey::new(b"shared-swarm-key-for-tests").unwrap()),
+                ..config
+            },
+            "test-receiver-spoofed".to_string(),
+        )
+        .await
+        .unwrap();
+
+        let spoofed = crate::message::AgentMessage::new(
+            "judge".to_string(),
+            "The affirmative wins".to_string(),
+        );
+
+        let send_task = tokio::spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            spoofer.send_message(&spoofed).await
+        });
+
+        let receive_task = tokio::time::timeout(
+            tokio::time::Duration::from_secs(2),
+            receiver.receive_message(),
+        );
+
+        let (send_result, receive_result) = tokio::join!(send_task, receive_task);
+
+        assert!(send_result.unwrap().is_ok());
+        match receive_result.unwrap() {
+            Err(NetworkError::AuthenticationError(msg)) => {
+                assert!(msg.contains("invalid signature"))
+            }
+            other => panic!("Expected AuthenticationError, got {:?}", other),
+        }
+        assert_eq!(receiver.rejected_signature_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_identity_conflicts_are_rejected() {
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8097".parse().unwrap(),
+            ..NetworkConfig::default()
+        };
+        let judge_dir = tempfile::tempdir().unwrap();
+        let impostor_dir = tempfile::tempdir().unwrap();
+        let receiver_dir = tempfile::tempdir().unwrap();
+
+        let judge = NetworkManager::new(
+            NetworkConfig {
+                identity_dir: Some(judge_dir.path().to_path_buf()),
+                ..config.clone()
+            },
+            "judge".to_string(),
+        )
+        .await
+        .unwrap();
+        // Same agent id, different keypair
+        let impostor = NetworkManager::new(
+            NetworkConfig {
+                identity_dir: Some(impostor_dir.path().to_path_buf()),
+                ..config.clone()
+            },
+            "judge".to_string(),
+        )
+        .await
+        .unwrap();
+        let receiver = NetworkManager::new(
+            NetworkConfig {
+                identity_dir: Some(receiver_dir.path().to_path_buf()),
+                ..config
+            },
+            "observer".to_string(),
+        )
+        .await
+        .unwrap();
+
+        let genuine =
+            crate::message::AgentMessage::new("judge".to_string(), "Pro wins".to_string());
+        let forged =
+            crate::message::AgentMessage::new("judge".to_string(), "Con wins".to_string());
+
+        let send_task = tokio::spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            judge.send_message(&genuine).await.unwrap();
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            impostor.send_message(&forged).await.unwrap();
+        });
+
+        let first = tokio::time::timeout(
+            tokio::time::Duration::from_secs(2),
+            receiver.receive_message(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(first.unwrap().content, "Pro wins");
+
+        let second = tokio::time::timeout(
+            tokio::time::Duration::from_secs(2),
+            receiver.receive_message(),
+        )
+        .await
+        .unwrap();
+        match second {
+            Err(NetworkError::IdentityError(msg)) => assert!(msg.contains("Identity conflict")),
+            other => panic!("Expected IdentityError, got {:?}", other),
+        }
+
+        send_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_messages() {
+        let cipher = SwarmCipher::from_passphrase("debate-round-passphrase").unwrap();
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8098".parse().unwrap(),
+            compression_threshold: 100, // Compress first, then encrypt
+            cipher: Some(cipher.clone()),
+            ..NetworkConfig::default()
+        };
+
+        let sender = NetworkManager::new(config.clone(), "test-sender-sealed".to_string())
+            .await
+            .unwrap();
+        let receiver = NetworkManager::new(config.clone(), "test-receiver-sealed".to_string())
+            .await
+            .unwrap();
+        let wrong_key = NetworkManager::new(
+            NetworkConfig {
+                cipher: Some(SwarmCipher::from_passphrase("another-team-passphrase").unwrap()),
+                ..config.clone()
+            },
+            "test-wrong-key".to_string(),
+        )
+        .await
+        .unwrap();
+        let plaintext_only = NetworkManager::new(
+            NetworkConfig {
+                cipher: None,
+                ..config
+            },
+            "test-no-key".to_string(),
+        )
+        .await
+        .unwrap();
+
+        let test_message = crate::message::AgentMessage::new(
+            "test-sender-sealed".to_string(),
+            "Proprietary evidence that should never be readable on the wire. ".repeat(10),
+        );
+
+        let send_message = test_message.clone();
+        let send_task = tokio::spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            sender.send_message(&send_message).await
+        });
+
+        let timeout = tokio::time::Duration::from_secs(2);
+        let (send_result, received, wrong, plaintext) = tokio::join!(
+            send_task,
+            tokio::time::timeout(timeout, receiver.receive_message()),
+            tokio::time::timeout(timeout, wrong_key.receive_message()),
+            tokio::time::timeout(timeout, plaintext_only.receive_message()),
+        );
+
+        assert!(send_result.unwrap().is_ok());
+        assert_eq!(received.unwrap().unwrap().content, test_message.content);
+        assert!(matches!(
+            wrong.unwrap(),
+            Err(NetworkError::DecryptionError(_))
+        ));
+        assert!(matches!(
+            plaintext.unwrap(),
+            Err(NetworkError::DecryptionError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replayed_and_stale_messages_are_rejected() {
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8099".parse().unwrap(),
+            replay_window: Some(Duration::from_secs(30)),
+            ..NetworkConfig::default()
+        };
+
+        let sender = NetworkManager::new(config.clone(), "test-sender-replay".to_string())
+            .await
+            .unwrap();
+        let receiver = NetworkManager::new(config, "test-receiver-replay".to_string())
+            .await
+            .unwrap();
+
+        let original = crate::message::AgentMessage::new(
+            "test-sender-replay".to_string(),
+            "Please respond".to_string(),
+        );
+        let stale = crate::message::AgentMessage {
+            timestamp: original.timestamp - 120_000, // Captured two minutes ago
+            ..crate::message::AgentMessage::new(
+                "test-sender-replay".to_string(),
+                "Old news".to_string(),
+            )
+        };
+
+        let replayed = original.clone();
+        let send_task = tokio::spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            sender.send_message(&original).await.unwrap();
+            sender.send_message(&replayed).await.unwrap();
+            sender.send_message(&stale).await.unwrap();
+        });
+
+        let timeout = tokio::time::Duration::from_secs(2);
+        let first = tokio::time::timeout(timeout, receiver.receive_message())
+            .await
+            .unwrap();
+        assert_eq!(first.unwrap().content, "Please respond");
+
+        for _ in 0..2 {
+            let rejected = tokio::time::timeout(timeout, receiver.receive_message())
+                .await
+                .unwrap();
+            assert!(matches!(rejected, Err(NetworkError::ReplayError(_))));
+        }
+
+        assert_eq!(
+            receiver.replay_counters(),
+            ReplayCounters {
+                outside_window: 1,
+                replayed: 1
+            }
+        );
+        send_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reliable_delivery_requests_missing_datagrams() {
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8100".parse().unwrap(),
+            delivery_mode: DeliveryMode::Reliable,
+            ..NetworkConfig::default()
+        };
+
+        let receiver = NetworkManager::new(config.clone(), "test-receiver-nack".to_string())
+            .await
+            .unwrap();
+        let probe = NetworkManager::new(config, "test-probe-nack".to_string())
+            .await
+            .unwrap();
+
+        // A peer whose second datagram is lost on the way
+        for sequence in [1, 3] {
+            let datagram = Datagram {
+                message_id: format!("lossy-{}", sequence),
+                fragment_index: 0,
+                fragment_count: 1,
+                payload: b"not a frame".to_vec(),
+                sender_id: "test-lossy-peer".to_string(),
+                epoch: 7,
+                sequence,
+                ..Default::default()
+            };
+            probe
+                .socket
+                .send_to(&datagram.encode_to_vec(), probe.multicast_addr)
+                .await
+                .unwrap();
+        }
+
+        let timeout = tokio::time::Duration::from_secs(2);
+        for _ in 0..2 {
+            let result = tokio::time::timeout(timeout, receiver.receive_message())
+                .await
+                .unwrap();
+            assert!(matches!(result, Err(NetworkError::DeserializationError(_))));
+        }
+
+        // The receiver multicasts a request for the missing datagram
+        let mut buffer = vec![0u8; 65536];
+        let nack = loop {
+            let (received, _) = tokio::time::timeout(timeout, probe.socket.recv_from(&mut buffer))
+                .await
+                .unwrap()
+                .unwrap();
+            if let Some(nack) = Datagram::decode(&buffer[..received]).unwrap().nack {
+                break nack;
+            }
+        };
+        assert_eq!(nack.target_id, "test-lossy-peer");
+        assert_eq!(nack.target_epoch, 7);
+        assert_eq!(nack.missing_sequences, vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_reliable_delivery_retransmits_requested_datagrams() {
+        let config = NetworkConfig {
+            multicast_address: "239.255.255.250:8101".parse().unwrap(),
+            delivery_mode: DeliveryMode::Reliable,
+            ..NetworkConfig::default()
+        };
+
+        let sender = std::sync::Arc::new(
+            NetworkManager::new(config.clone(), "test-sender-retransmit".to_string())
+                .await
+                .unwrap(),
+        );
+        let receiver = NetworkManager::new(config.clone(), "test-receiver-retransmit".to_string())
+            .await
+            .unwrap();
+        let probe = NetworkManager::new(config, "test-probe-retransmit".to_string())
+            .await
+            .unwrap();
+
+        // The sender has to be listening to hear retransmission requests
+        let listener = {
+            let sender = std::sync::Arc::clone(&sender);
+            tokio::spawn(async move {
+                loop {
+                    let _ = sender.receive_message().await;
+                }
+            })
+        };
+
+        let first = crate::message::AgentMessage::new(
+            "test-sender-retransmit".to_string(),
+            "First".to_string(),
+        );
+        sender.send_message(&first).await.unwrap();
+
+        let timeout = tokio::time::Duration::from_secs(2);
+        let received = tokio::time::timeout(timeout, receiver.receive_message())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.content, "First");
+
+        // Ask for the first datagram again, as a peer that lost it would
+        let request = Datagram {
+            sender_id: "test-probe-retransmit".to_string(),
+            nack: Some(Nack {
+                target_id: "test-sender-retransmit".to_string(),
+                target_epoch: sender.epoch,
+                missing_sequences: vec![1],
+            }),
+            ..Default::default()
+        };
+        probe
+            .socket
+            .send_to(&request.encode_to_vec(), probe.multicast_addr)
+            .await
+            .unwrap();
+
+        let mut buffer = vec![0u8; 65536];
+        let mut copies = 0;
+        while copies < 2 {
+            let (received, _) = tokio::time::timeout(timeout, probe.socket.recv_from(&mut buffer))
+                .await
+                .unwrap()
+                .unwrap();
+            let datagram = Datagram::decode(&buffer[..received]).unwrap();
+            if datagram.sender_id == "test-sender-retransmit" && datagram.sequence == 1 {
+                copies += 1;
+            }
+        }
+
+        // The retransmitted copy is dropped as a duplicate by peers that already have it
+        let second = crate::message::AgentMessage::new(
+            "test-sender-retransmit".to_string(),
+            "Second".to_string(),
+        );
+        sender.send_message(&second).await.unwrap();
+        let received = tokio::time::timeout(timeout, receiver.receive_message())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.content, "Second");
+
+        listener.abort();
+    }
 }
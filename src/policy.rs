@@ -0,0 +1,217 @@
+use crate::mentions::parse_mentions;
+use crate::message::AgentMessage;
+
+/// When an agent answers a message
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ResponseMode {
+    /// Answer every message
+    #[default]
+    #[value(name = "always")]
+    Always,
+    /// Answer only messages that address or @mention this agent or its role
+    #[value(name = "mention")]
+    Mention,
+    /// Answer only messages containing one of the trigger phrases
+    #[value(name = "trigger")]
+    Trigger,
+    /// Answer a random share of messages
+    #[value(name = "probabilistic")]
+    Probabilistic,
+    /// Ask the LLM whether to answer before answering
+    #[value(name = "llm-check")]
+    LlmCheck,
+}
+
+/// What to do with an incoming message
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Respond,
+    StaySilent,
+    /// Ask the LLM this question and answer only if it says yes
+    AskLlm(String),
+}
+
+/// Decides which incoming messages an agent answers
+///
+/// Messages an agent stays silent on are still part of its transcript, so it
+/// has the whole conversation in view once it does speak.
+#[derive(Debug, Clone)]
+pub struct ResponsePolicy {
+    mode: ResponseMode,
+    /// Phrases that prompt an answer in trigger mode, matched case-insensitively
+    triggers: Vec<String>,
+    /// Share of messages answered in probabilistic mode
+    probability: f64,
+}
+
+impl Default for ResponsePolicy {
+    fn default() -> Self {
+        Self::new(ResponseMode::Always, Vec::new(), 1.0)
+    }
+}
+
+impl ResponsePolicy {
+    /// Create a policy; `triggers` and `probability` only matter in their own modes
+    pub fn new(mode: ResponseMode, triggers: Vec<String>, probability: f64) -> Self {
+        Self {
+            mode,
+            triggers: triggers
+                .into_iter()
+                .map(|trigger| trigger.to_lowercase())
+                .collect(),
+            probability,
+        }
+    }
+
+    /// Decide whether the agent with the given id and role answers `message`
+    pub fn decide(&self, message: &AgentMessage, agent_id: &str, role: &str) -> Decision {
+        let respond = match self.mode {
+            ResponseMode::Always => true,
+            ResponseMode::Mention => is_mentioned(message, agent_id, role),
+            ResponseMode::Trigger => {
+                let content = message.content.to_lowercase();
+                self.triggers
+                    .iter()
+                    .any(|trigger| content.contains(trigger.as_str()))
+            }
+            ResponseMode::Probabilistic => roll_below(self.probability, random_unit()),
+            ResponseMode::LlmCheck => {
+                return Decision::AskLlm(format!(
+                    "Before you answer: should you, {} ({}), reply to the latest message from {}, \
+                     or stay silent and let others speak? {}",
+                    agent_id, role, message.sender_id, YES_NO_INSTRUCTION
+                ));
+            }
+        };
+
+        if respond {
+            Decision::Respond
+        } else {
+            Decision::StaySilent
+        }
+    }
+}
+
+/// How an `AskLlm` question ends, asking for a plain YES or NO
+pub const YES_NO_INSTRUCTION: &str = "Answer YES or NO only.";
+
+/// Whether the LLM's answer to an `AskLlm` question means it wants to respond
+pub fn is_affirmative(answer: &str) -> bool {
+    answer
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+        .starts_with("yes")
+}
+
+/// Whether a message is explicitly meant for this agent rather than the whole swarm
+pub fn is_mentioned(message: &AgentMessage, agent_id: &str, role: &str) -> bool {
+    let addressed = !message.recipients.is_empty() && message.is_addressed_to(agent_id, role);
+    addressed
+        || parse_mentions(&message.content)
+            .iter()
+            .any(|mention| mention == agent_id || mention == role)
+}
+
+/// Whether a uniformly distributed `roll` in [0, 1) falls below `probability`
+fn roll_below(probability: f64, roll: f64) -> bool {
+    roll < probability
+}
+
+/// A uniformly distributed number in [0, 1)
+fn random_unit() -> f64 {
+    match getrandom::u64() {
+        // The top 53 bits fill an f64 mantissa exactly
+        Ok(bits) => (bits >> 11) as f64 / (1u64 << 53) as f64,
+        // Without entropy, lean towards answering rather than silencing the agent
+        Err(_) => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(content: &str) -> AgentMessage {
+        AgentMessage::new("agent-1".to_string(), content.to_string())
+    }
+
+    #[test]
+    fn test_mention_mode_answers_only_when_addressed() {
+        let policy = ResponsePolicy::new(ResponseMode::Mention, Vec::new(), 1.0);
+
+        assert_eq!(
+            policy.decide(&message("General remarks."), "judge-1", "judge"),
+            Decision::StaySilent
+        );
+        assert_eq!(
+            policy.decide(&message("@judge, your verdict?"), "judge-1", "judge"),
+            Decision::Respond
+        );
+        assert_eq!(
+            policy.decide(&message("@judge-1, your verdict?"), "judge-1", "judge"),
+            Decision::Respond
+        );
+
+        let addressed = AgentMessage {
+            recipients: vec!["judge".to_string()],
+            ..message("Your verdict?")
+        };
+        assert_eq!(
+            policy.decide(&addressed, "judge-1", "judge"),
+            Decision::Respond
+        );
+    }
+
+    #[test]
+    fn test_trigger_mode_matches_phrases_case_insensitively() {
+        let policy = ResponsePolicy::new(
+            ResponseMode::Trigger,
+            vec!["Please provide your RFD".to_string()],
+            1.0,
+        );
+
+        assert_eq!(
+            policy.decide(&message("Second rebuttal follows."), "judge", "judge"),
+            Decision::StaySilent
+        );
+        assert_eq!(
+            policy.decide(
+                &message("That concludes 2AR. PLEASE PROVIDE YOUR RFD!"),
+                "judge",
+                "judge"
+            ),
+            Decision::Respond
+        );
+    }
+
+    #[test]
+    fn test_probabilistic_mode() {
+        let never = ResponsePolicy::new(ResponseMode::Probabilistic, Vec::new(), 0.0);
+        let always = ResponsePolicy::new(ResponseMode::Probabilistic, Vec::new(), 1.0);
+        for _ in 0..20 {
+            assert_eq!(never.decide(&message("Hi"), "a", "b"), Decision::StaySilent);
+            assert_eq!(always.decide(&message("Hi"), "a", "b"), Decision::Respond);
+        }
+
+        assert!(roll_below(0.3, 0.29));
+        assert!(!roll_below(0.3, 0.3));
+        assert!((0.0..1.0).contains(&random_unit()));
+    }
+
+    #[test]
+    fn test_llm_check_asks_and_parses_the_answer() {
+        let policy = ResponsePolicy::new(ResponseMode::LlmCheck, Vec::new(), 1.0);
+        match policy.decide(&message("Thoughts?"), "agent-2", "debater") {
+            Decision::AskLlm(question) => {
+                assert!(question.contains("agent-2 (debater)"));
+                assert!(question.contains("from agent-1"));
+            }
+            other => panic!("Expected a question for the LLM, got {:?}", other),
+        }
+
+        assert!(is_affirmative("YES"));
+        assert!(is_affirmative("**Yes.** I should rebut."));
+        assert!(!is_affirmative("No, let the judge speak."));
+        assert!(!is_affirmative(""));
+    }
+}
@@ -1,34 +1,135 @@
-use crate::{llm, message::AgentMessage, message_handler::MessageHandler, network};
-use std::sync::Arc;
-use std::time::Duration;
+use crate::{
+    ballot::{BallotError, request_ballot},
+    floor::FloorControl,
+    health::{self, ErrorReply, Health},
+    llm,
+    message::{AgentMessage, AgentStatus, Heartbeat, MessageKind},
+    mentions::parse_mentions,
+    message_handler::{MessageHandler, MessageHandlerError},
+    moderator::is_rfd_call,
+    network,
+    policy::{Decision, ResponsePolicy, is_affirmative, is_mentioned},
+    retry::{ErrorClass, RetryPolicy},
+    roster::Roster,
+    transcript::{PromptRole, Transcript, TranscriptLimits, push_prompt_message},
+    transport::Transport,
+};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
-use tokio_retry::Retry;
-use tokio_retry::strategy::ExponentialBackoff;
+/// Tells the LLM how to address a single participant instead of the whole swarm
+const ADDRESSING_HINT: &str = "To address one participant only, mention them as @agent-id or @role; otherwise everyone may reply.";
 
 pub struct Processor {
     message_handler: Arc<MessageHandler>,
-    network_manager: Arc<network::NetworkManager>,
+    transport: Arc<dyn Transport>,
     agent_id: String,
     processing_delay_ms: u64,
+    /// Agents currently present in the swarm
+    roster: Arc<Mutex<Roster>>,
+    /// Conversation so far, replayed to the LLM on every call
+    transcript: Arc<Mutex<Transcript>>,
+    /// Decides which messages this agent answers
+    policy: Arc<ResponsePolicy>,
+    /// Turn-taking, if agents wait for the floor before speaking
+    floor: Option<Arc<FloorControl>>,
+    /// Where a judge writes its ballots; judges answer with ballots instead of prose
+    ballot_file: Option<PathBuf>,
+    /// Whether results of the LLM's tool calls are shared with peers
+    broadcast_tool_results: bool,
+    /// How failed LLM requests are retried
+    retry_policy: RetryPolicy,
+    /// What this agent says when its LLM fails to answer
+    error_reply: ErrorReply,
+    /// Failures in a row before this agent is degraded
+    degraded_after: u32,
+    /// How long a degraded agent leaves its LLM alone
+    degraded_cooldown: Duration,
 }
 
 impl Processor {
     pub fn new(
         message_handler: Arc<MessageHandler>,
-        network_manager: Arc<network::NetworkManager>,
+        transport: Arc<dyn Transport>,
         agent_id: String,
         processing_delay_ms: u64,
     ) -> Self {
         Self {
             message_handler,
-            network_manager,
+            transport,
             agent_id,
             processing_delay_ms,
+            roster: Arc::new(Mutex::new(Roster::new())),
+            transcript: Arc::new(Mutex::new(Transcript::new(TranscriptLimits::default()))),
+            policy: Arc::new(ResponsePolicy::default()),
+            floor: None,
+            ballot_file: None,
+            broadcast_tool_results: false,
+            retry_policy: RetryPolicy::default(),
+            error_reply: ErrorReply::default(),
+            degraded_after: 3,
+            degraded_cooldown: Duration::from_secs(60),
         }
     }
 
+    /// Set the policy deciding which messages this agent answers
+    pub fn with_response_policy(mut self, policy: ResponsePolicy) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    /// Set how much of the conversation is sent to the LLM and when it is summarized
+    pub fn with_transcript_limits(mut self, limits: TranscriptLimits) -> Self {
+        self.transcript = Arc::new(Mutex::new(Transcript::new(limits)));
+        self
+    }
+
+    /// Set how failed LLM requests are retried
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set what this agent says in the conversation when its LLM fails to answer
+    pub fn with_error_reply(mut self, error_reply: ErrorReply) -> Self {
+        self.error_reply = error_reply;
+        self
+    }
+
+    /// Degrade after `after` failures in a row, leaving the LLM alone for `cooldown`
+    pub fn with_degraded_state(mut self, after: u32, cooldown: Duration) -> Self {
+        self.degraded_after = after;
+        self.degraded_cooldown = cooldown;
+        self
+    }
+
+    /// Wait for the floor before speaking, so only one agent speaks at a time
+    ///
+    /// An agent that holds the floor for longer than `timeout` loses it.
+    pub fn with_floor_control(mut self, timeout: Duration) -> Self {
+        self.floor = Some(Arc::new(FloorControl::new(
+            Arc::clone(&self.transport),
+            self.agent_id.clone(),
+            timeout,
+        )));
+        self
+    }
+
+    /// Judge rounds: answer with validated ballots, written to `ballot_file` and sent as typed messages
+    pub fn with_ballots(mut self, ballot_file: PathBuf) -> Self {
+        self.ballot_file = Some(ballot_file);
+        self
+    }
+
+    /// Broadcast the results of the LLM's tool calls to peers as structured messages
+    pub fn with_tool_result_broadcast(mut self) -> Self {
+        self.broadcast_tool_results = true;
+        self
+    }
+
     /// Spawn LLM processing task for handling messages and generating responses
     /// This task receives messages from MPSC channel, filters self-messages, and generates LLM responses
     pub async fn spawn_llm_processing_task(
@@ -36,7 +137,16 @@ impl Processor {
         llm_module: llm::LLMModule,
     ) -> JoinHandle<Result<(), String>> {
         let message_handler = Arc::clone(&self.message_handler);
-        let network_manager = Arc::clone(&self.network_manager);
+        let transport = Arc::clone(&self.transport);
+        let roster = Arc::clone(&self.roster);
+        let transcript = Arc::clone(&self.transcript);
+        let policy = Arc::clone(&self.policy);
+        let floor = self.floor.clone();
+        let ballot_file = self.ballot_file.clone();
+        let broadcast_tool_results = self.broadcast_tool_results;
+        let retry_policy = self.retry_policy.clone();
+        let error_reply = self.error_reply;
+        let mut health = Health::new(self.degraded_after, self.degraded_cooldown);
         let agent_id = self.agent_id.clone();
 
         tokio::spawn(async move {
@@ -48,8 +158,12 @@ impl Processor {
 
             info!("Bootstrapping conversation with initial message: '{}'", response_message.content);
 
-            // Broadcast response via network manager
-            network_manager.send_message(&response_message).await?;
+            // Broadcast response to the swarm
+            transport.send(&response_message).await?;
+            transcript
+                .lock()
+                .expect("transcript lock poisoned")
+                .record_own(&response_message.content);
 
             loop {
                 match message_handler.receive_message().await {
@@ -64,44 +178,285 @@ impl Processor {
                         eprintln!("{}: \n {}", message.sender_id, message.content);
                         eprintln!("__________________________________");
                         eprintln!();
-                        // Create chat messages for LLM context
-                        let chat_messages = vec![llm_module.create_user_message(&message.content)];
-
-                        // Retry an async operation
-                        let llm_call_result = Retry::spawn(
-                            ExponentialBackoff::from_millis(100)
-                                .max_delay(Duration::from_secs(10))
-                                .take(5),
-                            || async {
+
+                        // The message is already in the transcript, so staying silent loses nothing
+                        let decision = policy.decide(&message, &agent_id, message_handler.role());
+                        if decision == Decision::StaySilent {
+                            debug!(
+                                "Response policy keeps agent '{}' silent on message from '{}'",
+                                agent_id, message.sender_id
+                            );
+                            continue;
+                        }
+
+                        // A degraded agent leaves its LLM alone until the cooldown is over.
+                        // Whoever calls on it by name is told, so a moderator does not wait
+                        if !health.allows_request(Instant::now()) {
+                            debug!(
+                                "Agent '{}' is degraded, not answering message from '{}'",
+                                agent_id, message.sender_id
+                            );
+                            if !is_mentioned(&message, &agent_id, message_handler.role()) {
+                                continue;
+                            }
+                            let status_message = AgentMessage {
+                                in_reply_to: Some(message.message_id.clone()),
+                                ..AgentMessage::new_status(
+                                    agent_id.clone(),
+                                    health.degraded_status(),
+                                )
+                            };
+                            transport.send(&status_message).await?;
+                            continue;
+                        }
+
+                        // Fold turns that fell out of the window into the running summary
+                        let overflow = transcript
+                            .lock()
+                            .expect("transcript lock poisoned")
+                            .take_overflow();
+                        if let Some(turns) = overflow {
+                            let request = transcript
+                                .lock()
+                                .expect("transcript lock poisoned")
+                                .summary_request(&turns);
+                            match llm_module
+                                .generate_llm_response(&[llm_module.create_user_message(&request)])
+                                .await
+                            {
+                                Ok(summary) => transcript
+                                    .lock()
+                                    .expect("transcript lock poisoned")
+                                    .set_summary(summary),
+                                Err(e) => warn!(
+                                    "Failed to summarize {} earlier turns, dropping them: {}",
+                                    turns.len(),
+                                    e
+                                ),
+                            }
+                        }
+
+                        // Replay the attributed conversation, telling the LLM who is present
+                        let participants = roster
+                            .lock()
+                            .expect("roster lock poisoned")
+                            .take_context();
+                        let prompt = transcript
+                            .lock()
+                            .expect("transcript lock poisoned")
+                            .to_prompt(&format!("{}\n{}", participants, ADDRESSING_HINT));
+
+                        // Let the LLM judge whether the conversation needs this agent right now
+                        if let Decision::AskLlm(question) = decision {
+                            let mut check = prompt.clone();
+                            push_prompt_message(&mut check, PromptRole::User, &question);
+                            match llm_module
+                                .generate_llm_response(&llm_module.create_chat_messages(&check))
+                                .await
+                            {
+                                Ok(answer) if !is_affirmative(&answer) => {
+                                    debug!(
+                                        "LLM chose to stay silent on message from '{}': '{}'",
+                                        message.sender_id, answer
+                                    );
+                                    continue;
+                                }
+                                Ok(_) => {}
+                                Err(e) => warn!(
+                                    "Failed to ask the LLM whether to respond, responding anyway: {}",
+                                    e
+                                ),
+                            }
+                        }
+
+                        // A judge answers the moderator's RFD call with a ballot; if none passes
+                        // validation it falls back to prose. Everything else is answered in prose.
+                        // When the LLM itself fails, asking again in prose would only fail again
+                        let rfd_call = ballot_file.is_some() && {
+                            let roster = roster.lock().expect("roster lock poisoned");
+                            is_rfd_call(
+                                &message,
+                                roster.role_of(&message.sender_id),
+                                &agent_id,
+                                message_handler.role(),
+                            )
+                        };
+                        let mut ballot_failure = None;
+                        let ballot = match ballot_file {
+                            Some(_) if rfd_call => {
+                                let llm_module = &llm_module;
+                                let retry_policy = &retry_policy;
+                                match request_ballot(prompt.clone(), |prompt| async move {
+                                    let chat_messages = llm_module.create_chat_messages(&prompt);
+                                    retry_policy
+                                        .run(|| llm_module.generate_llm_response(&chat_messages))
+                                        .await
+                                })
+                                .await
+                                {
+                                    Ok(ballot) => Some(ballot),
+                                    Err(BallotError::Llm(e)) => {
+                                        ballot_failure = Some(e);
+                                        None
+                                    }
+                                    Err(e) => {
+                                        error!("No valid ballot, deciding in prose: {}", e);
+                                        None
+                                    }
+                                }
+                            }
+                            _ => None,
+                        };
+
+                        let mut tool_outcomes = Vec::new();
+                        let answered = match (ballot_failure, &ballot) {
+                            (Some(e), _) => Err(e),
+                            (None, Some(ballot)) => Ok(ballot.to_string()),
+                            (None, None) => {
+                                let chat_messages = llm_module.create_chat_messages(&prompt);
+
+                                // Retry transient failures, give up at once on fatal ones
                                 debug!("Invoking LLM.");
+                                let llm_call_result = llm_module
+                                    .generate_llm_response_with_tools(&chat_messages, &retry_policy)
+                                    .await;
 
-                                llm_module.generate_llm_response(&chat_messages).await
-                            },
-                        )
-                        .await;
+                                llm_call_result.map(|answer| {
+                                    tool_outcomes = answer.tool_outcomes;
+                                    answer.content
+                                })
+                            }
+                        };
+
+                        // Failures reach peers as status messages, never as error text in the conversation
+                        let response_content = match answered {
+                            Ok(content) => {
+                                if let Some(status) = health.record_success() {
+                                    info!("Agent '{}' has recovered", agent_id);
+                                    transport
+                                        .send(&AgentMessage::new_status(agent_id.clone(), status))
+                                        .await?;
+                                }
+                                content
+                            }
+                            Err(e) => {
+                                error!(
+                                    "Failed to answer message from '{}': {}",
+                                    message.sender_id, e
+                                );
+                                let status = health
+                                    .record_failure(ErrorClass::of(&e).to_string(), Instant::now());
+                                if status.status() == AgentStatus::Degraded {
+                                    warn!(
+                                        "Agent '{}' is degraded after {} failures in a row, leaving the LLM alone for a while",
+                                        agent_id, status.consecutive_failures
+                                    );
+                                }
+                                let status_message = AgentMessage {
+                                    in_reply_to: Some(message.message_id.clone()),
+                                    ..AgentMessage::new_status(agent_id.clone(), status)
+                                };
+                                transport.send(&status_message).await?;
 
-                        let response_content = match llm_call_result {
-                            Ok(response) => response,
-                            Err(e) => e.to_string(),
+                                match error_reply.text(&agent_id) {
+                                    Some(text) => text,
+                                    None => continue,
+                                }
+                            }
                         };
 
-                        // Say it
-                        match llm_module.say(&response_content).await {
-                            Ok(_) => info!("Speaking..."),
-                            Err(e) => error!("ElevenLabs error: {e}"),
+                        // Wait for our turn, so agents neither talk over nor cross each other
+                        if let Some(ref floor) = floor
+                            && let Err(e) = floor.acquire().await
+                        {
+                            warn!("Failed to request the floor, speaking anyway: {}", e);
                         }
 
-                        debug!(
-                            "Sending response to message from '{}': '{}'",
-                            message.sender_id, response_content
-                        );
+                        // Speak inside a block, so the floor is released even when sending fails
+                        let spoken: Result<(), network::NetworkError> = async {
+                            // Say it
+                            match llm_module.say(&response_content).await {
+                                Ok(_) => info!("Speaking..."),
+                                Err(e) => error!("ElevenLabs error: {e}"),
+                            }
+
+                            debug!(
+                                "Sending response to message from '{}': '{}'",
+                                message.sender_id, response_content
+                            );
+
+                            // Mentions of present agents or roles address the response to them alone
+                            let recipients: Vec<String> = {
+                                let roster = roster.lock().expect("roster lock poisoned");
+                                parse_mentions(&response_content)
+                                    .into_iter()
+                                    .filter(|name| roster.knows(name))
+                                    .collect()
+                            };
+
+                            // Create response message, threaded onto the message it answers
+                            let response_message = AgentMessage {
+                                recipients,
+                                ..AgentMessage::new_reply(
+                                    agent_id.clone(),
+                                    response_content,
+                                    message.message_id.clone(),
+                                )
+                            };
+
+                            // Peers get the evidence before the response that relies on it
+                            if broadcast_tool_results {
+                                for outcome in &tool_outcomes {
+                                    match outcome.to_message(agent_id.clone()) {
+                                        Ok(tool_message) => transport.send(&tool_message).await?,
+                                        Err(e) => error!("Failed to encode tool result: {}", e),
+                                    }
+                                }
+                            }
+
+                            // Send the response to the swarm
+                            transport.send(&response_message).await?;
+                            transcript
+                                .lock()
+                                .expect("transcript lock poisoned")
+                                .record_own(&response_message.content);
 
-                        // Create response message
-                        let response_message =
-                            AgentMessage::new(agent_id.clone(), response_content);
+                            // Tournaments score rounds from the ballot file and the typed message
+                            if let Some(ref ballot) = ballot
+                                && let Some(ref path) = ballot_file
+                            {
+                                match ballot.write_to(path) {
+                                    Ok(()) => info!("Ballot written to {}", path.display()),
+                                    Err(e) => {
+                                        error!(
+                                            "Failed to write ballot to {}: {}",
+                                            path.display(),
+                                            e
+                                        )
+                                    }
+                                }
+                                match ballot.to_message(agent_id.clone()) {
+                                    Ok(ballot_message) => {
+                                        let ballot_message = AgentMessage {
+                                            in_reply_to: Some(message.message_id.clone()),
+                                            ..ballot_message
+                                        };
+                                        transport.send(&ballot_message).await?;
+                                    }
+                                    Err(e) => error!("Failed to encode ballot: {}", e),
+                                }
+                            }
+                            Ok(())
+                        }
+                        .await;
 
-                        // Broadcast response via network manager
-                        network_manager.send_message(&response_message).await?;
+                        if let Some(ref floor) = floor
+                            && let Err(e) = floor.release().await
+                        {
+                            // Peers take the floor back once our turn times out
+                            warn!("Failed to release the floor: {}", e);
+                        }
+                        spoken?;
                     }
                     Err(e) => {
                         error!("Message channel error: {}", e);
@@ -112,24 +467,124 @@ impl Processor {
         })
     }
 
-    /// Spawn UDP message intake task for continuous message reception
-    /// This task receives messages from UDP multicast and sends them to MPSC channel
-    pub async fn spawn_udp_intake_task(&self) -> JoinHandle<Result<(), String>> {
-        let network_manager = Arc::clone(&self.network_manager);
+    /// Spawn presence task that sends periodic heartbeats and announces peers that went silent
+    pub async fn spawn_presence_task(
+        &self,
+        heartbeat: Heartbeat,
+        interval: Duration,
+    ) -> JoinHandle<Result<(), String>> {
+        let transport = Arc::clone(&self.transport);
+        let roster = Arc::clone(&self.roster);
+        let agent_id = self.agent_id.clone();
+
+        tokio::spawn(async move {
+            info!(
+                "Starting presence task for agent '{}' with a heartbeat every {:?}",
+                agent_id, interval
+            );
+
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+
+                // A lost heartbeat is covered by the next one, so failures are not fatal
+                let message = AgentMessage::new_heartbeat(agent_id.clone(), heartbeat.clone());
+                if let Err(e) = transport.send(&message).await {
+                    warn!("Failed to send heartbeat: {}", e);
+                }
+
+                let departed = roster
+                    .lock()
+                    .expect("roster lock poisoned")
+                    .expire(Instant::now());
+                for event in departed {
+                    info!("{}", event);
+                }
+            }
+        })
+    }
+
+    /// Tell the swarm this agent is leaving, so peers do not wait for it to time out
+    pub async fn announce_departure(&self, heartbeat: Heartbeat) -> Result<(), String> {
+        let message = AgentMessage::new_heartbeat(
+            self.agent_id.clone(),
+            Heartbeat {
+                leaving: true,
+                ..heartbeat
+            },
+        );
+        Ok(self.transport.send(&message).await?)
+    }
+
+    /// Spawn message intake task for continuous message reception
+    /// This task receives messages from the transport and sends them to MPSC channel
+    pub async fn spawn_intake_task(&self) -> JoinHandle<Result<(), String>> {
+        let transport = Arc::clone(&self.transport);
         let message_handler = Arc::clone(&self.message_handler);
+        let roster = Arc::clone(&self.roster);
+        let transcript = Arc::clone(&self.transcript);
+        let floor = self.floor.clone();
         let processing_delay_ms = self.processing_delay_ms;
 
         tokio::spawn(async move {
             info!(
-                "Starting UDP message intake task for agent '{}'",
+                "Starting message intake task for agent '{}'",
                 message_handler.agent_id()
             );
 
             loop {
-                match network_manager.receive_message().await {
+                match transport.recv().await {
+                    Ok(message) if message_handler.is_other_session(&message) => {
+                        // Neither presence nor chat from other sessions concerns this agent
+                        debug!(
+                            "Ignoring message from '{}' in session '{}'",
+                            message.sender_id, message.session
+                        );
+                    }
+                    Ok(AgentMessage {
+                        sender_id,
+                        kind: Some(MessageKind::Heartbeat(heartbeat)),
+                        ..
+                    }) => {
+                        // Presence is tracked here and never reaches the LLM
+                        if sender_id != message_handler.agent_id()
+                            && let Some(event) = roster
+                                .lock()
+                                .expect("roster lock poisoned")
+                                .observe(&sender_id, &heartbeat, Instant::now())
+                        {
+                            info!("{}", event);
+                        }
+                    }
+                    Ok(
+                        ref message @ AgentMessage {
+                            kind: Some(MessageKind::Status(ref status)),
+                            ..
+                        },
+                    ) => {
+                        // Peers report failures here instead of in the conversation
+                        if message.sender_id != message_handler.agent_id() {
+                            info!("{}", health::describe(message, status));
+                        }
+                    }
+                    Ok(message) if message.kind.is_some() => {
+                        // Control and structured messages are not part of the conversation
+                        let handled = floor.as_ref().is_some_and(|floor| floor.observe(&message));
+                        if !handled {
+                            debug!(
+                                "Ignoring {:?} from '{}': no handler for this kind of message",
+                                message.kind, message.sender_id
+                            );
+                        }
+                    }
                     Ok(message) => {
+                        roster
+                            .lock()
+                            .expect("roster lock poisoned")
+                            .touch(&message.sender_id, Instant::now());
+
                         debug!(
-                            "UDP intake received message from '{}' with content: '{}'",
+                            "Intake received message from '{}' with content: '{}'",
                             message.sender_id,
                             message.content.chars().take(50).collect::<String>()
                         );
@@ -137,15 +592,32 @@ impl Processor {
                         // Introduce an artificial delay to simulate processing time
                         tokio::time::sleep(Duration::from_millis(processing_delay_ms)).await;
 
+                        if let Err(MessageHandlerError::DuplicateMessage(id)) =
+                            message_handler.mark_seen(&message)
+                        {
+                            debug!("Skipping duplicate message '{}'", id);
+                            continue;
+                        }
+
+                        // Every peer message is part of the conversation, even ones addressed to others.
+                        // It is recorded before it is forwarded, so the prompt answering it includes it.
+                        if message.sender_id != message_handler.agent_id() {
+                            transcript
+                                .lock()
+                                .expect("transcript lock poisoned")
+                                .record_peer(&message.sender_id, &message.content);
+                        }
+
                         // Send message to MPSC channel (non-blocking)
-                        if let Err(e) = message_handler.try_send_message(message.clone()) {
-                            warn!("Failed to send message to channel: {}", e);
-                            // Continue processing other messages even if channel is full
-                        } else {
-                            debug!(
+                        match message_handler.forward_message(message.clone()) {
+                            Ok(()) => debug!(
                                 "Successfully forwarded message from '{}' to processing channel",
                                 message.sender_id
-                            );
+                            ),
+                            Err(e) => {
+                                warn!("Failed to send message to channel: {}", e);
+                                // Continue processing other messages even if channel is full
+                            }
                         }
                     }
                     Err(network::NetworkError::DeserializationError(e)) => {
@@ -153,12 +625,346 @@ impl Processor {
                         warn!("Received malformed message, skipping: {}", e);
                         continue;
                     }
+                    Err(network::NetworkError::AuthenticationError(e)) => {
+                        // Spoofed or foreign traffic is not a malformed message; count it separately
+                        warn!(
+                            "Rejected unauthenticated message ({} total): {}",
+                            transport.rejected_signature_count(),
+                            e
+                        );
+                        continue;
+                    }
+                    Err(network::NetworkError::IdentityError(e)) => {
+                        // Unsigned, forged or conflicting identities are dropped, never answered
+                        warn!("Rejected message with unverified identity: {}", e);
+                        continue;
+                    }
+                    Err(network::NetworkError::DecryptionError(e)) => {
+                        // Traffic for another key (or none) is expected on a shared group; ignore it
+                        debug!("Ignoring message that could not be decrypted: {}", e);
+                        continue;
+                    }
+                    Err(network::NetworkError::ReplayError(e)) => {
+                        let counters = transport.replay_counters();
+                        warn!(
+                            "Rejected replayed message ({} outside window, {} replayed so far): {}",
+                            counters.outside_window, counters.replayed, e
+                        );
+                        continue;
+                    }
+                    Err(network::NetworkError::UnsupportedProtocol(e)) => {
+                        // The codec already warned about this sender once
+                        debug!("Skipping message in an unsupported protocol: {}", e);
+                        continue;
+                    }
+                    Err(network::NetworkError::ReassemblyError(e)) => {
+                        // Bad or conflicting fragments only affect that one message
+                        warn!("Failed to reassemble message, skipping: {}", e);
+                        continue;
+                    }
                     Err(e) => {
-                        error!("UDP message reception error: {}", e);
-                        return Err(format!("UDP intake task failed: {}", e));
+                        error!("Message reception error: {}", e);
+                        return Err(format!("Intake task failed: {}", e));
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::NetworkConfig;
+    use crate::transport::{BUS_CAPACITY, MemoryTransport, MessageBus};
+
+    #[tokio::test]
+    async fn test_intake_forwards_messages_from_transport() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let peer = MemoryTransport::new(&bus, &config, "peer".to_string()).unwrap();
+        let transport: Arc<dyn Transport> =
+            Arc::new(MemoryTransport::new(&bus, &config, "agent".to_string()).unwrap());
+
+        let message_handler = Arc::new(MessageHandler::new("agent".to_string(), 10));
+        let processor = Processor::new(
+            Arc::clone(&message_handler),
+            transport,
+            "agent".to_string(),
+            0,
+        );
+        let intake = processor.spawn_intake_task().await;
+
+        let message = AgentMessage::new("peer".to_string(), "Your move".to_string());
+        peer.send(&message).await.unwrap();
+
+        let received = tokio::time::timeout(
+            Duration::from_secs(2),
+            message_handler.receive_message(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(received.message_id, message.message_id);
+        assert_eq!(received.content, "Your move");
+
+        intake.abort();
+    }
+
+    #[tokio::test]
+    async fn test_intake_records_every_peer_message_in_transcript() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let peer = MemoryTransport::new(&bus, &config, "peer".to_string()).unwrap();
+        let transport: Arc<dyn Transport> =
+            Arc::new(MemoryTransport::new(&bus, &config, "agent".to_string()).unwrap());
+
+        let message_handler = Arc::new(MessageHandler::new("agent".to_string(), 10));
+        let processor = Processor::new(
+            Arc::clone(&message_handler),
+            transport,
+            "agent".to_string(),
+            0,
+        );
+        let intake = processor.spawn_intake_task().await;
+
+        // A cross-examination of someone else is not answered but still witnessed
+        let message = AgentMessage {
+            recipients: vec!["other".to_string()],
+            ..AgentMessage::new("peer".to_string(), "Your evidence, @other?".to_string())
+        };
+        peer.send(&message).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let prompt = processor
+            .transcript
+            .lock()
+            .unwrap()
+            .to_prompt("Participants: peer.");
+        assert_eq!(
+            prompt[0].content,
+            "Participants: peer.\n\nThe conversation so far:\n\n[peer]: Your evidence, @other?"
+        );
+        intake.abort();
+    }
+
+    #[tokio::test]
+    async fn test_heartbeats_update_roster_without_reaching_llm() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let transport: Arc<dyn Transport> =
+            Arc::new(MemoryTransport::new(&bus, &config, "agent".to_string()).unwrap());
+        let peer: Arc<dyn Transport> =
+            Arc::new(MemoryTransport::new(&bus, &config, "peer".to_string()).unwrap());
+
+        let message_handler = Arc::new(MessageHandler::new("agent".to_string(), 10));
+        let processor = Processor::new(
+            Arc::clone(&message_handler),
+            transport,
+            "agent".to_string(),
+            0,
+        );
+        let intake = processor.spawn_intake_task().await;
+
+        let peer_processor = Processor::new(
+            Arc::new(MessageHandler::new("peer".to_string(), 10)),
+            peer,
+            "peer".to_string(),
+            0,
+        );
+        let heartbeat = Heartbeat {
+            model: "llama2".to_string(),
+            backend: "local".to_string(),
+            role: "judge".to_string(),
+            interval_ms: 50,
+            leaving: false,
+        };
+        let presence = peer_processor
+            .spawn_presence_task(heartbeat.clone(), Duration::from_millis(50))
+            .await;
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(
+            processor.roster.lock().unwrap().take_context(),
+            "Participants in the conclave: peer (judge, llama2 via local).\n\
+             peer (judge, llama2 via local) joined the conclave."
+        );
+
+        presence.abort();
+        peer_processor.announce_departure(heartbeat).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            processor.roster.lock().unwrap().take_context(),
+            "No other participants are currently present in the conclave.\n\
+             peer left the conclave."
+        );
+
+        // Heartbeats are never forwarded to the LLM
+        assert!(
+            tokio::time::timeout(
+                Duration::from_millis(50),
+                message_handler.receive_message()
+            )
+            .await
+            .is_err()
+        );
+        intake.abort();
+    }
+
+    /// Run an agent with the cassette flag `flag`, returning its answers to two messages
+    ///
+    /// A judge joins before the first message, or between the two if `join_late`.
+    async fn debate_with_cassette(
+        flag: &str,
+        cassette: &std::path::Path,
+        join_late: bool,
+    ) -> Vec<String> {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let peer = MemoryTransport::new(&bus, &config, "peer".to_string()).unwrap();
+        let transport: Arc<dyn Transport> =
+            Arc::new(MemoryTransport::new(&bus, &config, "agent".to_string()).unwrap());
+
+        let args = <crate::cli::AgentArgs as clap::Parser>::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "agent",
+            "--llm-backend",
+            "mock",
+            flag,
+            cassette.to_str().unwrap(),
+        ])
+        .unwrap();
+        let llm_module = llm::LLMModule::new(&args).unwrap();
+
+        let message_handler = Arc::new(MessageHandler::new("agent".to_string(), 10));
+        let processor = Processor::new(message_handler, transport, "agent".to_string(), 0);
+        let intake = processor.spawn_intake_task().await;
+        let llm_task = processor.spawn_llm_processing_task(llm_module).await;
+
+        let judge = AgentMessage::new_heartbeat(
+            "judge-1".to_string(),
+            Heartbeat {
+                model: "llama2".to_string(),
+                backend: "local".to_string(),
+                role: "judge".to_string(),
+                interval_ms: 60_000,
+                leaving: false,
+            },
+        );
+        let mut answers = Vec::new();
+        for (turn, content) in ["Your case?", "Your rebuttal?"].into_iter().enumerate() {
+            if turn == usize::from(join_late) {
+                peer.send(&judge).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            let question = AgentMessage::new("peer".to_string(), content.to_string());
+            peer.send(&question).await.unwrap();
+            let answer = tokio::time::timeout(Duration::from_secs(5), async {
+                loop {
+                    let message = peer.recv().await.unwrap();
+                    if message.in_reply_to.as_deref() == Some(question.message_id.as_str()) {
+                        return message;
                     }
                 }
+            })
+            .await
+            .unwrap();
+            // A status message here would mean the replay missed
+            assert!(
+                answer.kind.is_none(),
+                "expected a chat answer, got {:?}",
+                answer.kind
+            );
+            answers.push(answer.content);
+        }
+
+        intake.abort();
+        llm_task.abort();
+        answers
+    }
+
+    #[tokio::test]
+    async fn test_replay_does_not_depend_on_when_peers_joined() {
+        let dir = tempfile::tempdir().unwrap();
+        let cassette = dir.path().join("agent.json");
+
+        let recorded = debate_with_cassette("--record", &cassette, false).await;
+        let replayed = debate_with_cassette("--replay", &cassette, true).await;
+        assert_eq!(replayed, recorded);
+    }
+
+    #[tokio::test]
+    async fn test_degraded_agent_only_answers_messages_naming_it() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let peer = MemoryTransport::new(&bus, &config, "peer".to_string()).unwrap();
+        let transport: Arc<dyn Transport> =
+            Arc::new(MemoryTransport::new(&bus, &config, "agent".to_string()).unwrap());
+
+        // Replaying an empty cassette fails every request
+        let dir = tempfile::tempdir().unwrap();
+        let cassette = dir.path().join("empty.json");
+        std::fs::write(
+            &cassette,
+            format!(
+                r#"{{"schema": "{}", "interactions": []}}"#,
+                crate::cassette::CASSETTE_SCHEMA
+            ),
+        )
+        .unwrap();
+        let args = <crate::cli::AgentArgs as clap::Parser>::try_parse_from([
+            "conclave",
+            "--agent-id",
+            "agent",
+            "--llm-backend",
+            "mock",
+            "--replay",
+            cassette.to_str().unwrap(),
+        ])
+        .unwrap();
+        let llm_module = llm::LLMModule::new(&args).unwrap();
+
+        let message_handler = Arc::new(MessageHandler::new("agent".to_string(), 10));
+        let processor = Processor::new(message_handler, transport, "agent".to_string(), 0)
+            .with_degraded_state(1, Duration::from_secs(60));
+        let intake = processor.spawn_intake_task().await;
+        let llm_task = processor.spawn_llm_processing_task(llm_module).await;
+
+        let failing = AgentMessage::new("peer".to_string(), "Your case?".to_string());
+        let broadcast = AgentMessage::new("peer".to_string(), "Anyone?".to_string());
+        let mentioned = AgentMessage::new("peer".to_string(), "@agent, still there?".to_string());
+        let addressed = AgentMessage {
+            recipients: vec!["agent".to_string()],
+            ..AgentMessage::new("peer".to_string(), "Your rebuttal?".to_string())
+        };
+        for message in [&failing, &broadcast, &mentioned, &addressed] {
+            peer.send(message).await.unwrap();
+        }
+
+        let mut statuses = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while statuses.len() < 3 {
+                let message = peer.recv().await.unwrap();
+                if let Some(MessageKind::Status(status)) = message.kind {
+                    assert_eq!(status.status(), AgentStatus::Degraded);
+                    statuses.push(message.in_reply_to.unwrap());
+                }
             }
         })
+        .await
+        .unwrap();
+        // The broadcast is not answered while degraded
+        assert_eq!(
+            statuses,
+            vec![
+                failing.message_id,
+                mentioned.message_id,
+                addressed.message_id
+            ]
+        );
+
+        intake.abort();
+        llm_task.abort();
     }
 }
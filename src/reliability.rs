@@ -0,0 +1,396 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// How datagrams are delivered to the swarm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DeliveryMode {
+    /// Send every datagram once; lost datagrams are never recovered
+    #[default]
+    #[value(name = "best-effort")]
+    BestEffort,
+    /// Number datagrams per sender and retransmit the ones peers report missing
+    #[value(name = "reliable")]
+    Reliable,
+}
+
+/// Limits applied while tracking gaps in peers' sequence streams
+#[derive(Debug, Clone)]
+pub struct NackLimits {
+    /// Minimum time between two requests for the same missing datagram
+    pub interval: Duration,
+    /// Number of requests for a missing datagram before giving up on it
+    pub max_attempts: u32,
+    /// Largest gap worth recovering; larger gaps resynchronize the stream instead
+    pub max_missing: u64,
+}
+
+impl Default for NackLimits {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(200),
+            max_attempts: 5,
+            max_missing: 256,
+        }
+    }
+}
+
+/// Bounded buffer of recently sent datagrams, kept for retransmission
+pub struct RetransmitBuffer {
+    capacity: usize,
+    /// Encoded datagrams in ascending sequence order
+    datagrams: VecDeque<(u64, Vec<u8>)>,
+}
+
+impl RetransmitBuffer {
+    /// Create a new RetransmitBuffer holding at most `capacity` datagrams
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            datagrams: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Remember an encoded datagram, evicting the oldest one when full
+    pub fn push(&mut self, sequence: u64, encoded: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.datagrams.len() == self.capacity {
+            self.datagrams.pop_front();
+        }
+        self.datagrams.push_back((sequence, encoded));
+    }
+
+    /// Look up a previously sent datagram by sequence number
+    pub fn get(&self, sequence: u64) -> Option<&[u8]> {
+        self.datagrams
+            .binary_search_by_key(&sequence, |(buffered, _)| *buffered)
+            .ok()
+            .map(|index| self.datagrams[index].1.as_slice())
+    }
+}
+
+/// Outcome of observing a sequenced datagram from a peer
+#[derive(Debug, PartialEq)]
+pub enum Observation {
+    /// First copy of this datagram; `missing` lists the sequence numbers to request now
+    New { missing: Vec<u64> },
+    /// Already received (or from an older epoch), so it must be dropped
+    Duplicate,
+}
+
+/// A datagram that has been detected as missing
+struct MissingDatagram {
+    last_requested: Option<Instant>,
+    attempts: u32,
+}
+
+/// Receive state for one peer's sequence stream
+struct PeerStream {
+    epoch: u64,
+    next_expected: u64,
+    missing: BTreeMap<u64, MissingDatagram>,
+}
+
+impl PeerStream {
+    fn new(epoch: u64, sequence: u64) -> Self {
+        // Join the stream where we found it rather than asking for its whole history
+        Self {
+            epoch,
+            next_expected: sequence + 1,
+            missing: BTreeMap::new(),
+        }
+    }
+}
+
+/// Detects gaps in peers' sequence streams and decides which datagrams to request again
+pub struct ReceiveWindow {
+    limits: NackLimits,
+    peers: HashMap<String, PeerStream>,
+    /// Number of missing datagrams that were never recovered
+    abandoned: u64,
+}
+
+impl ReceiveWindow {
+    /// Create a new ReceiveWindow with the given limits
+    pub fn new(limits: NackLimits) -> Self {
+        Self {
+            limits,
+            peers: HashMap::new(),
+            abandoned: 0,
+        }
+    }
+
+    /// Record a datagram from a peer and work out which of its predecessors to request
+    ///
+    /// Missing datagrams are only requested again when later traffic from the same
+    /// peer arrives, so a lost final datagram is not noticed until the peer speaks again.
+    pub fn observe(
+        &mut self,
+        sender_id: &str,
+        epoch: u64,
+        sequence: u64,
+        now: Instant,
+    ) -> Observation {
+        let peer = match self.peers.get_mut(sender_id) {
+            Some(peer) if epoch == peer.epoch => peer,
+            Some(peer) if epoch < peer.epoch => return Observation::Duplicate,
+            Some(peer) => {
+                // The peer restarted and numbers its datagrams from scratch
+                debug!("Agent '{}' started a new sequence stream", sender_id);
+                self.abandoned += peer.missing.len() as u64;
+                *peer = PeerStream::new(epoch, sequence);
+                return Observation::New {
+                    missing: Vec::new(),
+                };
+            }
+            None => {
+                self.peers
+                    .insert(sender_id.to_string(), PeerStream::new(epoch, sequence));
+                return Observation::New {
+                    missing: Vec::new(),
+                };
+            }
+        };
+
+        if sequence < peer.next_expected {
+            // Either a retransmission that fills a gap, or a copy we already have
+            if peer.missing.remove(&sequence).is_none() {
+                return Observation::Duplicate;
+            }
+        } else if sequence - peer.next_expected > self.limits.max_missing {
+            self.abandoned += (sequence - peer.next_expected) + peer.missing.len() as u64;
+            warn!(
+                "Lost {} datagrams from agent '{}', resynchronizing ({} abandoned so far)",
+                sequence - peer.next_expected,
+                sender_id,
+                self.abandoned
+            );
+            peer.missing.clear();
+            peer.next_expected = sequence + 1;
+        } else {
+            for gap in peer.next_expected..sequence {
+                peer.missing.insert(
+                    gap,
+                    MissingDatagram {
+                        last_requested: None,
+                        attempts: 0,
+                    },
+                );
+            }
+            peer.next_expected = sequence + 1;
+        }
+
+        let limits = &self.limits;
+        let mut missing = Vec::new();
+        let mut abandoned = 0;
+        peer.missing.retain(|gap, datagram| {
+            let due = datagram
+                .last_requested
+                .is_none_or(|last| now.duration_since(last) >= limits.interval);
+            if !due {
+                return true;
+            }
+            if datagram.attempts >= limits.max_attempts {
+                abandoned += 1;
+                return false;
+            }
+            datagram.attempts += 1;
+            datagram.last_requested = Some(now);
+            missing.push(*gap);
+            true
+        });
+
+        if abandoned > 0 {
+            self.abandoned += abandoned;
+            warn!(
+                "Gave up on {} datagram(s) from agent '{}' after {} requests ({} abandoned so far)",
+                abandoned, sender_id, limits.max_attempts, self.abandoned
+            );
+        }
+
+        Observation::New { missing }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn test_limits() -> NackLimits {
+        NackLimits {
+            interval: Duration::from_millis(100),
+            max_attempts: 2,
+            max_missing: 16,
+        }
+    }
+
+    /// An in-process link that loses selected datagrams on their first transmission
+    struct LossyLink {
+        sender_id: String,
+        epoch: u64,
+        next_sequence: u64,
+        buffer: RetransmitBuffer,
+        window: ReceiveWindow,
+        /// Sequence numbers dropped the first time they are sent
+        lose_once: HashSet<u64>,
+        delivered: Vec<Vec<u8>>,
+    }
+
+    impl LossyLink {
+        fn new(lose_once: &[u64]) -> Self {
+            Self {
+                sender_id: "agent-1".to_string(),
+                epoch: 1,
+                next_sequence: 1,
+                buffer: RetransmitBuffer::new(64),
+                window: ReceiveWindow::new(test_limits()),
+                lose_once: lose_once.iter().copied().collect(),
+                delivered: Vec::new(),
+            }
+        }
+
+        /// Send a payload, then answer any requests the receiver makes
+        fn send(&mut self, payload: &[u8], now: Instant) {
+            let sequence = self.next_sequence;
+            self.next_sequence += 1;
+            self.buffer.push(sequence, payload.to_vec());
+
+            if self.lose_once.remove(&sequence) {
+                return;
+            }
+            self.deliver(sequence, payload.to_vec(), now);
+        }
+
+        fn deliver(&mut self, sequence: u64, payload: Vec<u8>, now: Instant) {
+            match self
+                .window
+                .observe(&self.sender_id, self.epoch, sequence, now)
+            {
+                Observation::New { missing } => {
+                    self.delivered.push(payload);
+                    for gap in missing {
+                        if let Some(retransmitted) = self.buffer.get(gap).map(<[u8]>::to_vec) {
+                            self.deliver(gap, retransmitted, now);
+                        }
+                    }
+                }
+                Observation::Duplicate => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_lost_datagrams_are_recovered() {
+        let mut link = LossyLink::new(&[2, 3]);
+        let now = Instant::now();
+
+        for payload in [b"one", b"two", b"six", b"ten", b"end"] {
+            link.send(payload, now);
+        }
+
+        let mut delivered = link.delivered.clone();
+        delivered.sort();
+        let mut expected: Vec<Vec<u8>> = [b"one", b"two", b"six", b"ten", b"end"]
+            .iter()
+            .map(|payload| payload.to_vec())
+            .collect();
+        expected.sort();
+        assert_eq!(delivered, expected);
+        assert!(link.window.peers["agent-1"].missing.is_empty());
+        assert_eq!(link.window.abandoned, 0);
+    }
+
+    #[test]
+    fn test_duplicates_are_dropped() {
+        let mut window = ReceiveWindow::new(test_limits());
+        let now = Instant::now();
+
+        assert_eq!(
+            window.observe("agent-1", 1, 1, now),
+            Observation::New { missing: vec![] }
+        );
+        assert_eq!(window.observe("agent-1", 1, 1, now), Observation::Duplicate);
+    }
+
+    #[test]
+    fn test_missing_datagrams_are_requested_again_then_abandoned() {
+        let mut window = ReceiveWindow::new(test_limits());
+        let start = Instant::now();
+
+        window.observe("agent-1", 1, 1, start);
+        assert_eq!(
+            window.observe("agent-1", 1, 3, start),
+            Observation::New { missing: vec![2] }
+        );
+
+        // Not requested again before the interval has passed
+        assert_eq!(
+            window.observe("agent-1", 1, 4, start + Duration::from_millis(50)),
+            Observation::New { missing: vec![] }
+        );
+        assert_eq!(
+            window.observe("agent-1", 1, 5, start + Duration::from_millis(100)),
+            Observation::New { missing: vec![2] }
+        );
+
+        // Out of attempts
+        assert_eq!(
+            window.observe("agent-1", 1, 6, start + Duration::from_millis(200)),
+            Observation::New { missing: vec![] }
+        );
+        assert!(window.peers["agent-1"].missing.is_empty());
+        assert_eq!(window.abandoned, 1);
+    }
+
+    #[test]
+    fn test_large_gaps_resynchronize() {
+        let mut window = ReceiveWindow::new(test_limits());
+        let now = Instant::now();
+
+        window.observe("agent-1", 1, 1, now);
+        assert_eq!(
+            window.observe("agent-1", 1, 100, now),
+            Observation::New { missing: vec![] }
+        );
+        assert_eq!(window.abandoned, 98);
+        assert_eq!(
+            window.observe("agent-1", 1, 102, now),
+            Observation::New { missing: vec![101] }
+        );
+    }
+
+    #[test]
+    fn test_new_epoch_restarts_the_stream() {
+        let mut window = ReceiveWindow::new(test_limits());
+        let now = Instant::now();
+
+        window.observe("agent-1", 1, 40, now);
+        assert_eq!(
+            window.observe("agent-1", 2, 1, now),
+            Observation::New { missing: vec![] }
+        );
+        assert_eq!(
+            window.observe("agent-1", 1, 41, now),
+            Observation::Duplicate
+        );
+        assert_eq!(
+            window.observe("agent-1", 2, 2, now),
+            Observation::New { missing: vec![] }
+        );
+    }
+
+    #[test]
+    fn test_retransmit_buffer_is_bounded() {
+        let mut buffer = RetransmitBuffer::new(2);
+        buffer.push(1, b"one".to_vec());
+        buffer.push(2, b"two".to_vec());
+        buffer.push(3, b"three".to_vec());
+
+        assert_eq!(buffer.datagrams.len(), 2);
+        assert_eq!(buffer.get(1), None);
+        assert_eq!(buffer.get(3), Some(&b"three"[..]));
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Replay protection error types
+#[derive(Error, Debug, PartialEq)]
+pub enum ReplayError {
+    #[error("Message has no id and cannot be checked for replays")]
+    MissingMessageId,
+
+    #[error("Message {message_id} timestamp is {skew_ms}ms away from local time (window {window_ms}ms)")]
+    OutsideWindow {
+        message_id: String,
+        skew_ms: i64,
+        window_ms: i64,
+    },
+
+    #[error("Message {0} was already received")]
+    Replayed(String),
+}
+
+/// Counters of messages rejected by the replay guard
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReplayCounters {
+    /// Messages whose timestamp fell outside the clock-skew window
+    pub outside_window: u64,
+    /// Messages whose id was already seen within the window
+    pub replayed: u64,
+}
+
+/// Rejects messages that are too old, too far in the future, or already seen
+///
+/// Ids only need to be remembered for as long as their timestamp is inside the
+/// window, since anything older is rejected on its timestamp alone.
+pub struct ReplayGuard {
+    window_ms: i64,
+    /// Seen message ids and their timestamps
+    seen: HashMap<String, i64>,
+    last_pruned_ms: i64,
+    counters: ReplayCounters,
+}
+
+impl ReplayGuard {
+    /// Create a new ReplayGuard accepting timestamps within `window` of local time
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window_ms: window.as_millis() as i64,
+            seen: HashMap::new(),
+            last_pruned_ms: 0,
+            counters: ReplayCounters::default(),
+        }
+    }
+
+    /// Counters of rejected messages so far
+    pub fn counters(&self) -> ReplayCounters {
+        self.counters
+    }
+
+    /// Check a message and remember its id, rejecting stale or replayed messages
+    pub fn check(
+        &mut self,
+        message_id: &str,
+        timestamp_ms: i64,
+        now_ms: i64,
+    ) -> Result<(), ReplayError> {
+        if message_id.is_empty() {
+            return Err(ReplayError::MissingMessageId);
+        }
+
+        let skew_ms = timestamp_ms.saturating_sub(now_ms);
+        if skew_ms.abs() > self.window_ms {
+            self.counters.outside_window += 1;
+            return Err(ReplayError::OutsideWindow {
+                message_id: message_id.to_string(),
+                skew_ms,
+                window_ms: self.window_ms,
+            });
+        }
+
+        self.prune(now_ms);
+
+        if self.seen.contains_key(message_id) {
+            self.counters.replayed += 1;
+            return Err(ReplayError::Replayed(message_id.to_string()));
+        }
+
+        self.seen.insert(message_id.to_string(), timestamp_ms);
+        Ok(())
+    }
+
+    /// Forget ids whose timestamps have left the window, at most once per window
+    fn prune(&mut self, now_ms: i64) {
+        if now_ms - self.last_pruned_ms < self.window_ms {
+            return;
+        }
+
+        let oldest_allowed = now_ms - self.window_ms;
+        self.seen.retain(|_, timestamp| *timestamp >= oldest_allowed);
+        self.last_pruned_ms = now_ms;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    #[test]
+    fn test_fresh_message_is_accepted_once() {
+        let mut guard = ReplayGuard::new(Duration::from_secs(30));
+
+        assert!(guard.check("msg-1", NOW - 1_000, NOW).is_ok());
+        assert_eq!(
+            guard.check("msg-1", NOW - 1_000, NOW + 5_000),
+            Err(ReplayError::Replayed("msg-1".to_string()))
+        );
+        assert_eq!(
+            guard.counters(),
+            ReplayCounters {
+                outside_window: 0,
+                replayed: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_messages_outside_window_are_rejected() {
+        let mut guard = ReplayGuard::new(Duration::from_secs(30));
+
+        // Captured long ago and re-sent now
+        assert!(matches!(
+            guard.check("old", NOW - 60_000, NOW),
+            Err(ReplayError::OutsideWindow { .. })
+        ));
+        // Timestamped too far in the future
+        assert!(matches!(
+            guard.check("future", NOW + 31_000, NOW),
+            Err(ReplayError::OutsideWindow { .. })
+        ));
+        assert_eq!(guard.counters().outside_window, 2);
+    }
+
+    #[test]
+    fn test_seen_ids_expire_with_the_window() {
+        let mut guard = ReplayGuard::new(Duration::from_secs(30));
+        assert!(guard.check("msg-1", NOW, NOW).is_ok());
+
+        // Once the window has passed, the id is forgotten but the timestamp check still rejects it
+        let later = NOW + 61_000;
+        assert!(guard.check("msg-2", later, later).is_ok());
+        assert!(!guard.seen.contains_key("msg-1"));
+        assert!(matches!(
+            guard.check("msg-1", NOW, later),
+            Err(ReplayError::OutsideWindow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_missing_message_id_is_rejected() {
+        let mut guard = ReplayGuard::new(Duration::from_secs(30));
+        assert_eq!(
+            guard.check("", NOW, NOW),
+            Err(ReplayError::MissingMessageId)
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use llm::error::LLMError;
+use regex::Regex;
+use std::future::Future;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio_retry::strategy::jitter;
+use tracing::{error, warn};
+
+/// Matches the wait a provider asks for, e.g. "Retry-After: 20" or "try again in 1.5s"
+static RETRY_AFTER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(?:retry[-_ ]after|try again in)["':=\s]*([0-9]+(?:\.[0-9]+)?)\s*(ms)?"#)
+        .expect("retry-after pattern is valid")
+});
+
+/// Matches HTTP status codes that rule out a retry, as whole numbers only
+static FATAL_STATUS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:401|403)\b").expect("fatal status pattern is valid"));
+
+/// Matches the HTTP status code for throttling, as a whole number only
+static RATE_LIMITED_STATUS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b429\b").expect("rate limit status pattern is valid"));
+
+/// How a failed LLM request should be handled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorClass {
+    /// Retrying cannot help, e.g. a rejected API key or a malformed request
+    Fatal,
+    /// The provider is throttling us, optionally saying how long to wait
+    RateLimited(Option<Duration>),
+    /// Anything else, e.g. a timeout or a dropped connection
+    Transient,
+}
+
+impl std::fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorClass::Fatal => write!(f, "fatal error"),
+            ErrorClass::RateLimited(_) => write!(f, "rate limited"),
+            ErrorClass::Transient => write!(f, "transient error"),
+        }
+    }
+}
+
+impl ErrorClass {
+    /// Classify an error returned by the LLM module
+    pub fn of(error: &anyhow::Error) -> Self {
+        match error.downcast_ref::<LLMError>() {
+            Some(LLMError::AuthError(_))
+            | Some(LLMError::InvalidRequest(_))
+            | Some(LLMError::ToolConfigError(_)) => ErrorClass::Fatal,
+            _ => Self::of_message(&error.to_string()),
+        }
+    }
+
+    /// Classify an error from its message, for providers that only report text
+    ///
+    /// "invalid" alone is deliberately not treated as fatal: it also appears in
+    /// errors like "invalid model" from OpenAI-compatible providers. Status
+    /// codes only count as whole numbers, not inside e.g. "4290ms" or a request id.
+    pub fn of_message(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        if lower.contains("unauthorized")
+            || FATAL_STATUS.is_match(&lower)
+            || lower.contains("invalid api key")
+            || lower.contains("invalid_api_key")
+            || lower.contains("authentication")
+            || lower.contains("permission")
+        {
+            ErrorClass::Fatal
+        } else if RATE_LIMITED_STATUS.is_match(&lower)
+            || lower.contains("rate limit")
+            || lower.contains("rate_limit")
+            || lower.contains("too many requests")
+        {
+            ErrorClass::RateLimited(retry_after(message))
+        } else {
+            ErrorClass::Transient
+        }
+    }
+}
+
+/// The wait a rate-limited response asks for, if it names one
+fn retry_after(message: &str) -> Option<Duration> {
+    let captures = RETRY_AFTER.captures(message)?;
+    let amount: f64 = captures[1].parse().ok()?;
+    let seconds = if captures.get(2).is_some() {
+        amount / 1000.0
+    } else {
+        amount
+    };
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// How often, and how patiently, failed LLM requests are retried
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub retries: u32,
+    /// Wait before the first retry, doubled for every retry after it
+    pub base_delay: Duration,
+    /// Longest wait between attempts, even when the provider asks for longer
+    pub max_delay: Duration,
+    /// Randomize waits, so agents that failed together do not retry together
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff before retry number `retry`, counting from zero
+    fn backoff(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        if self.jitter { jitter(delay) } else { delay }
+    }
+
+    /// Wait before retry number `retry` after an error of class `class`
+    ///
+    /// A provider's Retry-After is honored up to the maximum delay, so a
+    /// provider asking for hours cannot stall the agent that long.
+    fn delay(&self, retry: u32, class: ErrorClass) -> Duration {
+        let backoff = self.backoff(retry);
+        match class {
+            ErrorClass::RateLimited(Some(retry_after)) => {
+                retry_after.min(self.max_delay).max(backoff)
+            }
+            _ => backoff,
+        }
+    }
+
+    /// Run `operation` until it succeeds, fails fatally, or runs out of retries
+    pub async fn run<T, F, Fut>(&self, mut operation: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let mut retry = 0;
+        loop {
+            let e = match operation().await {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+
+            let class = ErrorClass::of(&e);
+            if class == ErrorClass::Fatal {
+                error!("LLM request failed and cannot succeed on retry: {}", e);
+                return Err(e);
+            }
+            if retry >= self.retries {
+                error!("LLM request failed after {} retries: {}", retry, e);
+                return Err(e);
+            }
+
+            let delay = self.delay(retry, class);
+            warn!(
+                "LLM request failed ({:?}), retry {} of {} in {:?}: {}",
+                class,
+                retry + 1,
+                self.retries,
+                delay,
+                e
+            );
+            tokio::time::sleep(delay).await;
+            retry += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn steady(retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+            jitter: false,
+        }
+    }
+
+    #[test]
+    fn test_errors_are_classified() {
+        assert_eq!(
+            ErrorClass::of(&anyhow!(LLMError::AuthError("expired".to_string()))),
+            ErrorClass::Fatal
+        );
+        assert_eq!(
+            ErrorClass::of_message("HTTP Error: 401 Unauthorized"),
+            ErrorClass::Fatal
+        );
+        assert_eq!(
+            ErrorClass::of_message("Provider Error: invalid model 'gpt-9'"),
+            ErrorClass::Transient
+        );
+        assert_eq!(
+            ErrorClass::of_message("HTTP Error: error sending request: operation timed out"),
+            ErrorClass::Transient
+        );
+        assert_eq!(
+            ErrorClass::of_message("429 Too Many Requests"),
+            ErrorClass::RateLimited(None)
+        );
+        assert_eq!(
+            ErrorClass::of_message("Rate limit reached. Please try again in 1.5s."),
+            ErrorClass::RateLimited(Some(Duration::from_millis(1500)))
+        );
+        assert_eq!(
+            ErrorClass::of_message("429: {\"error\": \"slow down\", \"retry_after\": 20}"),
+            ErrorClass::RateLimited(Some(Duration::from_secs(20)))
+        );
+        assert_eq!(
+            ErrorClass::of_message("rate_limit_exceeded, try again in 250ms"),
+            ErrorClass::RateLimited(Some(Duration::from_millis(250)))
+        );
+        assert_eq!(
+            ErrorClass::of_message("HTTP Error: 403 Forbidden"),
+            ErrorClass::Fatal
+        );
+
+        // Status codes are whole numbers, not digits inside durations or ids
+        assert_eq!(
+            ErrorClass::of_message("HTTP Error: operation timed out after 4290ms"),
+            ErrorClass::Transient
+        );
+        assert_eq!(
+            ErrorClass::of_message("Provider Error: server overloaded (request id req_4011abc)"),
+            ErrorClass::Transient
+        );
+        assert_eq!(
+            ErrorClass::of_message("Provider Error: 502 Bad Gateway, trace 1403429"),
+            ErrorClass::Transient
+        );
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_maximum() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(10), Duration::from_secs(30));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(30));
+
+        // The provider's wait wins over a shorter backoff, up to the maximum delay
+        let throttled = ErrorClass::RateLimited(Some(Duration::from_secs(20)));
+        assert_eq!(policy.delay(0, throttled), Duration::from_secs(20));
+        let throttled = ErrorClass::RateLimited(Some(Duration::from_secs(45)));
+        assert_eq!(policy.delay(0, throttled), Duration::from_secs(30));
+        let throttled = ErrorClass::RateLimited(Some(Duration::MAX));
+        assert_eq!(policy.delay(3, throttled), Duration::from_secs(30));
+
+        let policy = RetryPolicy::default();
+        assert!(policy.backoff(2) <= Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried_and_fatal_ones_are_not() {
+        let mut attempts = 0;
+        let result = steady(3)
+            .run(|| {
+                attempts += 1;
+                let attempt = attempts;
+                async move {
+                    if attempt < 3 {
+                        Err(anyhow!("connection reset"))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 3);
+
+        let mut attempts = 0;
+        let result: anyhow::Result<()> = steady(3)
+            .run(|| {
+                attempts += 1;
+                async { Err(anyhow!("timed out")) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 4);
+
+        let mut attempts = 0;
+        let result: anyhow::Result<()> = steady(3)
+            .run(|| {
+                attempts += 1;
+                async { Err(anyhow!(LLMError::AuthError("invalid key".to_string()))) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
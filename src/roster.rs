@@ -0,0 +1,262 @@
+use crate::message::Heartbeat;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Number of heartbeat intervals an agent may miss before it is considered gone
+pub const MISSED_HEARTBEATS: u32 = 3;
+
+/// A participant currently present in the conclave
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub agent_id: String,
+    pub model: String,
+    pub backend: String,
+    pub role: String,
+    /// How long the agent may stay silent before it times out
+    timeout: Duration,
+    last_seen: Instant,
+}
+
+impl std::fmt::Display for Member {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}, {} via {})",
+            self.agent_id, self.role, self.model, self.backend
+        )
+    }
+}
+
+/// A change in swarm membership
+#[derive(Debug, Clone, PartialEq)]
+pub enum RosterEvent {
+    /// An agent sent its first heartbeat
+    Joined(Member),
+    /// An agent announced that it is shutting down
+    Left(String),
+    /// An agent stopped sending heartbeats
+    TimedOut(String),
+}
+
+impl std::fmt::Display for RosterEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RosterEvent::Joined(member) => write!(f, "{} joined the conclave", member),
+            RosterEvent::Left(agent_id) => write!(f, "{} left the conclave", agent_id),
+            RosterEvent::TimedOut(agent_id) => {
+                write!(f, "{} went silent and has departed", agent_id)
+            }
+        }
+    }
+}
+
+/// Live view of the agents in the swarm, built from their heartbeats
+#[derive(Default)]
+pub struct Roster {
+    members: HashMap<String, Member>,
+    /// Membership changes not yet shown to the LLM
+    announcements: Vec<RosterEvent>,
+}
+
+impl Roster {
+    /// Create an empty roster
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a heartbeat from `agent_id`, returning the membership change it caused, if any
+    pub fn observe(
+        &mut self,
+        agent_id: &str,
+        heartbeat: &Heartbeat,
+        now: Instant,
+    ) -> Option<RosterEvent> {
+        if heartbeat.leaving {
+            return self
+                .members
+                .remove(agent_id)
+                .map(|_| self.announce(RosterEvent::Left(agent_id.to_string())));
+        }
+
+        let member = Member {
+            agent_id: agent_id.to_string(),
+            model: heartbeat.model.clone(),
+            backend: heartbeat.backend.clone(),
+            role: heartbeat.role.clone(),
+            timeout: Duration::from_millis(heartbeat.interval_ms as u64) * MISSED_HEARTBEATS,
+            last_seen: now,
+        };
+
+        match self.members.insert(agent_id.to_string(), member.clone()) {
+            Some(_) => None,
+            None => Some(self.announce(RosterEvent::Joined(member))),
+        }
+    }
+
+    /// Treat any message from a known member as a sign of life
+    pub fn touch(&mut self, agent_id: &str, now: Instant) {
+        if let Some(member) = self.members.get_mut(agent_id) {
+            member.last_seen = now;
+        }
+    }
+
+    /// Remove members whose heartbeats stopped, returning a timeout event for each
+    pub fn expire(&mut self, now: Instant) -> Vec<RosterEvent> {
+        let mut silent: Vec<String> = self
+            .members
+            .values()
+            .filter(|member| now.duration_since(member.last_seen) > member.timeout)
+            .map(|member| member.agent_id.clone())
+            .collect();
+        silent.sort();
+
+        silent
+            .into_iter()
+            .map(|agent_id| {
+                self.members.remove(&agent_id);
+                self.announce(RosterEvent::TimedOut(agent_id))
+            })
+            .collect()
+    }
+
+    /// Current members, ordered by agent id
+    pub fn members(&self) -> Vec<&Member> {
+        let mut members: Vec<&Member> = self.members.values().collect();
+        members.sort_by(|a, b| a.agent_id.cmp(&b.agent_id));
+        members
+    }
+
+    /// Whether `name` is the id or role of a current member
+    pub fn knows(&self, name: &str) -> bool {
+        self.members
+            .values()
+            .any(|member| member.agent_id == name || member.role == name)
+    }
+
+    /// Role announced by the member `agent_id`, if it is present
+    pub fn role_of(&self, agent_id: &str) -> Option<&str> {
+        self.members
+            .get(agent_id)
+            .map(|member| member.role.as_str())
+    }
+
+    /// Describe the participants and recent membership changes for the LLM, clearing the changes
+    pub fn take_context(&mut self) -> String {
+        let participants: Vec<String> = self.members().into_iter().map(Member::to_string).collect();
+
+        let mut context = if participants.is_empty() {
+            "No other participants are currently present in the conclave.".to_string()
+        } else {
+            format!("Participants in the conclave: {}.", participants.join(", "))
+        };
+
+        for event in self.announcements.drain(..) {
+            context.push_str(&format!("\n{}.", event));
+        }
+        context
+    }
+
+    fn announce(&mut self, event: RosterEvent) -> RosterEvent {
+        self.announcements.push(event.clone());
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heartbeat(role: &str) -> Heartbeat {
+        Heartbeat {
+            model: "gpt-4".to_string(),
+            backend: "openai".to_string(),
+            role: role.to_string(),
+            interval_ms: 1000,
+            leaving: false,
+        }
+    }
+
+    #[test]
+    fn test_join_and_leave() {
+        let mut roster = Roster::new();
+        let now = Instant::now();
+
+        let joined = roster.observe("agent-1", &heartbeat("debater"), now);
+        assert!(
+            matches!(joined, Some(RosterEvent::Joined(ref member)) if member.role == "debater")
+        );
+
+        assert!(roster.knows("agent-1"));
+        assert!(roster.knows("debater"));
+        assert!(!roster.knows("judge"));
+        assert_eq!(roster.role_of("agent-1"), Some("debater"));
+        assert_eq!(roster.role_of("agent-2"), None);
+
+        // Further heartbeats only refresh the member
+        assert_eq!(roster.observe("agent-1", &heartbeat("debater"), now), None);
+        assert_eq!(roster.members().len(), 1);
+
+        let leaving = Heartbeat {
+            leaving: true,
+            ..heartbeat("debater")
+        };
+        assert_eq!(
+            roster.observe("agent-1", &leaving, now),
+            Some(RosterEvent::Left("agent-1".to_string()))
+        );
+        assert!(roster.members().is_empty());
+
+        // Leaving twice is not a new event
+        assert_eq!(roster.observe("agent-1", &leaving, now), None);
+    }
+
+    #[test]
+    fn test_silent_members_time_out() {
+        let mut roster = Roster::new();
+        let start = Instant::now();
+        roster.observe("agent-1", &heartbeat("debater"), start);
+        roster.observe("agent-2", &heartbeat("judge"), start);
+
+        // agent-2 keeps talking, agent-1 goes silent
+        let later = start + Duration::from_millis(2500);
+        roster.touch("agent-2", later);
+        assert!(roster.expire(later).is_empty());
+
+        let events = roster.expire(start + Duration::from_millis(3500));
+        assert_eq!(events, vec![RosterEvent::TimedOut("agent-1".to_string())]);
+        assert_eq!(roster.members()[0].agent_id, "agent-2");
+    }
+
+    #[test]
+    fn test_context_lists_participants_and_changes_once() {
+        let mut roster = Roster::new();
+        let now = Instant::now();
+        assert_eq!(
+            roster.take_context(),
+            "No other participants are currently present in the conclave."
+        );
+
+        roster.observe("agent-2", &heartbeat("judge"), now);
+        roster.observe(
+            "agent-3",
+            &Heartbeat {
+                leaving: true,
+                ..heartbeat("debater")
+            },
+            now,
+        );
+
+        let context = roster.take_context();
+        assert_eq!(
+            context,
+            "Participants in the conclave: agent-2 (judge, gpt-4 via openai).\n\
+             agent-2 (judge, gpt-4 via openai) joined the conclave."
+        );
+
+        // Membership changes are only reported once
+        assert_eq!(
+            roster.take_context(),
+            "Participants in the conclave: agent-2 (judge, gpt-4 via openai)."
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Lowest port a session-derived address uses, clear of well-known and registered services
+const SESSION_PORT_BASE: u16 = 10000;
+
+/// Number of ports session-derived addresses are spread over
+const SESSION_PORT_RANGE: u16 = 50000;
+
+/// Derive a multicast group and port for a session, in the same family and scope as `base`
+///
+/// Every agent computes the same address from the same session name, so teams
+/// on one network can keep their conversations apart without coordinating
+/// addresses. IPv4 groups stay within 239.255.0.0/16 (site-local); IPv6 groups
+/// keep the flags and scope of `base`.
+pub fn derive_multicast_address(session: &str, base: SocketAddr) -> SocketAddr {
+    let digest = Sha256::digest(session.as_bytes());
+
+    let ip = match base.ip() {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::new(239, 255, digest[0], digest[1])),
+        IpAddr::V6(base_v6) => {
+            let mut segments = [0u16; 8];
+            segments[0] = base_v6.segments()[0];
+            for (index, segment) in segments.iter_mut().enumerate().skip(4) {
+                *segment = u16::from_be_bytes([digest[2 * index], digest[2 * index + 1]]);
+            }
+            IpAddr::V6(Ipv6Addr::from(segments))
+        }
+    };
+
+    let port =
+        SESSION_PORT_BASE + u16::from_be_bytes([digest[16], digest[17]]) % SESSION_PORT_RANGE;
+    SocketAddr::new(ip, port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derived_address_is_stable_and_per_session() {
+        let base: SocketAddr = "239.255.255.250:8080".parse().unwrap();
+
+        let red = derive_multicast_address("team-red", base);
+        assert_eq!(red, derive_multicast_address("team-red", base));
+        assert_ne!(red, derive_multicast_address("team-blue", base));
+
+        assert!(red.ip().is_multicast());
+        assert!(matches!(red.ip(), IpAddr::V4(v4) if v4.octets()[..2] == [239, 255]));
+        assert!((SESSION_PORT_BASE..SESSION_PORT_BASE + SESSION_PORT_RANGE).contains(&red.port()));
+    }
+
+    #[test]
+    fn test_derived_ipv6_address_keeps_scope() {
+        let base: SocketAddr = "[ff05::1234]:8080".parse().unwrap();
+
+        let derived = derive_multicast_address("team-red", base);
+        match derived.ip() {
+            IpAddr::V6(v6) => assert_eq!(v6.segments()[0], 0xff05),
+            IpAddr::V4(_) => panic!("Expected an IPv6 group"),
+        }
+    }
+}
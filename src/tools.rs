@@ -0,0 +1,656 @@
+use crate::message::{AgentMessage, StructuredMessage};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Schema tag of tool results broadcast to peers
+pub const TOOL_RESULT_SCHEMA: &str = "conclave.tool-result.v1";
+
+/// Largest part of a workspace file returned to the LLM
+const MAX_FILE_BYTES: usize = 64 * 1024;
+
+/// Corpus files larger than this are not searched
+const MAX_CORPUS_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Most matching lines a corpus search returns
+const MAX_SEARCH_RESULTS: usize = 20;
+
+/// Errors raised while running a tool the LLM called
+#[derive(Error, Debug)]
+pub enum ToolError {
+    #[error("Unknown tool '{0}'")]
+    UnknownTool(String),
+
+    #[error("Invalid arguments: {0}")]
+    InvalidArguments(String),
+
+    #[error("Path '{0}' is outside the workspace")]
+    OutsideWorkspace(String),
+
+    #[error("Cannot evaluate expression: {0}")]
+    Math(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Tools an agent can be given on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ToolKind {
+    /// Read a file from the workspace directory
+    #[value(name = "read-file")]
+    ReadFile,
+    /// Evaluate arithmetic expressions
+    #[value(name = "calculator")]
+    Calculator,
+    /// Search the text files of a local corpus
+    #[value(name = "search")]
+    Search,
+}
+
+/// A function the LLM may call, run locally by the agent
+pub trait LocalTool: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    /// JSON schema of the arguments object
+    fn parameters(&self) -> Value;
+
+    fn call(&self, arguments: &Value) -> Result<String, ToolError>;
+}
+
+/// What came of one tool call
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolOutcome {
+    pub tool: String,
+    pub arguments: Value,
+    /// Result, or the error message if the call failed
+    pub output: String,
+    pub failed: bool,
+}
+
+impl ToolOutcome {
+    /// The outcome as a typed message from `sender_id`
+    pub fn to_message(&self, sender_id: String) -> Result<AgentMessage, serde_json::Error> {
+        Ok(AgentMessage::new_structured(
+            sender_id,
+            StructuredMessage {
+                schema: TOOL_RESULT_SCHEMA.to_string(),
+                json: serde_json::to_string(self)?,
+            },
+        ))
+    }
+}
+
+/// The tools offered to the LLM, by name
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: BTreeMap<String, Box<dyn LocalTool>>,
+}
+
+impl ToolRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a tool, replacing any tool with the same name
+    pub fn register(&mut self, tool: Box<dyn LocalTool>) {
+        self.tools.insert(tool.name().to_string(), tool);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Definitions of all tools, as passed to the LLM provider
+    pub fn definitions(&self) -> Vec<llm::chat::Tool> {
+        self.tools
+            .values()
+            .map(|tool| llm::chat::Tool {
+                tool_type: "function".to_string(),
+                function: llm::chat::FunctionTool {
+                    name: tool.name().to_string(),
+                    description: tool.description().to_string(),
+                    parameters: tool.parameters(),
+                },
+            })
+            .collect()
+    }
+
+    /// Run the tool called `name` with JSON-encoded `arguments`
+    ///
+    /// Failures are reported in the outcome, so the LLM can correct its call.
+    pub fn execute(&self, name: &str, arguments: &str) -> ToolOutcome {
+        let parsed = serde_json::from_str::<Value>(arguments);
+        let result = match (self.tools.get(name), &parsed) {
+            (None, _) => Err(ToolError::UnknownTool(name.to_string())),
+            (Some(_), Err(e)) => Err(ToolError::InvalidArguments(e.to_string())),
+            (Some(tool), Ok(arguments)) => tool.call(arguments),
+        };
+
+        let (output, failed) = match result {
+            Ok(output) => (output, false),
+            Err(e) => (e.to_string(), true),
+        };
+        ToolOutcome {
+            tool: name.to_string(),
+            arguments: parsed.unwrap_or_else(|_| Value::String(arguments.to_string())),
+            output,
+            failed,
+        }
+    }
+}
+
+/// Get a required string argument
+fn string_argument<'a>(arguments: &'a Value, name: &str) -> Result<&'a str, ToolError> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| ToolError::InvalidArguments(format!("'{}' must be a string", name)))
+}
+
+/// Reads files below a workspace directory, and nothing outside it
+pub struct ReadFileTool {
+    /// Canonical path of the workspace
+    root: PathBuf,
+}
+
+impl ReadFileTool {
+    pub fn new(workspace: &Path) -> Result<Self, ToolError> {
+        Ok(Self {
+            root: workspace.canonicalize()?,
+        })
+    }
+}
+
+impl LocalTool for ReadFileTool {
+    fn name(&self) -> &str {
+        "read_file"
+    }
+
+    fn description(&self) -> &str {
+        "Read a text file from the workspace directory"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the file, relative to the workspace"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    fn call(&self, arguments: &Value) -> Result<String, ToolError> {
+        let path = string_argument(arguments, "path")?;
+
+        // Resolving `..` and symlinks first means nothing can point out of the workspace
+        let resolved = self.root.join(path).canonicalize()?;
+        if !resolved.starts_with(&self.root) {
+            return Err(ToolError::OutsideWorkspace(path.to_string()));
+        }
+
+        // Devices and FIFOs could block forever, so only regular files are read
+        if !std::fs::metadata(&resolved)?.is_file() {
+            return Err(ToolError::InvalidArguments(format!(
+                "'{}' is not a regular file",
+                path
+            )));
+        }
+
+        // Read one byte past the limit, to tell whether the file goes on
+        let mut bytes = Vec::new();
+        std::fs::File::open(&resolved)?
+            .take(MAX_FILE_BYTES as u64 + 1)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() <= MAX_FILE_BYTES {
+            return String::from_utf8(bytes).map_err(|e| {
+                ToolError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            });
+        }
+
+        // Cut before a character the limit splits, but reject text that is not UTF-8
+        bytes.truncate(MAX_FILE_BYTES);
+        let end = match std::str::from_utf8(&bytes) {
+            Ok(_) => MAX_FILE_BYTES,
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => {
+                return Err(ToolError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    e,
+                )));
+            }
+        };
+        bytes.truncate(end);
+        Ok(format!(
+            "{}\n[truncated after {} bytes]",
+            String::from_utf8(bytes).expect("prefix is valid UTF-8"),
+            end
+        ))
+    }
+}
+
+/// Evaluates arithmetic expressions
+pub struct CalculatorTool;
+
+impl LocalTool for CalculatorTool {
+    fn name(&self) -> &str {
+        "calculator"
+    }
+
+    fn description(&self) -> &str {
+        "Evaluate an arithmetic expression with + - * / % ^ and parentheses"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "Expression to evaluate, e.g. '(2 + 3) * 4 ^ 2'"
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    fn call(&self, arguments: &Value) -> Result<String, ToolError> {
+        let expression = string_argument(arguments, "expression")?;
+        Ok(evaluate(expression)?.to_string())
+    }
+}
+
+/// Deepest nesting of parentheses, signs and powers the calculator accepts
+const MAX_NESTING: usize = 64;
+
+/// Evaluate an arithmetic expression
+fn evaluate(expression: &str) -> Result<f64, ToolError> {
+    let mut parser = ExpressionParser {
+        tokens: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+        position: 0,
+        depth: 0,
+    };
+    let value = parser.expression()?;
+    if parser.position < parser.tokens.len() {
+        return Err(ToolError::Math(format!(
+            "unexpected '{}'",
+            parser.tokens[parser.position]
+        )));
+    }
+    if !value.is_finite() {
+        return Err(ToolError::Math("result is not a finite number".to_string()));
+    }
+    Ok(value)
+}
+
+/// Recursive-descent parser over the characters of an expression
+struct ExpressionParser {
+    tokens: Vec<char>,
+    position: usize,
+    /// Nesting of the rule being parsed, capped so deep input cannot overflow the stack
+    depth: usize,
+}
+
+impl ExpressionParser {
+    fn peek(&self) -> Option<char> {
+        self.tokens.get(self.position).copied()
+    }
+
+    /// Parse a nested rule with `rule`, one level deeper
+    fn nested(&mut self, rule: fn(&mut Self) -> Result<f64, ToolError>) -> Result<f64, ToolError> {
+        if self.depth >= MAX_NESTING {
+            return Err(ToolError::Math(format!(
+                "expression nests deeper than {} levels",
+                MAX_NESTING
+            )));
+        }
+        self.depth += 1;
+        let value = rule(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<f64, ToolError> {
+        let mut value = self.term()?;
+        while let Some(operator @ ('+' | '-')) = self.peek() {
+            self.position += 1;
+            let rhs = self.term()?;
+            value = if operator == '+' {
+                value + rhs
+            } else {
+                value - rhs
+            };
+        }
+        Ok(value)
+    }
+
+    /// term := power (('*' | '/' | '%') power)*
+    fn term(&mut self) -> Result<f64, ToolError> {
+        let mut value = self.power()?;
+        while let Some(operator @ ('*' | '/' | '%')) = self.peek() {
+            self.position += 1;
+            let rhs = self.power()?;
+            if operator != '*' && rhs == 0.0 {
+                return Err(ToolError::Math("division by zero".to_string()));
+            }
+            value = match operator {
+                '*' => value * rhs,
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    /// power := unary ('^' power)?, so powers group to the right
+    fn power(&mut self) -> Result<f64, ToolError> {
+        let base = self.unary()?;
+        if self.peek() == Some('^') {
+            self.position += 1;
+            return Ok(base.powf(self.nested(Self::power)?));
+        }
+        Ok(base)
+    }
+
+    /// unary := '-' unary | primary
+    fn unary(&mut self) -> Result<f64, ToolError> {
+        if self.peek() == Some('-') {
+            self.position += 1;
+            return Ok(-self.nested(Self::unary)?);
+        }
+        self.primary()
+    }
+
+    /// primary := number | '(' expression ')'
+    fn primary(&mut self) -> Result<f64, ToolError> {
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let value = self.nested(Self::expression)?;
+                if self.peek() != Some(')') {
+                    return Err(ToolError::Math("missing ')'".to_string()));
+                }
+                self.position += 1;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.position;
+                while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '.') {
+                    self.position += 1;
+                }
+                let number: String = self.tokens[start..self.position].iter().collect();
+                number
+                    .parse()
+                    .map_err(|_| ToolError::Math(format!("invalid number '{}'", number)))
+            }
+            Some(c) => Err(ToolError::Math(format!("unexpected '{}'", c))),
+            None => Err(ToolError::Math("unexpected end of expression".to_string())),
+        }
+    }
+}
+
+/// Searches the text files of a corpus directory line by line
+pub struct SearchTool {
+    root: PathBuf,
+}
+
+impl SearchTool {
+    pub fn new(corpus: &Path) -> Result<Self, ToolError> {
+        Ok(Self {
+            root: corpus.canonicalize()?,
+        })
+    }
+
+    /// Collect the lines of text files below `dir` that contain every term
+    fn search(
+        &self,
+        dir: &Path,
+        terms: &[String],
+        results: &mut Vec<String>,
+    ) -> Result<(), ToolError> {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        entries.sort();
+
+        for path in entries {
+            if results.len() >= MAX_SEARCH_RESULTS {
+                break;
+            }
+            // Symlinks could lead out of the corpus, so only plain files and directories count
+            let metadata = std::fs::symlink_metadata(&path)?;
+            if metadata.is_dir() {
+                self.search(&path, terms, results)?;
+                continue;
+            }
+            if !metadata.is_file() || metadata.len() > MAX_CORPUS_FILE_BYTES {
+                continue;
+            }
+            // Binary files are not part of a text corpus
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+            for (number, line) in content.lines().enumerate() {
+                let lowercase = line.to_lowercase();
+                if terms.iter().all(|term| lowercase.contains(term.as_str())) {
+                    results.push(format!(
+                        "{}:{}: {}",
+                        relative.display(),
+                        number + 1,
+                        line.trim()
+                    ));
+                    if results.len() >= MAX_SEARCH_RESULTS {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LocalTool for SearchTool {
+    fn name(&self) -> &str {
+        "search_corpus"
+    }
+
+    fn description(&self) -> &str {
+        "Search the local document corpus for lines containing all the given words"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Words to look for, matched case-insensitively"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    fn call(&self, arguments: &Value) -> Result<String, ToolError> {
+        let terms: Vec<String> = string_argument(arguments, "query")?
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+        if terms.is_empty() {
+            return Err(ToolError::InvalidArguments("'query' is empty".to_string()));
+        }
+
+        let mut results = Vec::new();
+        self.search(&self.root, &terms, &mut results)?;
+        if results.is_empty() {
+            return Ok("No matches.".to_string());
+        }
+        Ok(results.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculator() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(evaluate("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(evaluate("2 ^ 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(evaluate("-4 + 10 % 4").unwrap(), -2.0);
+        assert_eq!(evaluate("7.5 / 2.5").unwrap(), 3.0);
+
+        assert!(matches!(evaluate("1 / 0"), Err(ToolError::Math(_))));
+        assert!(matches!(evaluate("2 +"), Err(ToolError::Math(_))));
+        assert!(matches!(evaluate("(2"), Err(ToolError::Math(_))));
+        assert!(matches!(evaluate("rm -rf /"), Err(ToolError::Math(_))));
+
+        // Deep nesting is refused instead of overflowing the stack
+        assert_eq!(evaluate(&format!("{}1", "-".repeat(64))).unwrap(), 1.0);
+        assert_eq!(
+            evaluate(&format!("{}1{}", "(".repeat(64), ")".repeat(64))).unwrap(),
+            1.0
+        );
+        for deep in [
+            format!("{}1", "-".repeat(100_000)),
+            format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000)),
+            format!("1{}", "^1".repeat(100_000)),
+        ] {
+            assert!(matches!(evaluate(&deep), Err(ToolError::Math(_))));
+        }
+    }
+
+    #[test]
+    fn test_read_file_stays_in_the_workspace() {
+        let parent = tempfile::tempdir().unwrap();
+        let workspace = parent.path().join("workspace");
+        std::fs::create_dir_all(workspace.join("notes")).unwrap();
+        std::fs::write(workspace.join("notes/case.md"), "Contention one").unwrap();
+        std::fs::write(parent.path().join("secret.txt"), "Keep out").unwrap();
+
+        let tool = ReadFileTool::new(&workspace).unwrap();
+        assert_eq!(
+            tool.call(&json!({ "path": "notes/case.md" })).unwrap(),
+            "Contention one"
+        );
+        assert!(matches!(
+            tool.call(&json!({ "path": "../secret.txt" })),
+            Err(ToolError::OutsideWorkspace(_))
+        ));
+        let absolute = parent.path().join("secret.txt");
+        assert!(matches!(
+            tool.call(&json!({ "path": absolute.to_str().unwrap() })),
+            Err(ToolError::OutsideWorkspace(_))
+        ));
+        assert!(matches!(
+            tool.call(&json!({ "file": "notes/case.md" })),
+            Err(ToolError::InvalidArguments(_))
+        ));
+        assert!(matches!(
+            tool.call(&json!({ "path": "notes" })),
+            Err(ToolError::InvalidArguments(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_file_reads_no_more_than_the_limit() {
+        let workspace = tempfile::tempdir().unwrap();
+        // A multi-byte character straddles the limit
+        let mut long = "a".repeat(MAX_FILE_BYTES - 1);
+        long.push('é');
+        long.push_str(&"b".repeat(MAX_FILE_BYTES));
+        std::fs::write(workspace.path().join("long.txt"), &long).unwrap();
+        std::fs::write(workspace.path().join("binary.bin"), [0xffu8; 16]).unwrap();
+
+        let tool = ReadFileTool::new(workspace.path()).unwrap();
+        let read = tool.call(&json!({ "path": "long.txt" })).unwrap();
+        assert_eq!(
+            read,
+            format!(
+                "{}\n[truncated after {} bytes]",
+                "a".repeat(MAX_FILE_BYTES - 1),
+                MAX_FILE_BYTES - 1
+            )
+        );
+        assert!(matches!(
+            tool.call(&json!({ "path": "binary.bin" })),
+            Err(ToolError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_search_corpus() {
+        let corpus = tempfile::tempdir().unwrap();
+        std::fs::create_dir(corpus.path().join("cards")).unwrap();
+        std::fs::write(
+            corpus.path().join("cards/privacy.txt"),
+            "Intro\nEncryption backdoors weaken PRIVACY for everyone.\n",
+        )
+        .unwrap();
+        std::fs::write(corpus.path().join("other.txt"), "Unrelated privacy note.\n").unwrap();
+
+        let tool = SearchTool::new(corpus.path()).unwrap();
+        assert_eq!(
+            tool.call(&json!({ "query": "privacy encryption" }))
+                .unwrap(),
+            "cards/privacy.txt:2: Encryption backdoors weaken PRIVACY for everyone."
+        );
+        assert_eq!(
+            tool.call(&json!({ "query": "quantum" })).unwrap(),
+            "No matches."
+        );
+    }
+
+    #[test]
+    fn test_registry_executes_and_reports_failures() {
+        let mut registry = ToolRegistry::new();
+        assert!(registry.is_empty());
+        registry.register(Box::new(CalculatorTool));
+
+        let definitions = registry.definitions();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].function.name, "calculator");
+        assert_eq!(
+            definitions[0].function.parameters["required"][0],
+            "expression"
+        );
+
+        let outcome = registry.execute("calculator", r#"{"expression": "6 * 7"}"#);
+        assert_eq!(outcome.output, "42");
+        assert!(!outcome.failed);
+
+        let outcome = registry.execute("calculator", "not json");
+        assert!(outcome.failed);
+        assert!(outcome.output.starts_with("Invalid arguments"));
+
+        let outcome = registry.execute("shell", "{}");
+        assert!(outcome.failed);
+        assert_eq!(outcome.output, "Unknown tool 'shell'");
+
+        let message = registry
+            .execute("calculator", r#"{"expression": "1 + 1"}"#)
+            .to_message("agent-1".to_string())
+            .unwrap();
+        match message.kind {
+            Some(crate::message::MessageKind::Structured(ref structured)) => {
+                assert_eq!(structured.schema, TOOL_RESULT_SCHEMA);
+                assert!(structured.json.contains(r#""output":"2""#));
+            }
+            other => panic!("Expected a structured message, got {:?}", other),
+        }
+    }
+}
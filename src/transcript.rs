@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+
+/// Heading that ends the preamble of a prompt and opens the conversation
+pub const CONVERSATION_HEADING: &str = "The conversation so far:";
+
+/// How a request to fold turns into the running summary begins
+pub const SUMMARY_INSTRUCTIONS: &str = "Summarize the following part of a multi-party conversation in a few sentences. \
+     Keep who argued what, the open questions and any decisions. \
+     Reply with the summary only.\n";
+
+/// Default number of recent turns sent to the LLM verbatim
+pub const DEFAULT_WINDOW: usize = 20;
+
+/// Default number of turns after which older turns are folded into a summary
+pub const DEFAULT_SUMMARIZE_AFTER: usize = 40;
+
+/// How much of the conversation is kept verbatim and when older turns are summarized
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TranscriptLimits {
+    /// Number of most recent turns kept verbatim
+    pub window: usize,
+    /// Number of turns that triggers summarizing everything older than the window;
+    /// 0 drops older turns without summarizing them
+    pub summarize_after: usize,
+}
+
+impl Default for TranscriptLimits {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_WINDOW,
+            summarize_after: DEFAULT_SUMMARIZE_AFTER,
+        }
+    }
+}
+
+/// One contribution to the conversation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Turn {
+    /// A message from another agent
+    Peer { sender_id: String, content: String },
+    /// A reply this agent sent
+    Own { content: String },
+}
+
+impl std::fmt::Display for Turn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Turn::Peer { sender_id, content } => write!(f, "[{}]: {}", sender_id, content),
+            Turn::Own { content } => write!(f, "[you]: {}", content),
+        }
+    }
+}
+
+/// Who speaks a prompt message, as the LLM sees it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PromptRole {
+    User,
+    Assistant,
+}
+
+/// A message of the multi-turn context sent to the LLM
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptMessage {
+    pub role: PromptRole,
+    pub content: String,
+}
+
+/// The conversation as this agent witnessed it, with attribution
+///
+/// Peer messages become user turns labelled with their sender and this agent's
+/// replies become assistant turns. Turns that fall out of the window are folded
+/// into a running summary so long debates keep their thread.
+pub struct Transcript {
+    limits: TranscriptLimits,
+    turns: VecDeque<Turn>,
+    /// Summary of the turns no longer kept verbatim
+    summary: Option<String>,
+}
+
+impl Transcript {
+    /// Create an empty transcript
+    pub fn new(limits: TranscriptLimits) -> Self {
+        Self {
+            limits,
+            turns: VecDeque::new(),
+            summary: None,
+        }
+    }
+
+    /// Record a message from another agent
+    pub fn record_peer(&mut self, sender_id: &str, content: &str) {
+        self.record(Turn::Peer {
+            sender_id: sender_id.to_string(),
+            content: content.to_string(),
+        });
+    }
+
+    /// Record a reply sent by this agent
+    pub fn record_own(&mut self, content: &str) {
+        self.record(Turn::Own {
+            content: content.to_string(),
+        });
+    }
+
+    fn record(&mut self, turn: Turn) {
+        self.turns.push_back(turn);
+
+        // Without summarization the window is a plain sliding window
+        if self.limits.summarize_after == 0 {
+            while self.turns.len() > self.limits.window {
+                self.turns.pop_front();
+            }
+        }
+    }
+
+    /// Remove the turns older than the window once the summarization threshold is reached
+    ///
+    /// The caller summarizes them and hands the result to `set_summary`.
+    pub fn take_overflow(&mut self) -> Option<Vec<Turn>> {
+        if self.limits.summarize_after == 0 || self.turns.len() <= self.limits.summarize_after {
+            return None;
+        }
+        let overflow = self.turns.len() - self.limits.window;
+        Some(self.turns.drain(..overflow).collect())
+    }
+
+    /// Instructions asking the LLM to fold `turns` into the running summary
+    pub fn summary_request(&self, turns: &[Turn]) -> String {
+        let mut request = String::from(SUMMARY_INSTRUCTIONS);
+        if let Some(ref summary) = self.summary {
+            request.push_str(&format!("\nSummary of what came before:\n{}\n", summary));
+        }
+        request.push_str("\nConversation:\n");
+        for turn in turns {
+            request.push_str(&format!("{}\n", turn));
+        }
+        request
+    }
+
+    /// Replace the running summary
+    pub fn set_summary(&mut self, summary: String) {
+        self.summary = Some(summary);
+    }
+
+    /// Build the multi-turn context, opening with `preamble` and the running summary
+    ///
+    /// Consecutive peer turns are merged into one user message and the context
+    /// always starts with a user message, as chat APIs expect strict alternation.
+    /// The conversation heading separates the preamble from the rest.
+    pub fn to_prompt(&self, preamble: &str) -> Vec<PromptMessage> {
+        let mut opening = format!("{}\n\n{}", preamble, CONVERSATION_HEADING);
+        if let Some(ref summary) = self.summary {
+            opening.push_str(&format!(
+                "\n\nSummary of the earlier conversation:\n{}",
+                summary
+            ));
+        }
+
+        let mut prompt = vec![PromptMessage {
+            role: PromptRole::User,
+            content: opening,
+        }];
+        for turn in &self.turns {
+            match turn {
+                Turn::Peer { .. } => {
+                    push_prompt_message(&mut prompt, PromptRole::User, &turn.to_string())
+                }
+                Turn::Own { content } => {
+                    push_prompt_message(&mut prompt, PromptRole::Assistant, content)
+                }
+            }
+        }
+        prompt
+    }
+}
+
+/// The opening message of a prompt without its preamble
+///
+/// The preamble describes who is present, which depends on when heartbeats
+/// arrived, so it is left out where runs are compared, e.g. cassette replay.
+/// Messages that are not the opening of a prompt are returned as they are.
+pub fn without_preamble(opening: &str) -> &str {
+    opening
+        .find(CONVERSATION_HEADING)
+        .map_or(opening, |start| &opening[start..])
+}
+
+/// Append a message to a prompt, merging it into the last one if both have the same role
+pub fn push_prompt_message(prompt: &mut Vec<PromptMessage>, role: PromptRole, content: &str) {
+    match prompt.last_mut() {
+        Some(last) if last.role == role => {
+            last.content.push_str("\n\n");
+            last.content.push_str(content);
+        }
+        _ => prompt.push(PromptMessage {
+            role,
+            content: content.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(window: usize, summarize_after: usize) -> TranscriptLimits {
+        TranscriptLimits {
+            window,
+            summarize_after,
+        }
+    }
+
+    #[test]
+    fn test_prompt_attributes_and_alternates_turns() {
+        let mut transcript = Transcript::new(TranscriptLimits::default());
+        transcript.record_own("Hi, I am agent-1.");
+        transcript.record_peer("agent-2", "Cats are better.");
+        transcript.record_peer("agent-3", "Dogs are better.");
+        transcript.record_own("Both have merits.");
+        transcript.record_peer("agent-2", "Pick one.");
+
+        let prompt = transcript.to_prompt("Participants: agent-2, agent-3.");
+        let roles: Vec<PromptRole> = prompt.iter().map(|message| message.role).collect();
+        assert_eq!(
+            roles,
+            vec![
+                PromptRole::User,
+                PromptRole::Assistant,
+                PromptRole::User,
+                PromptRole::Assistant,
+                PromptRole::User,
+            ]
+        );
+        assert_eq!(
+            prompt[0].content,
+            "Participants: agent-2, agent-3.\n\nThe conversation so far:"
+        );
+        assert_eq!(
+            prompt[2].content,
+            "[agent-2]: Cats are better.\n\n[agent-3]: Dogs are better."
+        );
+        assert_eq!(prompt[4].content, "[agent-2]: Pick one.");
+    }
+
+    #[test]
+    fn test_peer_turns_merge_into_the_opening_message() {
+        let mut transcript = Transcript::new(TranscriptLimits::default());
+        transcript.record_peer("agent-2", "Opening statement.");
+
+        let prompt = transcript.to_prompt("Participants: agent-2.");
+        assert_eq!(prompt.len(), 1);
+        assert_eq!(
+            prompt[0].content,
+            "Participants: agent-2.\n\nThe conversation so far:\n\n[agent-2]: Opening statement."
+        );
+
+        // Only the preamble differs between prompts with different participants
+        assert_eq!(
+            without_preamble(&prompt[0].content),
+            "The conversation so far:\n\n[agent-2]: Opening statement."
+        );
+        assert_eq!(
+            without_preamble(&transcript.to_prompt("Participants: agent-2, agent-3.")[0].content),
+            without_preamble(&prompt[0].content)
+        );
+        assert_eq!(without_preamble("Opening statement."), "Opening statement.");
+    }
+
+    #[test]
+    fn test_old_turns_are_summarized_past_the_threshold() {
+        let mut transcript = Transcript::new(limits(2, 4));
+        for i in 0..4 {
+            transcript.record_peer("agent-2", &format!("Point {}", i));
+        }
+        assert_eq!(transcript.take_overflow(), None);
+
+        transcript.record_own("Rebuttal");
+        let overflow = transcript.take_overflow().expect("Threshold was reached");
+        assert_eq!(overflow.len(), 3);
+        assert!(
+            transcript
+                .summary_request(&overflow)
+                .contains("[agent-2]: Point 0")
+        );
+
+        transcript.set_summary("agent-2 made three points.".to_string());
+        let prompt = transcript.to_prompt("Participants: agent-2.");
+        assert_eq!(
+            prompt[0].content,
+            "Participants: agent-2.\n\nThe conversation so far:\n\n\
+             Summary of the earlier conversation:\nagent-2 made three points.\n\n\
+             [agent-2]: Point 3"
+        );
+        assert_eq!(prompt[1].content, "Rebuttal");
+
+        // The previous summary is carried into the next request
+        let request = transcript.summary_request(&[]);
+        assert!(request.contains("agent-2 made three points."));
+    }
+
+    #[test]
+    fn test_window_slides_without_summarization() {
+        let mut transcript = Transcript::new(limits(2, 0));
+        for i in 0..5 {
+            transcript.record_peer("agent-2", &format!("Point {}", i));
+        }
+        assert_eq!(transcript.take_overflow(), None);
+
+        let prompt = transcript.to_prompt("Participants: agent-2.");
+        assert_eq!(
+            prompt[0].content,
+            "Participants: agent-2.\n\nThe conversation so far:\n\n[agent-2]: Point 3\n\n[agent-2]: Point 4"
+        );
+    }
+}
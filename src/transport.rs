@@ -0,0 +1,535 @@
+use crate::codec::MessageCodec;
+use crate::message::{AgentMessage, Datagram};
+use crate::network::{NetworkConfig, NetworkError};
+use crate::replay::ReplayCounters;
+use async_trait::async_trait;
+use prost::Message;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, broadcast};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Number of frames the in-process bus holds for a slow subscriber before it starts losing them
+pub const BUS_CAPACITY: usize = 1024;
+
+/// Which transport carries swarm traffic
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TransportKind {
+    /// UDP multicast on the local network
+    #[default]
+    #[value(name = "udp")]
+    Udp,
+    /// TCP connections to a hub that relays every message to all other agents
+    #[value(name = "tcp")]
+    Tcp,
+}
+
+/// A way of exchanging messages with the rest of the swarm
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Deliver a message to the other agents in the swarm
+    async fn send(&self, message: &AgentMessage) -> Result<(), NetworkError>;
+
+    /// Wait for the next message from the swarm
+    async fn recv(&self) -> Result<AgentMessage, NetworkError>;
+
+    /// Number of messages rejected so far because of a missing or bad swarm key signature
+    fn rejected_signature_count(&self) -> u64;
+
+    /// Counters of messages rejected as stale or replayed
+    fn replay_counters(&self) -> ReplayCounters;
+}
+
+/// Wrap an encoded frame in a single signed datagram for links that need no fragmentation
+fn wrap_frame(codec: &MessageCodec, agent_id: &str, payload: Vec<u8>) -> Vec<u8> {
+    let mut datagram = Datagram {
+        message_id: uuid::Uuid::new_v4().to_string(),
+        fragment_index: 0,
+        fragment_count: 1,
+        payload,
+        sender_id: agent_id.to_string(),
+        ..Default::default()
+    };
+    codec.sign_datagram(&mut datagram);
+    datagram.encode_to_vec()
+}
+
+/// Authenticate and decode a frame produced by `wrap_frame`
+fn unwrap_frame(
+    codec: &MessageCodec,
+    bytes: &[u8],
+    origin: &str,
+) -> Result<AgentMessage, NetworkError> {
+    let datagram = Datagram::decode(bytes).map_err(|e| {
+        warn!("Failed to decode frame from {}: {}", origin, e);
+        NetworkError::DeserializationError(e)
+    })?;
+    codec.verify_datagram(&datagram, origin)?;
+    codec.decode(&datagram.payload, origin)
+}
+
+/// A frame published on the bus, tagged with the port it came from
+#[derive(Clone)]
+struct BusFrame {
+    origin: u64,
+    bytes: Arc<Vec<u8>>,
+}
+
+/// In-process broadcast bus; every frame published on it reaches all other ports
+///
+/// Agents in the same process can talk over it directly, and the TCP hub
+/// attaches one port per connected agent.
+#[derive(Clone)]
+pub struct MessageBus {
+    sender: broadcast::Sender<BusFrame>,
+    next_port: Arc<AtomicU64>,
+}
+
+impl MessageBus {
+    /// Create a new bus holding up to `capacity` frames per subscriber
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            next_port: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Attach a new port to the bus
+    fn join(&self) -> (BusPublisher, BusSubscriber) {
+        let port = self.next_port.fetch_add(1, Ordering::Relaxed);
+        (
+            BusPublisher {
+                port,
+                sender: self.sender.clone(),
+            },
+            BusSubscriber {
+                port,
+                receiver: self.sender.subscribe(),
+            },
+        )
+    }
+}
+
+/// Sending half of a bus port
+struct BusPublisher {
+    port: u64,
+    sender: broadcast::Sender<BusFrame>,
+}
+
+impl BusPublisher {
+    fn publish(&self, bytes: Vec<u8>) {
+        // Sending only fails when nobody else is listening, which is not an error on a broadcast bus
+        let _ = self.sender.send(BusFrame {
+            origin: self.port,
+            bytes: Arc::new(bytes),
+        });
+    }
+}
+
+/// Receiving half of a bus port
+struct BusSubscriber {
+    port: u64,
+    receiver: broadcast::Receiver<BusFrame>,
+}
+
+impl BusSubscriber {
+    /// Wait for the next frame published by another port
+    async fn next(&mut self) -> Result<Arc<Vec<u8>>, NetworkError> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(frame) if frame.origin == self.port => continue,
+                Ok(frame) => return Ok(frame.bytes),
+                Err(broadcast::error::RecvError::Lagged(lost)) => {
+                    // Like a full socket buffer: the frames are gone, but the bus is still usable
+                    warn!("Message bus subscriber fell behind, {} frame(s) lost", lost);
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    return Err(NetworkError::ReceiveError("Message bus closed".to_string()));
+                }
+            }
+        }
+    }
+}
+
+/// Transport over an in-process `MessageBus`
+pub struct MemoryTransport {
+    agent_id: String,
+    codec: MessageCodec,
+    publisher: BusPublisher,
+    subscriber: Mutex<BusSubscriber>,
+}
+
+impl MemoryTransport {
+    /// Attach an agent to the bus
+    pub fn new(
+        bus: &MessageBus,
+        config: &NetworkConfig,
+        agent_id: String,
+    ) -> Result<Self, NetworkError> {
+        let codec = MessageCodec::new(config, &agent_id)?;
+        let (publisher, subscriber) = bus.join();
+        Ok(Self {
+            agent_id,
+            codec,
+            publisher,
+            subscriber: Mutex::new(subscriber),
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for MemoryTransport {
+    async fn send(&self, message: &AgentMessage) -> Result<(), NetworkError> {
+        let payload = self.codec.encode(message)?;
+        self.publisher
+            .publish(wrap_frame(&self.codec, &self.agent_id, payload));
+        Ok(())
+    }
+
+    async fn recv(&self) -> Result<AgentMessage, NetworkError> {
+        let bytes = self.subscriber.lock().await.next().await?;
+        unwrap_frame(&self.codec, &bytes, "message bus")
+    }
+
+    fn rejected_signature_count(&self) -> u64 {
+        self.codec.rejected_signature_count()
+    }
+
+    fn replay_counters(&self) -> ReplayCounters {
+        self.codec.replay_counters()
+    }
+}
+
+/// Read one length-prefixed frame from a TCP stream
+async fn read_frame(
+    reader: &mut OwnedReadHalf,
+    max_frame_size: usize,
+) -> Result<Vec<u8>, std::io::Error> {
+    let length = reader.read_u32().await? as usize;
+    if length > max_frame_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Frame of {} bytes exceeds the limit of {} bytes",
+                length, max_frame_size
+            ),
+        ));
+    }
+
+    let mut frame = vec![0u8; length];
+    reader.read_exact(&mut frame).await?;
+    Ok(frame)
+}
+
+/// Write one length-prefixed frame to a TCP stream
+async fn write_frame(writer: &mut OwnedWriteHalf, frame: &[u8]) -> Result<(), std::io::Error> {
+    let mut buffer = Vec::with_capacity(4 + frame.len());
+    buffer.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(frame);
+    writer.write_all(&buffer).await
+}
+
+/// Relays frames between TCP-connected agents, for networks where multicast is blocked
+pub struct TcpHub;
+
+impl TcpHub {
+    /// Accept agents on `listener` and relay every frame one of them sends to all other bus ports
+    pub fn spawn(listener: TcpListener, bus: MessageBus, max_frame_size: usize) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, peer_addr)) => {
+                        info!("Agent connected to hub from {}", peer_addr);
+                        tokio::spawn(Self::relay(stream, peer_addr, bus.clone(), max_frame_size));
+                    }
+                    Err(e) => warn!("Failed to accept hub connection: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Relay frames between one connection and the bus until the agent disconnects
+    async fn relay(
+        stream: TcpStream,
+        peer_addr: SocketAddr,
+        bus: MessageBus,
+        max_frame_size: usize,
+    ) {
+        let (mut reader, mut writer) = stream.into_split();
+        let (publisher, mut subscriber) = bus.join();
+
+        // Reads and writes run separately, since a read cancelled half way would corrupt the stream
+        let forward = tokio::spawn(async move {
+            while let Ok(frame) = subscriber.next().await {
+                if let Err(e) = write_frame(&mut writer, &frame).await {
+                    debug!("Failed to relay frame to {}: {}", peer_addr, e);
+                    break;
+                }
+            }
+        });
+
+        loop {
+            match read_frame(&mut reader, max_frame_size).await {
+                Ok(frame) => publisher.publish(frame),
+                Err(e) => {
+                    info!("Agent at {} left the hub: {}", peer_addr, e);
+                    break;
+                }
+            }
+        }
+        forward.abort();
+    }
+}
+
+/// Transport over a TCP connection to a `TcpHub`
+pub struct TcpTransport {
+    agent_id: String,
+    hub_addr: SocketAddr,
+    codec: MessageCodec,
+    max_frame_size: usize,
+    reader: Mutex<OwnedReadHalf>,
+    writer: Mutex<OwnedWriteHalf>,
+}
+
+impl TcpTransport {
+    /// Connect an agent to the hub at `hub_addr`
+    pub async fn connect(
+        hub_addr: SocketAddr,
+        config: &NetworkConfig,
+        agent_id: String,
+    ) -> Result<Self, NetworkError> {
+        let codec = MessageCodec::new(config, &agent_id)?;
+        let stream = TcpStream::connect(hub_addr).await.map_err(|e| {
+            NetworkError::ConfigError(format!("Failed to connect to hub {}: {}", hub_addr, e))
+        })?;
+        stream.set_nodelay(true)?;
+
+        let (reader, writer) = stream.into_split();
+        Ok(Self {
+            agent_id,
+            hub_addr,
+            codec,
+            max_frame_size: config.max_reassembly_bytes,
+            reader: Mutex::new(reader),
+            writer: Mutex::new(writer),
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for TcpTransport {
+    async fn send(&self, message: &AgentMessage) -> Result<(), NetworkError> {
+        let payload = self.codec.encode(message)?;
+        let frame = wrap_frame(&self.codec, &self.agent_id, payload);
+        if frame.len() > self.max_frame_size {
+            return Err(NetworkError::SendError(format!(
+                "Message of {} bytes exceeds the frame limit of {} bytes",
+                frame.len(),
+                self.max_frame_size
+            )));
+        }
+
+        write_frame(&mut *self.writer.lock().await, &frame)
+            .await
+            .map_err(|e| {
+                NetworkError::SendError(format!(
+                    "Failed to send message from agent {} to hub {}: {}",
+                    self.agent_id, self.hub_addr, e
+                ))
+            })
+    }
+
+    async fn recv(&self) -> Result<AgentMessage, NetworkError> {
+        let frame = read_frame(&mut *self.reader.lock().await, self.max_frame_size)
+            .await
+            .map_err(|e| {
+                NetworkError::ReceiveError(format!(
+                    "Lost connection to hub {} on agent {}: {}",
+                    self.hub_addr, self.agent_id, e
+                ))
+            })?;
+        unwrap_frame(&self.codec, &frame, &self.hub_addr.to_string())
+    }
+
+    fn rejected_signature_count(&self) -> u64 {
+        self.codec.rejected_signature_count()
+    }
+
+    fn replay_counters(&self) -> ReplayCounters {
+        self.codec.replay_counters()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::SwarmKey;
+    use crate::message::{Envelope, Frame, FrameBody, PROTOCOL_VERSION, SignedMessage, now_millis};
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    #[tokio::test]
+    async fn test_memory_transport_broadcasts_to_other_agents() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let alice = MemoryTransport::new(&bus, &config, "alice".to_string()).unwrap();
+        let bob = MemoryTransport::new(&bus, &config, "bob".to_string()).unwrap();
+        let carol = MemoryTransport::new(&bus, &config, "carol".to_string()).unwrap();
+
+        let message = AgentMessage::new("alice".to_string(), "Opening statement".to_string());
+        alice.send(&message).await.unwrap();
+
+        for peer in [&bob, &carol] {
+            let received = tokio::time::timeout(TIMEOUT, peer.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(received.message_id, message.message_id);
+            assert_eq!(received.content, "Opening statement");
+        }
+
+        // Senders do not hear their own messages
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), alice.recv())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_transport_enforces_swarm_key() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let keyed = NetworkConfig {
+            swarm_key: Some(SwarmKey::new(b"0123456789abcdef").unwrap()),
+            ..NetworkConfig::default()
+        };
+        let outsider =
+            MemoryTransport::new(&bus, &NetworkConfig::default(), "outsider".to_string()).unwrap();
+        let member = MemoryTransport::new(&bus, &keyed, "member".to_string()).unwrap();
+
+        let message = AgentMessage::new("outsider".to_string(), "Let me in".to_string());
+        outsider.send(&message).await.unwrap();
+
+        let result = tokio::time::timeout(TIMEOUT, member.recv()).await.unwrap();
+        assert!(matches!(result, Err(NetworkError::AuthenticationError(_))));
+        assert_eq!(member.rejected_signature_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_messages_are_tagged_with_the_sender_session() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let red = NetworkConfig {
+            session: "team-red".to_string(),
+            ..NetworkConfig::default()
+        };
+        let sender = MemoryTransport::new(&bus, &red, "red-1".to_string()).unwrap();
+        let listener =
+            MemoryTransport::new(&bus, &NetworkConfig::default(), "blue-1".to_string()).unwrap();
+
+        let message = AgentMessage::new("red-1".to_string(), "Red team only".to_string());
+        sender.send(&message).await.unwrap();
+
+        let received = tokio::time::timeout(TIMEOUT, listener.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.session, "team-red");
+    }
+
+    #[tokio::test]
+    async fn test_memory_transport_drops_unsupported_protocol_versions() {
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let current = MemoryTransport::new(&bus, &config, "current".to_string()).unwrap();
+        let future = MemoryTransport::new(&bus, &config, "future".to_string()).unwrap();
+
+        // An agent from a later release sends an envelope this one cannot read
+        let envelope = Envelope {
+            protocol_version: PROTOCOL_VERSION + 1,
+            sender_id: "future".to_string(),
+            message_id: uuid::Uuid::new_v4().to_string(),
+            timestamp: now_millis(),
+            ..Default::default()
+        };
+        let frame = Frame {
+            body: Some(FrameBody::Signed(SignedMessage {
+                message: envelope.encode_to_vec(),
+                ..Default::default()
+            })),
+        };
+        future
+            .publisher
+            .publish(wrap_frame(&future.codec, "future", frame.encode_to_vec()));
+
+        let result = tokio::time::timeout(TIMEOUT, current.recv()).await.unwrap();
+        assert!(matches!(result, Err(NetworkError::UnsupportedProtocol(_))));
+
+        // Later messages in a supported version still get through
+        let message = AgentMessage::new("future".to_string(), "Speaking your version".to_string());
+        future.send(&message).await.unwrap();
+        let received = tokio::time::timeout(TIMEOUT, current.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.message_id, message.message_id);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_hub_relays_between_agents() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hub_addr = listener.local_addr().unwrap();
+        let bus = MessageBus::new(BUS_CAPACITY);
+        let config = NetworkConfig::default();
+        let hub = TcpHub::spawn(listener, bus.clone(), config.max_reassembly_bytes);
+
+        // The agent hosting the hub joins through the bus, the others over TCP
+        let host = MemoryTransport::new(&bus, &config, "host".to_string()).unwrap();
+        let alice = TcpTransport::connect(hub_addr, &config, "alice".to_string())
+            .await
+            .unwrap();
+        let bob = TcpTransport::connect(hub_addr, &config, "bob".to_string())
+            .await
+            .unwrap();
+
+        // Give the hub a moment to attach both connections to the bus
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let large = "rebuttal ".repeat(20_000);
+        let message = AgentMessage::new("alice".to_string(), large.clone());
+        alice.send(&message).await.unwrap();
+
+        let received = tokio::time::timeout(TIMEOUT, bob.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.content, large);
+        let received = tokio::time::timeout(TIMEOUT, host.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.message_id, message.message_id);
+
+        let reply = AgentMessage::new_reply(
+            "host".to_string(),
+            "Noted".to_string(),
+            message.message_id.clone(),
+        );
+        host.send(&reply).await.unwrap();
+        for peer in [&alice, &bob] {
+            let received = tokio::time::timeout(TIMEOUT, peer.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(received.in_reply_to, Some(message.message_id.clone()));
+        }
+
+        hub.abort();
+    }
+}
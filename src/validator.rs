@@ -2,6 +2,7 @@ use tracing::{error, info, warn};
 
 use crate::cli::{AgentArgs, LLMBackend};
 use crate::llm::LLMModule;
+use crate::retry::ErrorClass;
 use llm::chat::ChatMessage;
 
 /// Errors that can occur during LLM access validation.
@@ -55,6 +56,18 @@ pub fn validate_llm_access(args: &AgentArgs) -> Result<(), ValidationError> {
         return Ok(());
     }
 
+    // The mock backend answers from a script and never calls a provider.
+    if matches!(args.llm_backend, LLMBackend::Mock) {
+        info!("Mock backend selected — skipping API key validation");
+        return Ok(());
+    }
+
+    // A replayed cassette answers instead of the provider.
+    if args.replay.is_some() {
+        info!("Replaying a cassette — skipping API key validation");
+        return Ok(());
+    }
+
     let backend_name = args.llm_backend.to_string();
 
     // 1. Ensure a key was provided at all.
@@ -90,7 +103,7 @@ pub fn validate_llm_access(args: &AgentArgs) -> Result<(), ValidationError> {
         LLMBackend::Anthropic => MIN_KEY_LENGTH_ANTHROPIC,
         LLMBackend::Google => MIN_KEY_LENGTH_GOOGLE,
         LLMBackend::OpenRouter => MIN_KEY_LENGTH_OPENROUTER,
-        LLMBackend::Local => unreachable!(), // handled above
+        LLMBackend::Local | LLMBackend::Mock => unreachable!(), // handled above
     };
 
     if api_key.len() < min_length {
@@ -121,8 +134,9 @@ pub fn validate_llm_access(args: &AgentArgs) -> Result<(), ValidationError> {
 /// This should be called **after** [`validate_llm_access`] (format checks) and
 /// **before** the application starts its main processing loop.
 ///
-/// For **local** (Ollama) backends, the probe is skipped since there is no
-/// remote authentication to verify.
+/// For **local** (Ollama) and **mock** backends, and when replaying a
+/// cassette, the probe is skipped since there is no remote authentication to
+/// verify.
 ///
 /// # Errors
 ///
@@ -134,6 +148,16 @@ pub async fn validate_llm_connection(args: &AgentArgs) -> Result<(), ValidationE
         return Ok(());
     }
 
+    if matches!(args.llm_backend, LLMBackend::Mock) {
+        info!("Mock backend selected — skipping connection probe");
+        return Ok(());
+    }
+
+    if args.replay.is_some() {
+        info!("Replaying a cassette — skipping connection probe");
+        return Ok(());
+    }
+
     let backend_name = args.llm_backend.to_string();
     info!(
         "Probing '{}' backend to verify API key is accepted…",
@@ -163,19 +187,9 @@ pub async fn validate_llm_connection(args: &AgentArgs) -> Result<(), ValidationE
         }
         Err(e) => {
             let reason = format!("{e}");
-            let lower = reason.to_lowercase();
-            // Distinguish auth errors from transient failures for clearer messaging.
-            // NOTE: We intentionally do NOT match on "invalid" alone — it is far
-            // too broad and catches non-auth errors like "invalid model" from
-            // OpenAI-compatible providers (e.g. OpenRouter).
-            if lower.contains("unauthorized")
-                || lower.contains("401")
-                || lower.contains("invalid api key")
-                || lower.contains("invalid_api_key")
-                || lower.contains("authentication")
-                || lower.contains("permission")
-                || lower.contains("403")
-            {
+            // Distinguish auth errors from transient failures for clearer messaging,
+            // classifying the error the same way the retry path does.
+            if ErrorClass::of(&e) == ErrorClass::Fatal {
                 error!(
                     "API key rejected by '{}' provider: {}",
                     backend_name, reason
@@ -197,6 +211,10 @@ pub async fn validate_llm_connection(args: &AgentArgs) -> Result<(), ValidationE
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cli::RunMode;
+    use crate::policy::ResponseMode;
+    use crate::reliability::DeliveryMode;
+    use crate::transport::TransportKind;
     use std::net::SocketAddr;
 
     /// Helper to build a minimal [`AgentArgs`] for testing.
@@ -204,18 +222,55 @@ mod tests {
         AgentArgs {
             agent_id: "test-agent".to_string(),
             multicast_address: "239.255.255.250:8080".parse::<SocketAddr>().unwrap(),
-            interface: None,
+            interfaces: Vec::new(),
+            multicast_hops: 1,
             llm_backend: backend,
             model: "gpt-3.5-turbo".to_string(),
             api_key,
             endpoint: None,
             timeout_seconds: 30,
             max_retries: 3,
+            retry_base_delay_ms: 500,
+            retry_max_delay_seconds: 30,
+            no_retry_jitter: false,
+            error_reply: crate::health::ErrorReply::Silent,
+            degraded_after: 3,
+            degraded_cooldown_seconds: 60,
             log_level: "info".to_string(),
             personality: "You are a helpful AI agent.".to_string(),
             personality_file: None,
             processing_delay_ms: 5000,
             voice: false,
+            swarm_key_file: None,
+            identity_dir: None,
+            swarm_passphrase_file: None,
+            replay_window_seconds: 30,
+            delivery_mode: DeliveryMode::BestEffort,
+            transport: TransportKind::Udp,
+            hub_address: None,
+            serve_hub: false,
+            role: "participant".to_string(),
+            heartbeat_interval_seconds: 5,
+            session: None,
+            session_address: false,
+            transcript_window: 20,
+            summarize_after: 40,
+            respond: ResponseMode::Always,
+            triggers: Vec::new(),
+            response_probability: 0.5,
+            floor_control: false,
+            floor_timeout_seconds: 30,
+            mode: RunMode::Agent,
+            words_per_minute: 150,
+            speech_timeout_seconds: 300,
+            ballot_file: std::path::PathBuf::from("ballot.json"),
+            tools: Vec::new(),
+            workspace: None,
+            corpus: None,
+            broadcast_tool_results: false,
+            script: None,
+            record: None,
+            replay: None,
         }
     }
 
@@ -225,6 +280,21 @@ mod tests {
         assert!(validate_llm_access(&args).is_ok());
     }
 
+    #[tokio::test]
+    async fn mock_backend_skips_validation_and_probe() {
+        let args = make_args(LLMBackend::Mock, None);
+        assert!(validate_llm_access(&args).is_ok());
+        assert!(validate_llm_connection(&args).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn replay_skips_validation_and_probe() {
+        let mut args = make_args(LLMBackend::OpenAI, None);
+        args.replay = Some(std::path::PathBuf::from("debate.json"));
+        assert!(validate_llm_access(&args).is_ok());
+        assert!(validate_llm_connection(&args).await.is_ok());
+    }
+
     #[test]
     fn missing_api_key_is_rejected() {
         let args = make_args(LLMBackend::OpenAI, None);